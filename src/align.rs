@@ -0,0 +1,207 @@
+//! Aligning the two columns of a pair
+//!
+//! A pair holds the ids in its first column and the names in its second,
+//! one per line, so the two are normally zipped line by line. When the
+//! names did not reassemble into as many chunks as there are ids, say a
+//! short name such as "兽医" without a known ending ran into the next, the
+//! zip would hand every later id the name of its neighbour. Instead each id
+//! of the first column is taken as an anchor and the printed lines of the
+//! second column are cut into as many names. The blank lines after an id
+//! leave room for a name wrapped over several lines, so a cut is best where
+//! the name spans as many lines as its id, at a blank line, and after a
+//! line closing with a name ending or too short to have been wrapped,
+//! rather than after a line filling the column. Lines of either column
+//! left over are handed back to be reported.
+
+use crate::{Line, ParseOptions, clean_lines};
+use regex::Regex;
+
+/// Lines of a pair joined side by side, and those that have no partner
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct Alignment {
+    /// An id and its name on each line
+    pub lines: Vec<String>,
+    /// Lines of the first column without a name, or before the first id
+    pub leftover_first: Vec<String>,
+    /// Lines of the second column without an id
+    pub leftover_second: Vec<String>,
+}
+
+/// A non-blank line and the 0-based line of the cell it is on
+type Placed = (usize, String);
+
+/// Pair each id of `first_text`, a line matching `id_line`, with a name cut
+/// from the lines of `second_text`
+pub(crate) fn align_pair(
+    first_text: &str,
+    second_text: &str,
+    id_line: &Regex,
+    options: &ParseOptions,
+) -> Alignment {
+    let mut alignment = Alignment::default();
+    let first = clean_lines(first_text, options);
+    let second = clean_lines(second_text, options);
+    // letters spread to fill the column, as in "文 献 信 息", before
+    // cleaning closes them up
+    let spread: Vec<bool> = second_text
+        .lines()
+        .map(|line| line.split_whitespace().count() > 2)
+        .collect();
+
+    // lines not starting with an id, e.g. a code wrapped onto its own line,
+    // belong to the id before them
+    let mut anchors: Vec<Placed> = Vec::new();
+    for (at, line) in first.iter().enumerate() {
+        match anchors.last_mut() {
+            _ if line.text.is_empty() && line.marker.is_empty() => {}
+            _ if id_line.is_match(&line.text) => anchors.push((at, line.joined())),
+            Some((_, anchor)) => anchor.push_str(&line.joined()),
+            None => alignment.leftover_first.push(line.joined()),
+        }
+    }
+    // a marker alone on its line belongs to the line before
+    let mut names: Vec<(usize, &Line)> = Vec::new();
+    let mut markers = vec![String::new(); second.len()];
+    for (at, line) in second.iter().enumerate() {
+        match names.last() {
+            _ if !line.text.is_empty() => names.push((at, line)),
+            Some(&(last, _)) if !line.marker.is_empty() => markers[last].push_str(&line.marker),
+            _ => {}
+        }
+    }
+    let name_text = |(at, line): (usize, &Line)| format!("{}{}", line.joined(), markers[at]);
+
+    if anchors.len() > names.len() {
+        let unnamed = anchors.drain(names.len()..).map(|(_, anchor)| anchor);
+        alignment.leftover_first.extend(unnamed);
+    }
+    if anchors.is_empty() {
+        alignment
+            .leftover_second
+            .extend(names.into_iter().map(name_text));
+        return alignment;
+    }
+
+    let starts = cut_names(&anchors, first.len(), &names, &spread, options);
+    for (i, (_, anchor)) in anchors.iter().enumerate() {
+        let end = starts.get(i + 1).copied().unwrap_or(names.len());
+        let name = names[starts[i]..end]
+            .iter()
+            .map(|&name| name_text(name))
+            .collect::<String>();
+        alignment.lines.push(format!("{} {}", anchor, name));
+    }
+    alignment
+}
+
+/// Index in `names` of the first line of the name of each anchor, the
+/// first being 0, maximizing the score of the names
+fn cut_names(
+    anchors: &[Placed],
+    first_lines: usize,
+    names: &[(usize, &Line)],
+    spread: &[bool],
+    options: &ParseOptions,
+) -> Vec<usize> {
+    let (n, k) = (anchors.len(), names.len());
+    // spread-out lines like "文 献 信 息" count by their letters
+    let width = |line: &str| line.chars().filter(|c| !c.is_whitespace()).count();
+    let longest = names
+        .iter()
+        .map(|(_, line)| width(&line.text))
+        .max()
+        .unwrap_or(0);
+    // lines of the cell taken by anchor i, and by a name from line j to end
+    let anchor_span =
+        |i: usize| anchors.get(i + 1).map_or(first_lines, |&(at, _)| at) - anchors[i].0;
+    let name_span =
+        |j: usize, end: usize| names.get(end).map_or(spread.len(), |&(at, _)| at) - names[j].0;
+    // a line closing a name as `SuffixMerger` sees it
+    let closes = |line: &str| {
+        line.ends_with('工')
+            || options
+                .suffixes
+                .iter()
+                .any(|suffix| line.ends_with(suffix.as_str()))
+    };
+    // the name of anchor i on the lines from j to end
+    let score = |i: usize, j: usize, end: usize| {
+        // the cell ends the last anchor, whose name may run on further
+        let fits = if i + 1 < n {
+            anchor_span(i) == name_span(j, end)
+        } else {
+            anchor_span(i) <= name_span(j, end)
+        };
+        let mut score = 2 * i32::from(fits);
+        if end < k {
+            let (at, last) = names[end - 1];
+            let last = last.text.as_str();
+            score += 2 * i32::from(names[end].0 > names[end - 1].0 + 1);
+            score += if closes(last) {
+                2
+            } else if spread[at] {
+                -4
+            } else if width(last) < longest {
+                1
+            } else {
+                // likely the first part of a wrapped name
+                -2
+            };
+        }
+        score
+    };
+
+    // best[i][j]: score of the first i names ending before line j, with
+    // the start of the last
+    let mut best = vec![vec![None::<(i32, usize)>; k + 1]; n + 1];
+    best[0][0] = Some((0, 0));
+    for i in 0..n {
+        // leave at least one line for each anchor after i
+        for end in i + 1..=k - (n - i - 1) {
+            best[i + 1][end] = (i..end)
+                .filter_map(|j| best[i][j].map(|(total, _)| (total + score(i, j, end), j)))
+                .max_by_key(|&(total, j)| (total, std::cmp::Reverse(j)));
+        }
+    }
+    let mut starts = vec![0; n];
+    let mut end = k;
+    for i in (0..n).rev() {
+        let (_, start) = best[i + 1][end].expect("a line for each anchor");
+        starts[i] = start;
+        end = start;
+    }
+    starts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_align_pair() {
+        let options = ParseOptions::default();
+        let id_line = options.id_format.line_regex().unwrap();
+        let ids = "4-06-01-01\n4-06-01-02\n\n4-06-01-03\n4-06-01-04\n\n\n\
+                   4-06-02-01\n4-06-02-02\n4-06-02-03";
+        let names = "物业管理师\n中央空调系统运\n行操作员\n停车管理员\n智 能 楼 宇 管 理\n员 S\n\n\
+                     房地产经纪人\n房地产策划师\n验房师";
+        let alignment = align_pair(ids, names, &id_line, &options);
+        assert_eq!(
+            alignment.lines,
+            [
+                "4-06-01-01 物业管理师",
+                "4-06-01-02 中央空调系统运行操作员",
+                "4-06-01-03 停车管理员",
+                "4-06-01-04 智能楼宇管理员S",
+                "4-06-02-01 房地产经纪人",
+                "4-06-02-02 房地产策划师",
+                "4-06-02-03 验房师",
+            ]
+        );
+        assert!(alignment.leftover_first.is_empty());
+
+        let alignment = align_pair("", "续表", &id_line, &options);
+        assert!(alignment.lines.is_empty());
+        assert_eq!(alignment.leftover_second, ["续表"]);
+    }
+}
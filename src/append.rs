@@ -0,0 +1,117 @@
+use crate::diagnostics::{Diagnostics, LogWarnings};
+use crate::report::check_mode;
+use crate::rows::parse_rows_with;
+use crate::{CategoryTree, KimiError, ParseOptions, ParseReport, RowCells, row_categories};
+
+impl CategoryTree {
+    /// Add the categories of another workbook, e.g. the next volume of a
+    /// scan split over several files
+    ///
+    /// Categories whose id is already in the tree are skipped, so a heading
+    /// repeated at the top of each volume is kept once.
+    pub fn append_from(&mut self, input_file: &str) -> Result<(), KimiError> {
+        self.append_from_with(input_file, &ParseOptions::default())
+    }
+
+    /// Like `append_from`, reading the workbook as given by `options`; with
+    /// `options.validate` the whole tree is validated afterwards
+    pub fn append_from_with(
+        &mut self,
+        input_file: &str,
+        options: &ParseOptions,
+    ) -> Result<(), KimiError> {
+        let mut report = ParseReport::default();
+        parse_rows_with(input_file, options, |row| {
+            self.append_row(row, options, &mut report, &mut LogWarnings)
+        })?;
+        check_mode(options, &report)?;
+        self.check_valid(options)
+    }
+
+    /// Add the categories of rows read by `parse_rows`, skipping ids already
+    /// in the tree
+    pub fn append_rows<I>(&mut self, rows: I) -> Result<(), KimiError>
+    where
+        I: IntoIterator<Item = RowCells>,
+    {
+        self.append_rows_with(rows, &ParseOptions::default())
+    }
+
+    /// Like `append_rows`, reassembling lines with the heuristics of
+    /// `options`
+    pub fn append_rows_with<I>(&mut self, rows: I, options: &ParseOptions) -> Result<(), KimiError>
+    where
+        I: IntoIterator<Item = RowCells>,
+    {
+        let mut report = ParseReport::default();
+        rows.into_iter()
+            .try_for_each(|row| self.append_row(row, options, &mut report, &mut LogWarnings))?;
+        check_mode(options, &report)
+    }
+
+    pub(crate) fn append_row(
+        &mut self,
+        row: RowCells,
+        options: &ParseOptions,
+        report: &mut ParseReport,
+        diagnostics: &mut dyn Diagnostics,
+    ) -> Result<(), KimiError> {
+        for cat in row_categories(row, options, report, diagnostics)? {
+            if self.get(&cat.id).is_none() {
+                self.insert(cat.id.clone(), cat);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CellText;
+
+    fn row(row: u32, text: &str) -> RowCells {
+        RowCells {
+            sheet: "Sheet1".to_string(),
+            row,
+            cells: vec![CellText::Column {
+                col: 1,
+                text: text.to_string(),
+                confidence: Default::default(),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_append_rows() {
+        let mut tree = CategoryTree::new();
+        tree.append_rows([
+            row(1, "2-04(GBM20400)交通运输工程技术人员"),
+            row(2, "2-04-02(GBM20402)船舶指挥和引航人员"),
+        ])
+        .unwrap();
+        // the second volume repeats the heading it continues
+        tree.append_rows([
+            row(1, "2-04-02(GBM20402)船舶指挥和引航人员"),
+            row(2, "2-04-02-01船舶驾驶员"),
+        ])
+        .unwrap();
+        assert_eq!(tree.iter().count(), 3);
+        assert!(tree.get("2-04-02-01").is_some());
+
+        // an id with an empty segment is skipped, unless in strict mode
+        let malformed = || [row(7, "2-04-(GBM20400)交通运输工程技术人员")];
+        tree.append_rows(malformed()).unwrap();
+        assert_eq!(tree.iter().count(), 3);
+        let options = ParseOptions {
+            mode: crate::ParseMode::Strict,
+            ..ParseOptions::default()
+        };
+        let err = tree.append_rows_with(malformed(), &options).unwrap_err();
+        assert!(matches!(
+            err,
+            KimiError::CellParse { row: 7, col: 1, source }
+                if matches!(*source, KimiError::MalformedId(ref id) if id == "2-04-")
+        ));
+    }
+}
@@ -0,0 +1,153 @@
+//! Zip archives of workbooks
+//!
+//! The 大典 is commonly distributed as a zip of one workbook per 大类.
+//! Every entry with a readable extension is parsed in name order, with runs
+//! of digits compared as numbers, and merged into one tree like
+//! `append_from`, so a heading repeated at the top of each volume is kept
+//! once. Other entries, such as a readme, are skipped.
+
+use crate::diagnostics::{Diagnostics, LogWarnings};
+use crate::report::check_mode;
+use crate::source::read_source;
+use crate::{CategoryTree, KimiError, ParseOptions, ParseReport, parse_rows_from};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek};
+use zip::ZipArchive;
+
+impl CategoryTree {
+    /// Parse the workbooks of a zip archive into this tree
+    pub fn build_from_zip(
+        &mut self,
+        input_file: &str,
+        options: &ParseOptions,
+    ) -> Result<ParseReport, KimiError> {
+        self.zip_entries(
+            BufReader::new(File::open(input_file)?),
+            options,
+            &mut LogWarnings,
+        )
+    }
+
+    /// Like `build_from_zip`, reading the archive bytes from `reader`
+    pub fn build_from_zip_reader<R: Read + Seek>(
+        &mut self,
+        reader: R,
+        options: &ParseOptions,
+    ) -> Result<ParseReport, KimiError> {
+        self.zip_entries(reader, options, &mut LogWarnings)
+    }
+
+    pub(crate) fn zip_entries<R: Read + Seek>(
+        &mut self,
+        reader: R,
+        options: &ParseOptions,
+        diagnostics: &mut dyn Diagnostics,
+    ) -> Result<ParseReport, KimiError> {
+        let mut archive = ZipArchive::new(reader)?;
+        let mut report = ParseReport::default();
+        let mut names = archive
+            .file_names()
+            .filter(|name| !name.ends_with('/') && !name.starts_with("__MACOSX/"))
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        names.sort_by_cached_key(|name| natural_key(name));
+        for name in names {
+            let mut bytes = Vec::new();
+            archive.by_name(&name)?.read_to_end(&mut bytes)?;
+            let in_entry = |source| KimiError::ArchiveEntry {
+                entry: name.clone(),
+                source: Box::new(source),
+            };
+            let Some(mut source) = read_source(&name, bytes, options.backend).map_err(in_entry)?
+            else {
+                continue;
+            };
+            parse_rows_from(&mut *source, options, |row| {
+                self.append_row(row, options, &mut report, diagnostics)
+            })
+            .map_err(in_entry)?;
+        }
+        check_mode(options, &report)?;
+        self.check_valid(options)?;
+        Ok(report)
+    }
+}
+
+/// Chunks of `name` with runs of digits as numbers, so that "第2卷" sorts
+/// before "第10卷"
+fn natural_key(name: &str) -> Vec<Result<u64, String>> {
+    let mut key = Vec::new();
+    let mut rest = name;
+    while let Some(c) = rest.chars().next() {
+        let digit = c.is_ascii_digit();
+        let end = rest
+            .find(|c: char| c.is_ascii_digit() != digit)
+            .unwrap_or(rest.len());
+        let (chunk, tail) = rest.split_at(end);
+        key.push(match chunk.parse() {
+            Ok(n) if digit => Ok(n),
+            _ => Err(chunk.to_string()),
+        });
+        rest = tail;
+    }
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+    use zip::write::{SimpleFileOptions, ZipWriter};
+
+    fn workbook(cells: &[&str]) -> Vec<u8> {
+        let mut book = umya_spreadsheet::new_file();
+        let sheet = book.get_sheet_mut(&0).unwrap();
+        for (row, text) in (1..).zip(cells) {
+            sheet.get_cell_mut((1, row)).set_value(*text);
+        }
+        let mut bytes = Vec::new();
+        umya_spreadsheet::writer::xlsx::write_writer(&book, &mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_build_from_zip() {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let entries = [
+            (
+                "大典/第10卷.xlsx",
+                workbook(&[
+                    "2-04-02(GBM20402)船舶指挥和引航人员",
+                    "2-04-02-02船舶引航员",
+                ]),
+            ),
+            (
+                "大典/第2卷.xlsx",
+                workbook(&[
+                    "2-04(GBM20400)交通运输工程技术人员",
+                    "2-04-02(GBM20402)船舶指挥和引航人员",
+                ]),
+            ),
+            ("大典/说明.txt", b"readme".to_vec()),
+        ];
+        for (name, bytes) in entries {
+            zip.start_file(name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(&bytes).unwrap();
+        }
+        let bytes = zip.finish().unwrap().into_inner();
+
+        let options = ParseOptions {
+            layout: Some(crate::LayoutProfile::default()),
+            ..ParseOptions::default()
+        };
+        let mut tree = CategoryTree::new();
+        tree.build_from_zip_reader(Cursor::new(bytes), &options)
+            .unwrap();
+        let ids = tree
+            .iter()
+            .map(|(_, cat)| cat.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["2-04", "2-04-02", "2-04-02-02"]);
+        assert!(natural_key("第2卷") < natural_key("第10卷"));
+    }
+}
@@ -0,0 +1,277 @@
+//! Terminal tree browser (cargo feature `browse`)
+
+use crate::{Category, CategoryTree, KimiError};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::HashSet;
+
+/// One visible line of the tree pane
+#[derive(Debug)]
+pub struct Row<'a> {
+    pub id: String,
+    pub depth: usize,
+    pub has_children: bool,
+    pub category: Option<&'a Category>,
+}
+
+/// State of the browser: expanded nodes, selection and search query
+pub struct Browser<'a> {
+    tree: &'a CategoryTree,
+    expanded: HashSet<String>,
+    selected: usize,
+    query: String,
+    searching: bool,
+}
+
+impl<'a> Browser<'a> {
+    pub fn new(tree: &'a CategoryTree) -> Self {
+        Browser {
+            tree,
+            expanded: HashSet::new(),
+            selected: 0,
+            query: String::new(),
+            searching: false,
+        }
+    }
+
+    /// Rows currently shown; while a query is set, only the paths to
+    /// matching categories are shown, fully expanded
+    pub fn rows(&self) -> Vec<Row<'a>> {
+        let mut rows = Vec::new();
+        self.collect_rows(self.tree, "", 0, &mut rows);
+        rows
+    }
+
+    fn collect_rows(
+        &self,
+        node: &'a CategoryTree,
+        prefix: &str,
+        depth: usize,
+        rows: &mut Vec<Row<'a>>,
+    ) {
+        for (key, child) in node.children() {
+            let id = if prefix.is_empty() {
+                key.to_string()
+            } else {
+                format!("{}-{}", prefix, key)
+            };
+            if !self.query.is_empty() && !child.iter().any(|(_, cat)| self.matches(cat)) {
+                continue;
+            }
+            let open = !self.query.is_empty() || self.expanded.contains(&id);
+            rows.push(Row {
+                id: id.clone(),
+                depth,
+                has_children: child.children().next().is_some(),
+                category: child.items().first(),
+            });
+            if open {
+                self.collect_rows(child, &id, depth + 1, rows);
+            }
+        }
+    }
+
+    fn matches(&self, cat: &Category) -> bool {
+        cat.id.starts_with(&self.query)
+            || cat.desc.as_deref().is_some_and(|d| d.contains(&self.query))
+            || cat.code.as_deref().is_some_and(|c| c.contains(&self.query))
+    }
+
+    /// Index of the selected row
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Apply a key press; returns false once the user asked to quit
+    pub fn handle_key(&mut self, key: KeyCode) -> bool {
+        if self.searching {
+            match key {
+                KeyCode::Char(c) => self.query.push(c),
+                KeyCode::Backspace => {
+                    self.query.pop();
+                }
+                KeyCode::Esc => {
+                    self.query.clear();
+                    self.searching = false;
+                }
+                KeyCode::Enter => self.searching = false,
+                _ => {}
+            }
+            self.selected = 0;
+            return true;
+        }
+        let rows = self.rows();
+        let Some(row) = rows.get(self.selected) else {
+            return !matches!(key, KeyCode::Char('q') | KeyCode::Esc);
+        };
+        match key {
+            KeyCode::Char('q') => return false,
+            KeyCode::Esc if self.query.is_empty() => return false,
+            KeyCode::Esc => {
+                self.query.clear();
+                self.selected = 0;
+            }
+            KeyCode::Char('/') => self.searching = true,
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(rows.len() - 1)
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Enter if row.has_children => {
+                self.expanded.insert(row.id.clone());
+            }
+            KeyCode::Left | KeyCode::Char('h') if !self.expanded.remove(&row.id) => {
+                // Already collapsed: jump to the parent row
+                let parent = row.id.rsplit_once('-').map(|(parent, _)| parent);
+                if let Some(i) = rows.iter().position(|r| Some(r.id.as_str()) == parent) {
+                    self.selected = i;
+                }
+            }
+            _ => {}
+        }
+        true
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [main, status] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+        let [tree_area, detail_area] =
+            Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)])
+                .areas(main);
+
+        let rows = self.rows();
+        let items = rows.iter().map(|row| {
+            let marker = if !row.has_children {
+                " "
+            } else if !self.query.is_empty() || self.expanded.contains(&row.id) {
+                "▾"
+            } else {
+                "▸"
+            };
+            let name = row.category.and_then(|c| c.desc.as_deref()).unwrap_or("");
+            ListItem::new(format!(
+                "{}{} {} {}",
+                "  ".repeat(row.depth),
+                marker,
+                row.id,
+                name
+            ))
+        });
+        let list = List::new(items)
+            .block(Block::bordered().title("职业分类"))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        let mut state = ListState::default().with_selected(Some(self.selected));
+        frame.render_stateful_widget(list, tree_area, &mut state);
+
+        let detail = rows
+            .get(self.selected)
+            .map(|row| self.detail(row))
+            .unwrap_or_default();
+        frame.render_widget(
+            Paragraph::new(detail)
+                .block(Block::bordered().title("详情"))
+                .wrap(Wrap { trim: false }),
+            detail_area,
+        );
+
+        let status_line = if self.searching {
+            format!("/{}", self.query)
+        } else if !self.query.is_empty() {
+            format!("filter: {}  (Esc clears)", self.query)
+        } else {
+            "↑↓ move  ←→ collapse/expand  / search  q quit".to_string()
+        };
+        frame.render_widget(Paragraph::new(status_line), status);
+    }
+
+    fn detail(&self, row: &Row) -> Vec<Line<'static>> {
+        let mut lines = vec![Line::from(format!("id: {}", row.id))];
+        let Some(cat) = row.category else {
+            lines.push(Line::from("(no category at this node)"));
+            return lines;
+        };
+        if let Some(level) = cat.level() {
+            lines.push(Line::from(format!("level: {}", level)));
+        }
+        lines.push(Line::from(format!(
+            "desc: {}",
+            cat.desc.as_deref().unwrap_or("-")
+        )));
+        lines.push(Line::from(format!(
+            "code: {}",
+            cat.code.as_deref().unwrap_or("-")
+        )));
+        if let Some(marker) = cat.marker() {
+            lines.push(Line::from(format!("marker: {}", marker)));
+        }
+        let ancestors = self.tree.ancestors(&cat.id);
+        if !ancestors.is_empty() {
+            lines.push(Line::from(""));
+            lines.push(Line::from("path:"));
+            for ancestor in ancestors {
+                lines.push(Line::from(format!("  {}", ancestor)));
+            }
+        }
+        lines
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<(), KimiError> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+                && !self.handle_key(key.code)
+            {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Browse the tree in the terminal until the user quits
+pub fn browse(tree: &CategoryTree) -> Result<(), KimiError> {
+    let mut terminal = ratatui::init();
+    let result = Browser::new(tree).run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(browser: &Browser) -> Vec<String> {
+        browser.rows().into_iter().map(|row| row.id).collect()
+    }
+
+    #[test]
+    fn test_browser() {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column(
+            "2-05(GBM20500)卫生专业技术人员\n2-05-08(GBM20508)护理人员\n2-05-08-01内科护士\n2-05-08-02儿科护士\n2-06(GBM20600)经济和金融专业人员",
+        )
+        .unwrap();
+        let mut browser = Browser::new(&tree);
+        assert_eq!(ids(&browser), ["2"]);
+        browser.handle_key(KeyCode::Right);
+        assert_eq!(ids(&browser), ["2", "2-05", "2-06"]);
+        browser.handle_key(KeyCode::Down);
+        browser.handle_key(KeyCode::Enter);
+        assert_eq!(ids(&browser).len(), 4);
+        browser.handle_key(KeyCode::Left);
+        browser.handle_key(KeyCode::Left);
+        assert_eq!(browser.selected(), 0);
+
+        for key in "/儿科".chars().map(KeyCode::Char) {
+            browser.handle_key(key);
+        }
+        assert_eq!(ids(&browser), ["2", "2-05", "2-05-08", "2-05-08-02"]);
+        browser.handle_key(KeyCode::Enter);
+        browser.handle_key(KeyCode::Esc);
+        assert_eq!(ids(&browser), ["2", "2-05", "2-06"]);
+        assert!(!browser.handle_key(KeyCode::Char('q')));
+    }
+}
@@ -0,0 +1,129 @@
+//! Cache of parsed trees
+//!
+//! Parsing a 20MB scan takes seconds, so the tree can be kept as JSON under
+//! the SHA-256 of the input bytes, the parse options and the kimi version,
+//! and read back as long as all three are unchanged.
+
+use crate::{CategoryTree, KimiError, ParseOptions};
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory of parsed trees, one JSON file per input and options
+#[derive(Debug, Clone)]
+pub struct ParseCache {
+    dir: PathBuf,
+}
+
+impl ParseCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        ParseCache { dir: dir.into() }
+    }
+
+    /// `$XDG_CACHE_HOME/kimi`, or `~/.cache/kimi`
+    pub fn default_dir() -> Option<PathBuf> {
+        env::var_os("XDG_CACHE_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+            .map(|dir| dir.join("kimi"))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Parse `input_file` like `build_from_with`, or read back the tree of
+    /// an earlier parse of the same bytes with the same options
+    pub fn build(
+        &self,
+        input_file: &str,
+        options: &ParseOptions,
+    ) -> Result<CategoryTree, KimiError> {
+        let bytes = fs::read(input_file)?;
+        let key = cache_key(input_file, &bytes, options)?;
+        let path = self.dir.join(format!("{}.json", key));
+        // an entry that no longer deserializes is parsed again
+        if let Some(tree) = fs::read(&path)
+            .ok()
+            .and_then(|json| serde_json::from_slice(&json).ok())
+        {
+            return Ok(tree);
+        }
+        let mut tree = CategoryTree::new();
+        tree.build_from_with(input_file, options)?;
+        // written aside and renamed, so a concurrent run never reads half
+        // an entry
+        fs::create_dir_all(&self.dir)?;
+        let partial = self.dir.join(format!("{}.{}.tmp", key, std::process::id()));
+        fs::write(&partial, serde_json::to_vec(&tree)?)?;
+        fs::rename(&partial, &path)?;
+        Ok(tree)
+    }
+}
+
+/// Hex SHA-256 of the kimi version, the options and line merger, the file
+/// extension, which picks the reader, and the file bytes
+fn cache_key(input_file: &str, bytes: &[u8], options: &ParseOptions) -> Result<String, KimiError> {
+    let extension = Path::new(input_file)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    let mut hasher = Sha256::new();
+    for part in [
+        env!("CARGO_PKG_VERSION").as_bytes(),
+        &serde_json::to_vec(options)?,
+        options.merger.name().as_bytes(),
+        extension.as_bytes(),
+    ] {
+        hasher.update(part);
+        hasher.update([0]);
+    }
+    hasher.update(bytes);
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cache() {
+        let dir = env::temp_dir().join(format!("kimi-cache-{}", std::process::id()));
+        let input = dir.join("dict.xlsx");
+        let mut book = umya_spreadsheet::new_file();
+        book.get_sheet_mut(&0)
+            .unwrap()
+            .get_cell_mut((1, 1))
+            .set_value("2-04(GBM20400)交通运输工程技术人员\n2-04-02(GBM20402)船舶指挥和引航人员");
+        fs::create_dir_all(&dir).unwrap();
+        umya_spreadsheet::writer::xlsx::write(&book, &input).unwrap();
+
+        let cache = ParseCache::new(dir.join("cache"));
+        let options = ParseOptions {
+            layout: Some(crate::LayoutProfile::default()),
+            ..ParseOptions::default()
+        };
+        let input = input.to_str().unwrap();
+        let tree = cache.build(input, &options).unwrap();
+        assert_eq!(tree.iter().count(), 2);
+        let entries = || fs::read_dir(cache.dir()).unwrap().count();
+        assert_eq!(entries(), 1);
+        assert_eq!(cache.build(input, &options).unwrap(), tree);
+        assert_eq!(entries(), 1);
+
+        // other options are another entry
+        let keep_headers = ParseOptions {
+            skip_headers: false,
+            ..options
+        };
+        cache.build(input, &keep_headers).unwrap();
+        assert_eq!(entries(), 2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,126 @@
+use crate::KimiError;
+use std::io::Write;
+use std::str::FromStr;
+
+/// Compression applied to an export by `write_compressed`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Compression {
+    #[default]
+    None,
+    #[cfg(feature = "gzip")]
+    Gzip,
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+impl Compression {
+    /// Conventional file extension, without the dot
+    pub fn extension(self) -> Option<&'static str> {
+        match self {
+            Compression::None => None,
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => Some("gz"),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => Some("zst"),
+        }
+    }
+}
+
+impl FromStr for Compression {
+    type Err = String;
+
+    /// `none`, `gzip` (or `gz`) and `zstd` (or `zst`), for the features
+    /// that are enabled
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Compression::None),
+            #[cfg(feature = "gzip")]
+            "gzip" | "gz" => Ok(Compression::Gzip),
+            #[cfg(feature = "zstd")]
+            "zstd" | "zst" => Ok(Compression::Zstd),
+            _ => Err(format!(
+                "unsupported compression {:?} (gzip and zstd need their cargo features)",
+                s
+            )),
+        }
+    }
+}
+
+/// Run `write` against `w`, compressing everything it writes
+///
+/// ```ignore
+/// write_compressed(file, Compression::Gzip, |w| tree.to_ndjson(w))?;
+/// ```
+pub fn write_compressed<W, F>(w: W, compression: Compression, write: F) -> Result<(), KimiError>
+where
+    W: Write,
+    F: FnOnce(&mut dyn Write) -> Result<(), KimiError>,
+{
+    match compression {
+        Compression::None => {
+            let mut w = w;
+            write(&mut w)?;
+            w.flush()?;
+        }
+        #[cfg(feature = "gzip")]
+        Compression::Gzip => {
+            let mut encoder = flate2::write::GzEncoder::new(w, flate2::Compression::default());
+            write(&mut encoder)?;
+            encoder.finish()?.flush()?;
+        }
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => {
+            let mut encoder = zstd::Encoder::new(w, 0)?;
+            write(&mut encoder)?;
+            encoder.finish()?.flush()?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CategoryTree;
+
+    fn tree() -> CategoryTree {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column("1-01(GBM10100)机关负责人\n1-01-00-01中国共产党机关负责人")
+            .unwrap();
+        tree
+    }
+
+    #[test]
+    fn test_write_uncompressed() {
+        let tree = tree();
+        let mut buf = Vec::new();
+        write_compressed(&mut buf, Compression::None, |w| tree.to_ndjson(w)).unwrap();
+        assert_eq!(buf.iter().filter(|&&b| b == b'\n').count(), 2);
+        assert!("brotli".parse::<Compression>().is_err());
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_write_gzip() {
+        use std::io::Read;
+
+        let tree = tree();
+        let mut buf = Vec::new();
+        write_compressed(&mut buf, "gzip".parse().unwrap(), |w| tree.to_ndjson(w)).unwrap();
+        let mut text = String::new();
+        flate2::read::GzDecoder::new(&buf[..])
+            .read_to_string(&mut text)
+            .unwrap();
+        assert_eq!(text.lines().count(), 2);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_write_zstd() {
+        let tree = tree();
+        let mut buf = Vec::new();
+        write_compressed(&mut buf, Compression::Zstd, |w| tree.to_ndjson(w)).unwrap();
+        let text = zstd::decode_all(&buf[..]).unwrap();
+        assert_eq!(text.iter().filter(|&&b| b == b'\n').count(), 2);
+    }
+}
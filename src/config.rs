@@ -0,0 +1,461 @@
+use crate::{Backend, IdFormat, KimiError, LayoutProfile, Merger, OnProgress, Span};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Which worksheets of a workbook to parse
+///
+/// Written as a 0-based index, a sheet name or `all`; a sheet literally
+/// named like a number or "all" cannot be selected by name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum SheetSelection {
+    Index(usize),
+    Name(String),
+    All,
+}
+
+impl Default for SheetSelection {
+    fn default() -> Self {
+        SheetSelection::Index(0)
+    }
+}
+
+impl FromStr for SheetSelection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "" => Err("empty sheet name".to_string()),
+            "all" => Ok(SheetSelection::All),
+            s => Ok(s.parse().map_or_else(
+                |_| SheetSelection::Name(s.to_string()),
+                SheetSelection::Index,
+            )),
+        }
+    }
+}
+
+impl TryFrom<String> for SheetSelection {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<SheetSelection> for String {
+    fn from(sheets: SheetSelection) -> Self {
+        sheets.to_string()
+    }
+}
+
+impl fmt::Display for SheetSelection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SheetSelection::Index(index) => write!(f, "{}", index),
+            SheetSelection::Name(name) => f.write_str(name),
+            SheetSelection::All => f.write_str("all"),
+        }
+    }
+}
+
+/// What to do with text that does not parse as a category
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum ParseMode {
+    /// Fail with every chunk the category regex rejected and every
+    /// category without a name
+    Strict,
+    /// Skip chunks the regex rejects or whose id has an empty segment, and
+    /// keep categories without a name
+    #[default]
+    Lenient,
+}
+
+impl FromStr for ParseMode {
+    type Err = String;
+
+    /// `strict` or `lenient`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "strict" => Ok(ParseMode::Strict),
+            "lenient" => Ok(ParseMode::Lenient),
+            _ => Err(format!("unknown parse mode {:?}", s)),
+        }
+    }
+}
+
+impl TryFrom<String> for ParseMode {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<ParseMode> for String {
+    fn from(mode: ParseMode) -> Self {
+        mode.to_string()
+    }
+}
+
+impl fmt::Display for ParseMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ParseMode::Strict => "strict",
+            ParseMode::Lenient => "lenient",
+        })
+    }
+}
+
+/// Options steering how a workbook is read and its lines reassembled
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ParseOptions {
+    /// Library reading the workbook, umya-spreadsheet by default
+    pub backend: Backend,
+    /// Worksheets to read, the first one by default
+    pub sheet: SheetSelection,
+    /// Rows to read, all by default
+    pub rows: Option<Span>,
+    /// Columns to read, all by default
+    pub cols: Option<Span>,
+    /// Columns holding the category text, detected per sheet when unset
+    pub layout: Option<LayoutProfile>,
+    /// Drop lines matching `header_patterns` before parsing a cell
+    pub skip_headers: bool,
+    /// Regular expressions for header rows, page numbers and the banner
+    /// repeated on every page of the scan
+    pub header_patterns: Vec<String>,
+    /// Name endings that close a line, e.g. "员" or "医生"
+    pub suffixes: Vec<String>,
+    /// Joins the lines of a cell into categories, `SuffixMerger` by default;
+    /// not read from or written to a config
+    #[serde(skip)]
+    pub merger: Merger,
+    /// OCR noise removed from every line once markers are split off
+    pub artifacts: Vec<String>,
+    /// Regular expressions for OCR noise removed along with `artifacts`,
+    /// e.g. "[·•]"
+    pub artifact_patterns: ArtifactPatterns,
+    /// Apply Unicode NFKC to the cell text, so full-width digits, letters,
+    /// brackets and dashes such as "（ＧＢＭ１０１００）" read as ASCII
+    pub nfkc: bool,
+    /// Characters the OCR reads for a digit or dash, e.g. "O" for "0",
+    /// replaced in the id at the start of a line when that makes it an id
+    pub confusions: BTreeMap<char, char>,
+    /// How ids and codes are written, those of the 大典 by default
+    pub id_format: IdFormat,
+    /// Fail when the parsed tree does not pass `validate`; unlike
+    /// `ParseMode::Strict`, this checks the tree rather than each chunk
+    pub validate: bool,
+    /// Fail on chunks that are not categories instead of skipping them
+    pub mode: ParseMode,
+    /// Keep each decision of the line merger in the `ParseReport`
+    pub trace_merges: bool,
+    /// Replace a 大类 or 中类 name a few characters off the published one
+    /// with it; needs the `reference` cargo feature
+    pub correct_names: bool,
+    /// Called after each row read; not read from or written to a config
+    #[serde(skip)]
+    pub progress: OnProgress,
+}
+
+impl Default for ParseOptions {
+    /// The layout and heuristics of the 2022 大典 scan
+    fn default() -> Self {
+        let strings = |items: &[&str]| items.iter().map(|s| s.to_string()).collect();
+        ParseOptions {
+            backend: Backend::default(),
+            sheet: SheetSelection::default(),
+            rows: None,
+            cols: None,
+            layout: None,
+            skip_headers: true,
+            header_patterns: strings(&[
+                // the scan spaces out the characters of the banner
+                r"^\s*(?:中\s*华\s*人\s*民\s*共\s*和\s*国\s*)?职\s*业\s*分\s*类\s*大\s*典\s*$",
+                r"^\s*(?:中\s*华\s*人\s*民\s*共\s*和\s*国|分\s*类\s*体\s*系\s*表)\s*$",
+                r"^\s*续\s*表\s*$",
+                r"^\s*[(（]\s*\d{4}\s*年版\s*[)）]\s*$",
+                r"^\s*(?:大类|中类|小类|细类)\s*(?:[(（]\s*职业\s*[)）])?\s*$",
+                r"^\s*(?:第\s*)?[-—]?\s*\d{1,4}\s*[-—]?\s*页?\s*$",
+            ]),
+            suffixes: strings(&[
+                "责人",
+                "员",
+                "护士",
+                "制片人",
+                "师",
+                "官",
+                "律师",
+                "医生",
+                "顾问",
+                "教师",
+                "警察",
+                "经理",
+                "董事",
+            ]),
+            merger: Merger::default(),
+            artifacts: strings(&["L", "S", "/"]),
+            artifact_patterns: ArtifactPatterns::default(),
+            nfkc: true,
+            confusions: [('O', '0'), ('l', '1'), ('I', '1'), ('B', '8'), ('一', '-')]
+                .into_iter()
+                .chain(('０'..='９').zip('0'..='9'))
+                .chain([('－', '-')])
+                .collect(),
+            id_format: IdFormat::default(),
+            validate: false,
+            mode: ParseMode::default(),
+            trace_merges: false,
+            correct_names: false,
+            progress: OnProgress::default(),
+        }
+    }
+}
+
+impl ParseOptions {
+    /// Add name endings to `suffixes`, skipping those already there
+    pub fn add_suffixes<I, S>(&mut self, suffixes: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        for suffix in suffixes {
+            let suffix = suffix.into();
+            if !suffix.is_empty() && !self.suffixes.contains(&suffix) {
+                self.suffixes.push(suffix);
+            }
+        }
+    }
+
+    /// Add the name endings listed in a file, see `read_suffixes`
+    pub fn add_suffixes_from(&mut self, path: impl AsRef<Path>) -> Result<(), KimiError> {
+        self.add_suffixes(read_suffixes(path)?);
+        Ok(())
+    }
+}
+
+/// Regular expressions for OCR noise, compiled into one when they are set,
+/// so a bad pattern fails there rather than when a cell is cleaned
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(try_from = "Vec<String>", into = "Vec<String>")]
+pub struct ArtifactPatterns {
+    patterns: Vec<String>,
+    regex: Option<Regex>,
+}
+
+impl ArtifactPatterns {
+    pub fn new<I, S>(patterns: I) -> Result<Self, KimiError>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let patterns = patterns.into_iter().map(Into::into).collect::<Vec<_>>();
+        let regex = if patterns.is_empty() {
+            None
+        } else {
+            let alternatives = patterns
+                .iter()
+                .map(|pattern| format!("(?:{})", pattern))
+                .collect::<Vec<_>>();
+            Some(Regex::new(&alternatives.join("|"))?)
+        };
+        Ok(ArtifactPatterns { patterns, regex })
+    }
+
+    pub fn as_slice(&self) -> &[String] {
+        &self.patterns
+    }
+
+    /// The patterns as one regular expression, `None` if there are none
+    pub(crate) fn regex(&self) -> Option<&Regex> {
+        self.regex.as_ref()
+    }
+}
+
+impl PartialEq for ArtifactPatterns {
+    fn eq(&self, other: &Self) -> bool {
+        self.patterns == other.patterns
+    }
+}
+
+impl Eq for ArtifactPatterns {}
+
+impl TryFrom<Vec<String>> for ArtifactPatterns {
+    type Error = KimiError;
+
+    fn try_from(patterns: Vec<String>) -> Result<Self, Self::Error> {
+        ArtifactPatterns::new(patterns)
+    }
+}
+
+impl From<ArtifactPatterns> for Vec<String> {
+    fn from(patterns: ArtifactPatterns) -> Self {
+        patterns.patterns
+    }
+}
+
+/// Name endings listed one per line, blank lines and lines starting with
+/// `#` left out
+pub fn read_suffixes(path: impl AsRef<Path>) -> Result<Vec<String>, KimiError> {
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Defaults for the command line output
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputConfig {
+    /// Format used by `export` when `--format` is not given
+    pub format: Option<String>,
+    /// Compression used by `export` when `--compress` is not given
+    pub compress: Option<String>,
+    /// Hide the progress of downloads and parses
+    pub quiet: bool,
+}
+
+/// Cache of parsed trees used by the command line
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheConfig {
+    /// Reuse the tree parsed from identical bytes with the same parse
+    /// options; needs the `cache` cargo feature
+    pub enabled: bool,
+    /// Directory of the cache, `$XDG_CACHE_HOME/kimi` or `~/.cache/kimi`
+    /// when unset
+    pub dir: Option<PathBuf>,
+}
+
+/// Contents of a `kimi.toml` file
+///
+/// ```toml
+/// [parse]
+/// sheet = "all"
+/// rows = "3:500"
+/// cols = "A:F"
+/// suffixes = ["员", "师", "医生"]
+/// validate = true
+///
+/// [parse.layout]
+/// columns = [1, 3]
+/// paired_columns = [[5, 6], { columns = [7, 8], join = "stacked" }]
+/// ignore = ["A1:F2"]
+///
+/// [parse.id_format]
+/// separator = "-"
+/// code_prefix = "GBM"
+///
+/// [output]
+/// format = "yaml"
+///
+/// [cache]
+/// enabled = true
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub parse: ParseOptions,
+    pub output: OutputConfig,
+    pub cache: CacheConfig,
+}
+
+impl Config {
+    /// Name of the file picked up from the working directory
+    pub const FILE_NAME: &'static str = "kimi.toml";
+
+    /// Parse a config from TOML text; missing keys keep their defaults
+    pub fn from_toml_str(s: &str) -> Result<Self, KimiError> {
+        Ok(toml::from_str(s)?)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, KimiError> {
+        Self::from_toml_str(&fs::read_to_string(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColumnGroup, JoinRule};
+
+    #[test]
+    fn test_config() {
+        let config = Config::from_toml_str(
+            "[parse]\nsuffixes = [\"员\"]\nvalidate = true\n\n[parse.layout]\ncolumns = [2]\n\n[output]\nformat = \"yaml\"\n",
+        )
+        .unwrap();
+        let layout = config.parse.layout.unwrap();
+        assert_eq!(layout.columns, [2]);
+        assert_eq!(layout.paired_columns, [ColumnGroup::pair(5, 6)]);
+        assert_eq!(config.parse.suffixes, ["员"]);
+        assert!(config.parse.validate);
+        assert_eq!(config.output.format.as_deref(), Some("yaml"));
+        assert!(!config.cache.enabled);
+        assert_eq!(Config::from_toml_str("").unwrap(), Config::default());
+        assert!(Config::from_toml_str("[parse]\ncolumn = [1]").is_err());
+
+        let groups = |s: &str| {
+            Config::from_toml_str(&format!("[parse.layout]\npaired_columns = {}", s))
+                .map(|config| config.parse.layout.unwrap().paired_columns)
+        };
+        assert_eq!(
+            groups("[[2, 3], { columns = [4, 5, 6], join = \"stacked\" }]").unwrap(),
+            [
+                ColumnGroup::pair(2, 3),
+                ColumnGroup::new(vec![4, 5, 6], JoinRule::Stacked).unwrap(),
+            ]
+        );
+        assert!(groups("[[5]]").is_err());
+
+        let format = Config::from_toml_str(
+            "[parse.id_format]\nseparator = \".\"\nsegments = 3\nsegment_pattern = \"[A-Z]|\\\\d+\"\n",
+        )
+        .unwrap()
+        .parse
+        .id_format;
+        assert_eq!(
+            format,
+            IdFormat {
+                separator: ".".to_string(),
+                segments: 3,
+                segment_pattern: r"[A-Z]|\d+".to_string(),
+                ..IdFormat::default()
+            }
+        );
+        assert!(Config::from_toml_str("[parse.id_format]\nprefix = \"NOC\"").is_err());
+        assert!(groups("[{ columns = [4, 5], join = \"sideways\" }]").is_err());
+
+        let sheet = |s: &str| Config::from_toml_str(s).unwrap().parse.sheet;
+        assert_eq!(sheet("[parse]\nsheet = \"all\""), SheetSelection::All);
+        assert_eq!(sheet("[parse]\nsheet = \"2\""), SheetSelection::Index(2));
+        assert_eq!(
+            sheet("[parse]\nsheet = \"第一大类\""),
+            SheetSelection::Name("第一大类".to_string())
+        );
+    }
+
+    #[test]
+    fn test_suffix_file() {
+        let path = std::env::temp_dir().join(format!("kimi-suffixes-{}.txt", std::process::id()));
+        fs::write(&path, "# 2022 scan\n操作工\n\n 员 \n讲解员\n").unwrap();
+        let mut options = ParseOptions {
+            suffixes: vec!["员".to_string()],
+            ..ParseOptions::default()
+        };
+        options.add_suffixes_from(&path).unwrap();
+        options.add_suffixes(["操作工", "调酒师"]);
+        assert_eq!(options.suffixes, ["员", "操作工", "讲解员", "调酒师"]);
+        fs::remove_file(&path).unwrap();
+    }
+}
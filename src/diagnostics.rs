@@ -0,0 +1,176 @@
+//! Warnings raised while parsing
+//!
+//! Some text parses but looks wrong: the two columns of a pair with
+//! different numbers of lines, and the lines left without a partner, a name of a
+//! single character left by the OCR, or an id read twice. The parser hands
+//! each of these to a `Diagnostics` sink as it meets them; without one they
+//! are logged at the warn level, see the `tracing` events of `build_from`.
+
+use crate::CellRef;
+use serde::Serialize;
+use std::fmt;
+use std::sync::mpsc::Sender;
+
+/// Something the parser read but that is likely wrong
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Warning {
+    /// The columns of a pair have different numbers of lines; the names
+    /// are cut anew to give each id one
+    UnevenPair {
+        cell: CellRef,
+        first: usize,
+        second: usize,
+    },
+    /// Lines of one column of a pair left without a partner in the other,
+    /// and dropped
+    LeftoverLines { cell: CellRef, lines: Vec<String> },
+    /// The category name is shorter than two characters
+    ShortDesc {
+        id: String,
+        desc: String,
+        cell: Option<CellRef>,
+    },
+    /// The id was already read; both categories are kept
+    DuplicateId {
+        id: String,
+        first: Option<CellRef>,
+        second: Option<CellRef>,
+    },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::UnevenPair {
+                cell,
+                first,
+                second,
+            } => write!(
+                f,
+                "{}: pair columns have {} and {} lines",
+                cell, first, second
+            ),
+            Warning::LeftoverLines { cell, lines } => {
+                write!(f, "{}: no partner for {}", cell, lines.join(", "))
+            }
+            Warning::ShortDesc { id, desc, cell } => {
+                write!(f, "{}: short name {:?}", id, desc)?;
+                at(f, cell.as_ref())
+            }
+            Warning::DuplicateId { id, first, second } => {
+                write!(f, "{}: id read twice", id)?;
+                match (first, second) {
+                    (Some(first), Some(second)) => write!(f, " at {} and {}", first, second),
+                    _ => at(f, first.as_ref().or(second.as_ref())),
+                }
+            }
+        }
+    }
+}
+
+fn at(f: &mut fmt::Formatter<'_>, cell: Option<&CellRef>) -> fmt::Result {
+    match cell {
+        Some(cell) => write!(f, " at {}", cell),
+        None => Ok(()),
+    }
+}
+
+/// Receiver of the warnings of a parse
+pub trait Diagnostics {
+    fn warn(&mut self, warning: Warning);
+}
+
+impl Diagnostics for Vec<Warning> {
+    fn warn(&mut self, warning: Warning) {
+        self.push(warning);
+    }
+}
+
+/// Sends each warning over the channel, e.g. to another thread; warnings
+/// are dropped once the receiver is gone
+impl Diagnostics for Sender<Warning> {
+    fn warn(&mut self, warning: Warning) {
+        let _ = self.send(warning);
+    }
+}
+
+impl<F: FnMut(Warning)> Diagnostics for F {
+    fn warn(&mut self, warning: Warning) {
+        self(warning);
+    }
+}
+
+/// Logs warnings as `tracing` events
+pub(crate) struct LogWarnings;
+
+impl Diagnostics for LogWarnings {
+    fn warn(&mut self, warning: Warning) {
+        tracing::warn!("{}", warning);
+    }
+}
+
+/// Warn about the categories of a cell whose name is too short
+pub(crate) fn check_desc(category: &crate::Category, diagnostics: &mut dyn Diagnostics) {
+    if let Some(desc) = &category.desc
+        && !desc.is_empty()
+        && desc.chars().count() < 2
+    {
+        diagnostics.warn(Warning::ShortDesc {
+            id: category.id.clone(),
+            desc: desc.clone(),
+            cell: category.source.clone(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Backend, CategoryTree, ColumnGroup, LayoutProfile, ParseOptions};
+
+    #[test]
+    fn test_diagnostics() {
+        let mut book = umya_spreadsheet::new_file();
+        let sheet = book.get_sheet_mut(&0).unwrap();
+        for (cell, text) in [
+            (
+                (1, 1),
+                "2-04(GBM20400)交通运输工程技术人员\n2-04-02(GBM20402)船",
+            ),
+            ((1, 2), "2-04(GBM20400)交通运输工程技术人员"),
+            ((2, 3), "2-04-02-01\n2-04-02-02"),
+            ((3, 3), "船舶驾驶员"),
+        ] {
+            sheet.get_cell_mut(cell).set_value(text);
+        }
+        let mut bytes = Vec::new();
+        umya_spreadsheet::writer::xlsx::write_writer(&book, &mut bytes).unwrap();
+
+        let options = ParseOptions {
+            layout: Some(LayoutProfile {
+                columns: vec![1],
+                paired_columns: vec![ColumnGroup::pair(2, 3)],
+                ignore: Vec::new(),
+            }),
+            ..ParseOptions::default()
+        };
+        let mut source = Backend::Umya
+            .from_reader(std::io::Cursor::new(bytes))
+            .unwrap();
+        let mut warnings: Vec<Warning> = Vec::new();
+        CategoryTree::new()
+            .build_from_source_with_diagnostics(&mut *source, &options, &mut warnings)
+            .unwrap();
+        let warnings = warnings.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(
+            warnings,
+            [
+                "2-04-02: short name \"船\" at Sheet1!A1",
+                "2-04: id read twice at Sheet1!A1 and Sheet1!A2",
+                "Sheet1!B3: pair columns have 2 and 1 lines",
+                "Sheet1!B3: no partner for 2-04-02-02",
+            ]
+        );
+    }
+}
@@ -0,0 +1,98 @@
+use crate::{Category, CategoryTree};
+use indexmap::IndexMap;
+use serde::Serialize;
+
+/// Differences between two trees, matched by category id
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TreeDiff {
+    /// Categories only present in the new tree
+    pub added: Vec<Category>,
+    /// Categories only present in the old tree
+    pub removed: Vec<Category>,
+    /// Categories whose code or desc differ
+    pub changed: Vec<CategoryChange>,
+}
+
+/// A category present in both trees with a different code or desc
+#[derive(Debug, Clone, Serialize)]
+pub struct CategoryChange {
+    pub id: String,
+    pub old: Category,
+    pub new: Category,
+}
+
+impl TreeDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl CategoryTree {
+    /// Compare this (old) tree against `other` (new)
+    pub fn diff(&self, other: &CategoryTree) -> TreeDiff {
+        let old = by_id(self);
+        let new = by_id(other);
+        let mut diff = TreeDiff::default();
+
+        for (id, old_cat) in &old {
+            match new.get(id) {
+                None => diff.removed.push((*old_cat).clone()),
+                Some(new_cat) => {
+                    if old_cat.code != new_cat.code || old_cat.desc != new_cat.desc {
+                        diff.changed.push(CategoryChange {
+                            id: id.to_string(),
+                            old: (*old_cat).clone(),
+                            new: (*new_cat).clone(),
+                        });
+                    }
+                }
+            }
+        }
+        for (id, new_cat) in &new {
+            if !old.contains_key(id) {
+                diff.added.push((*new_cat).clone());
+            }
+        }
+        diff
+    }
+}
+
+// First category for each id, in tree order
+fn by_id(tree: &CategoryTree) -> IndexMap<&str, &Category> {
+    let mut map = IndexMap::new();
+    for (_, cat) in tree {
+        map.entry(cat.id.as_str()).or_insert(cat);
+    }
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree(text: &str) -> CategoryTree {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column(text).unwrap();
+        tree
+    }
+
+    #[test]
+    fn test_diff() {
+        let old =
+            tree("1-01(GBM10100)机关负责人\n1-02(GBM10200)国家机关负责人\n1-03 民主党派负责人");
+        let new = tree(
+            "1-01(GBM10100)机关负责人\n1-02(GBM10201)国家机关负责人\n1-04(GBM10400)人民团体负责人",
+        );
+
+        let diff = old.diff(&new);
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].id, "1-04");
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].id, "1-03");
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].id, "1-02");
+        assert_eq!(diff.changed[0].new.code.as_deref(), Some("GBM10201"));
+
+        assert!(old.diff(&old).is_empty());
+    }
+}
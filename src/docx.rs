@@ -0,0 +1,256 @@
+//! Word documents
+//!
+//! `word/document.xml` is read as a single sheet: the rows of every table
+//! are stacked in document order, since a table running over several pages
+//! is often split into one table per page, and each run of paragraphs
+//! between tables becomes one cell in the first column. Horizontal
+//! (`gridSpan`) and vertical (`vMerge`) merges turn into merged regions.
+
+use crate::{
+    CategoryTree, CellRange, KimiError, ParseOptions, ParseReport, Sheet, SpreadsheetSource,
+};
+use quick_xml::Reader;
+use quick_xml::events::{BytesStart, Event};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek};
+use std::path::Path;
+use zip::ZipArchive;
+
+/// Text and tables of a `.docx` file, as one sheet named "Document"
+pub struct DocxSource {
+    sheet: Sheet,
+}
+
+impl DocxSource {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, KimiError> {
+        Self::from_reader(File::open(path)?)
+    }
+
+    pub fn from_reader<R: Read + Seek>(reader: R) -> Result<Self, KimiError> {
+        let mut archive = ZipArchive::new(reader).map_err(document_error)?;
+        let mut xml = String::new();
+        archive
+            .by_name("word/document.xml")
+            .map_err(document_error)?
+            .read_to_string(&mut xml)?;
+        Ok(DocxSource {
+            sheet: document_sheet(&xml)?,
+        })
+    }
+}
+
+impl SpreadsheetSource for DocxSource {
+    fn sheet_names(&self) -> Vec<String> {
+        vec![self.sheet.name().to_string()]
+    }
+
+    fn read_sheet(&mut self, index: usize) -> Result<Sheet, KimiError> {
+        match index {
+            0 => Ok(self.sheet.clone()),
+            _ => Err(KimiError::SheetNotFound(index)),
+        }
+    }
+}
+
+impl CategoryTree {
+    /// Parse the tables and paragraphs of a Word document, like
+    /// `build_from_with`
+    pub fn build_from_docx(
+        &mut self,
+        input_file: &str,
+        options: &ParseOptions,
+    ) -> Result<ParseReport, KimiError> {
+        self.build_from_source(&mut DocxSource::open(input_file)?, options)
+    }
+}
+
+fn document_error(e: impl std::fmt::Display) -> KimiError {
+    KimiError::Document(e.to_string())
+}
+
+/// A `w:vMerge` marker: the first cell of a vertical merge, or one of the
+/// cells below it
+#[derive(Clone, Copy, PartialEq)]
+enum VMerge {
+    Restart,
+    Continue,
+}
+
+/// The table cell being read
+struct Cell {
+    text: String,
+    span: u32,
+    vmerge: Option<VMerge>,
+}
+
+fn document_sheet(xml: &str) -> Result<Sheet, KimiError> {
+    let mut reader = Reader::from_str(xml);
+    let mut sheet = Sheet::new("Document");
+    let mut row = 0;
+    let mut col = 1;
+    // paragraphs read since the last table
+    let mut paragraphs = String::new();
+    let mut cell: Option<Cell> = None;
+    // vertical merges still open, by their first column
+    let mut open: HashMap<u32, CellRange> = HashMap::new();
+    let mut tables = 0;
+    let mut in_text = false;
+
+    loop {
+        let event = reader.read_event().map_err(document_error)?;
+        let empty = matches!(event, Event::Empty(_));
+        match event {
+            Event::Start(e) | Event::Empty(e) => match e.local_name().as_ref() {
+                b"tbl" if !empty => {
+                    tables += 1;
+                    if tables == 1 && !paragraphs.trim().is_empty() {
+                        row += 1;
+                        sheet.set_text(1, row, paragraphs.trim_matches('\n'));
+                    }
+                    paragraphs.clear();
+                }
+                b"tr" if tables == 1 && !empty => {
+                    row += 1;
+                    col = 1;
+                }
+                b"gridBefore" if tables == 1 => {
+                    col += val(&e)?.and_then(|v| v.parse().ok()).unwrap_or(0)
+                }
+                b"tc" if tables == 1 && !empty => {
+                    cell = Some(Cell {
+                        text: String::new(),
+                        span: 1,
+                        vmerge: None,
+                    })
+                }
+                b"gridSpan" if tables == 1 => {
+                    if let Some(cell) = &mut cell {
+                        cell.span = val(&e)?.and_then(|v| v.parse().ok()).unwrap_or(1).max(1);
+                    }
+                }
+                b"vMerge" if tables == 1 => {
+                    if let Some(cell) = &mut cell {
+                        cell.vmerge = Some(match val(&e)?.as_deref() {
+                            Some("restart") => VMerge::Restart,
+                            _ => VMerge::Continue,
+                        });
+                    }
+                }
+                b"t" if !empty => in_text = true,
+                b"br" | b"cr" => current(&mut cell, &mut paragraphs).push('\n'),
+                // a tab character, not a tab stop of the paragraph style
+                b"tab" if val(&e)?.is_none() => current(&mut cell, &mut paragraphs).push(' '),
+                _ => {}
+            },
+            Event::Text(e) if in_text => {
+                let text = e.unescape().map_err(document_error)?;
+                current(&mut cell, &mut paragraphs).push_str(&text);
+            }
+            Event::End(e) => match e.local_name().as_ref() {
+                b"t" => in_text = false,
+                b"p" => current(&mut cell, &mut paragraphs).push('\n'),
+                b"tc" if tables == 1 => {
+                    let Some(cell) = cell.take() else { continue };
+                    let last_col = col + cell.span - 1;
+                    match cell.vmerge {
+                        Some(VMerge::Continue) => {
+                            if let Some(range) = open.get_mut(&col) {
+                                range.last.1 = row;
+                            }
+                        }
+                        vmerge => {
+                            sheet.set_text(col, row, cell.text.trim_matches('\n'));
+                            let range = CellRange {
+                                first: (col, row),
+                                last: (last_col, row),
+                            };
+                            if let Some(done) = open.remove(&col) {
+                                add_merged(&mut sheet, done);
+                            }
+                            if vmerge == Some(VMerge::Restart) {
+                                open.insert(col, range);
+                            } else {
+                                add_merged(&mut sheet, range);
+                            }
+                        }
+                    }
+                    col = last_col + 1;
+                }
+                b"tbl" => {
+                    tables -= 1;
+                    if tables == 0 {
+                        for (_, range) in open.drain() {
+                            add_merged(&mut sheet, range);
+                        }
+                    }
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    if !paragraphs.trim().is_empty() {
+        sheet.set_text(1, row + 1, paragraphs.trim_matches('\n'));
+    }
+    Ok(sheet)
+}
+
+/// Text being read: the current table cell, or the paragraphs between
+/// tables
+fn current<'a>(cell: &'a mut Option<Cell>, paragraphs: &'a mut String) -> &'a mut String {
+    match cell {
+        Some(cell) => &mut cell.text,
+        None => paragraphs,
+    }
+}
+
+fn add_merged(sheet: &mut Sheet, range: CellRange) {
+    if range.first != range.last {
+        sheet.add_merged(range);
+    }
+}
+
+/// The `w:val` attribute of an element
+fn val(e: &BytesStart) -> Result<Option<String>, KimiError> {
+    for attr in e.attributes() {
+        let attr = attr.map_err(document_error)?;
+        if attr.key.local_name().as_ref() == b"val" {
+            return Ok(Some(
+                attr.unescape_value().map_err(document_error)?.into_owned(),
+            ));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_document_sheet() {
+        let sheet = document_sheet(
+            r#"<w:document xmlns:w="w"><w:body>
+            <w:p><w:r><w:t>中华人民共和国职业分类大典</w:t></w:r></w:p>
+            <w:tbl>
+              <w:tr><w:tc><w:tcPr><w:gridSpan w:val="2"/></w:tcPr><w:p><w:r><w:t>2-04-02(GBM20402)</w:t></w:r><w:r><w:br/><w:t>船舶指挥和引航人员</w:t></w:r></w:p></w:tc>
+                <w:tc><w:tcPr><w:vMerge w:val="restart"/></w:tcPr><w:p><w:r><w:t>2-04-02-01</w:t></w:r></w:p><w:p><w:r><w:t>2-04-02-02</w:t></w:r></w:p></w:tc>
+                <w:tc><w:p><w:r><w:t>船舶驾驶员</w:t></w:r></w:p></w:tc></w:tr>
+              <w:tr><w:tc><w:p/></w:tc><w:tc><w:p/></w:tc><w:tc><w:tcPr><w:vMerge/></w:tcPr><w:p/></w:tc>
+                <w:tc><w:p><w:r><w:t xml:space="preserve">船舶引航员 </w:t></w:r><w:r><w:t>L</w:t></w:r></w:p></w:tc></w:tr>
+            </w:tbl>
+            <w:p><w:r><w:t>2-04-03(GBM20403)</w:t></w:r></w:p>
+            </w:body></w:document>"#,
+        )
+        .unwrap();
+        assert_eq!(sheet.text(1, 1), "中华人民共和国职业分类大典");
+        assert_eq!(sheet.text(1, 2), "2-04-02(GBM20402)\n船舶指挥和引航人员");
+        assert_eq!(sheet.merged().find(2, 2).map(|r| r.last), Some((2, 2)));
+        assert_eq!(sheet.text(3, 2), "2-04-02-01\n2-04-02-02");
+        assert_eq!(sheet.merged().rows(3, 3), (2, 3));
+        assert_eq!(sheet.text(4, 3), "船舶引航员 L");
+        assert_eq!(sheet.text(1, 4), "2-04-03(GBM20403)");
+    }
+}
@@ -0,0 +1,246 @@
+use crate::id::{compare_ids, compare_segments};
+use crate::{CategoryTree, KimiError, Tree};
+
+impl<T> Tree<T> {
+    /// Remove the node for `id` together with everything under it
+    pub fn remove(&mut self, id: &str) -> Option<Tree<T>> {
+        let (parent, last) = match id.rsplit_once('-') {
+            Some((parent, last)) => (self.get_node_mut(parent)?, last),
+            None => (self, id),
+        };
+        parent.children.shift_remove(last)
+    }
+
+    /// Remove nodes that hold no categories anywhere below them,
+    /// returning the number of nodes removed
+    pub fn prune_empty(&mut self) -> usize {
+        let mut removed = 0;
+        self.children.retain(|_, child| {
+            removed += child.prune_empty();
+            if child.items.is_empty() && child.children.is_empty() {
+                removed += 1;
+                false
+            } else {
+                true
+            }
+        });
+        removed
+    }
+
+    /// Keep only the categories for which `f` returns true
+    ///
+    /// Nodes are kept even if they end up empty; see `prune_empty`.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        self.retain_inner(&mut f);
+    }
+
+    fn retain_inner<F: FnMut(&T) -> bool>(&mut self, f: &mut F) {
+        self.items.retain(|c| f(c));
+        for child in self.children.values_mut() {
+            child.retain_inner(f);
+        }
+    }
+
+    /// Find the node for a dash-separated id, mutably
+    pub fn get_node_mut(&mut self, id: &str) -> Option<&mut Tree<T>> {
+        let mut node = self;
+        for level in id.split('-') {
+            node = node.children.get_mut(level)?;
+        }
+        Some(node)
+    }
+}
+
+impl CategoryTree {
+    /// Give the node at `old_id` the id `new_id`, rewriting the ids of all
+    /// categories below it, e.g. "2-02-01" -> "2-03-01" turns "2-02-01-05"
+    /// into "2-03-01-05"
+    pub fn rename(&mut self, old_id: &str, new_id: &str) -> Result<(), KimiError> {
+        if new_id.split('-').any(|seg| seg.is_empty()) {
+            return Err(KimiError::MalformedId(new_id.to_string()));
+        }
+        if new_id == old_id || new_id.starts_with(&format!("{}-", old_id)) {
+            return Err(KimiError::InvalidMove {
+                from: old_id.to_string(),
+                to: new_id.to_string(),
+            });
+        }
+        if self.get_node(old_id).is_none() {
+            return Err(KimiError::IdNotFound(old_id.to_string()));
+        }
+        if self.get_node(new_id).is_some() {
+            return Err(KimiError::IdExists(new_id.to_string()));
+        }
+
+        let mut node = self.remove(old_id).unwrap();
+        node.relabel(old_id, new_id);
+
+        let (parent, last) = match new_id.rsplit_once('-') {
+            Some((parent_id, last)) => {
+                let mut parent = &mut *self;
+                for level in parent_id.split('-') {
+                    parent = parent.children.entry(level.to_string()).or_default();
+                }
+                (parent, last)
+            }
+            None => (self, new_id),
+        };
+        parent.children.insert(last.to_string(), node);
+        Ok(())
+    }
+
+    /// Move the node at `from` under the node `to_parent`, keeping its last
+    /// id segment, e.g. "2-02-01-03" moved to "2-02-02" becomes "2-02-02-03"
+    pub fn move_subtree(&mut self, from: &str, to_parent: &str) -> Result<(), KimiError> {
+        let last = from.rsplit('-').next().unwrap_or(from);
+        self.rename(from, &format!("{}-{}", to_parent, last))
+    }
+
+    /// Order children numerically by key (1, 2, …, 10) and the categories
+    /// of every node by id, recursively
+    pub fn sort(&mut self) {
+        self.children.sort_by(|a, _, b, _| compare_segments(a, b));
+        self.items.sort_by(|a, b| compare_ids(&a.id, &b.id));
+        for child in self.children.values_mut() {
+            child.sort();
+        }
+    }
+
+    /// Remove repeated categories (same id and code) within each node,
+    /// keeping the first, and return how many were dropped
+    pub fn dedup(&mut self) -> usize {
+        let before = self.items.len();
+        let mut seen = Vec::new();
+        self.items.retain(|c| {
+            let key = (c.id.clone(), c.code.clone());
+            if seen.contains(&key) {
+                false
+            } else {
+                seen.push(key);
+                true
+            }
+        });
+        let mut dropped = before - self.items.len();
+        for child in self.children.values_mut() {
+            dropped += child.dedup();
+        }
+        dropped
+    }
+
+    fn relabel(&mut self, old_prefix: &str, new_prefix: &str) {
+        for cat in &mut self.items {
+            if let Some(rest) = cat.id.strip_prefix(old_prefix) {
+                cat.id = format!("{}{}", new_prefix, rest);
+            }
+        }
+        for child in self.children.values_mut() {
+            child.relabel(old_prefix, new_prefix);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> CategoryTree {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column(
+            "1-01(GBM10100)机关负责人\n1-01-00-01中国共产党机关负责人\n1-01-00-02中国共产党基层组织负责人\n1-02(GBM10200)国家机关负责人",
+        )
+        .unwrap();
+        tree
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut tree = tree();
+        let removed = tree.remove("1-01").unwrap();
+        assert_eq!(removed.iter().count(), 3);
+        assert!(tree.get("1-01-00-01").is_none());
+        assert!(tree.get("1-02").is_some());
+        assert!(tree.remove("1-01").is_none());
+        assert!(tree.remove("1").is_some());
+        assert_eq!(tree.iter().count(), 0);
+    }
+
+    #[test]
+    fn test_retain_and_prune() {
+        let mut tree = tree();
+        tree.retain(|c| c.id != "1-01-00-01" && c.id != "1-01-00-02");
+        assert_eq!(tree.iter().count(), 2);
+        // "1-01-00", "1-01-00-01" and "1-01-00-02" are now empty
+        assert_eq!(tree.prune_empty(), 3);
+        assert!(tree.get_node("1-01-00").is_none());
+        assert!(tree.get_node("1-01").is_some());
+        assert_eq!(tree.prune_empty(), 0);
+    }
+
+    #[test]
+    fn test_rename() {
+        let mut tree = tree();
+        tree.rename("1-01", "1-03").unwrap();
+        assert!(tree.get_node("1-01").is_none());
+        assert_eq!(tree.get("1-03").unwrap().code.as_deref(), Some("GBM10100"));
+        assert_eq!(
+            tree.get("1-03-00-02").unwrap().desc.as_deref(),
+            Some("中国共产党基层组织负责人")
+        );
+        for (path, cat) in &tree {
+            assert_eq!(path.join("-"), cat.id);
+        }
+
+        assert!(matches!(
+            tree.rename("1-03", "1-02"),
+            Err(KimiError::IdExists(_))
+        ));
+        assert!(matches!(
+            tree.rename("1-09", "1-10"),
+            Err(KimiError::IdNotFound(_))
+        ));
+        assert!(matches!(
+            tree.rename("1-03", "1-03-01"),
+            Err(KimiError::InvalidMove { .. })
+        ));
+    }
+
+    #[test]
+    fn test_move_subtree() {
+        let mut tree = tree();
+        tree.move_subtree("1-01-00-02", "2-05-01").unwrap();
+        assert!(tree.get("1-01-00-02").is_none());
+        assert_eq!(
+            tree.get("2-05-01-02").unwrap().desc.as_deref(),
+            Some("中国共产党基层组织负责人")
+        );
+        tree.move_subtree("1-02", "2").unwrap();
+        assert_eq!(tree.get("2-02").unwrap().code.as_deref(), Some("GBM10200"));
+    }
+
+    #[test]
+    fn test_sort() {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column(
+            "2-10(GBM21000)甲工程技术人员\n1-02(GBM10200)国家机关负责人\n2-9(GBM20900)乙工程技术人员\n1-01(GBM10100)机关负责人",
+        )
+        .unwrap();
+        tree.sort();
+        let ids = tree.iter().map(|(_, c)| c.id.as_str()).collect::<Vec<_>>();
+        assert_eq!(ids, vec!["1-01", "1-02", "2-9", "2-10"]);
+    }
+
+    #[test]
+    fn test_dedup() {
+        let mut tree = tree();
+        // overlapping OCR pages
+        tree.parse_one_column(
+            "1-01(GBM10100)机关负责人\n1-01-00-01中国共产党机关负责人\n1-01(GBM10101)机关负责人",
+        )
+        .unwrap();
+        assert_eq!(tree.iter().count(), 7);
+        assert_eq!(tree.dedup(), 2);
+        assert_eq!(tree.get_node("1-01").unwrap().items().len(), 2);
+        assert_eq!(tree.iter().count(), 5);
+        assert_eq!(tree.dedup(), 0);
+    }
+}
@@ -0,0 +1,184 @@
+//! Published editions of the 大典
+//!
+//! Each edition states how many categories it has at every level, so a
+//! parse can be checked for whole branches lost to the scan or the OCR:
+//! the 2015 edition has 8 大类, 75 中类, 434 小类 and 1481 职业, the 2022
+//! edition 8 大类, 79 中类, 449 小类 and 1636 职业.
+
+use crate::{CategoryTree, Level, LevelCounts};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+/// An edition of the 中华人民共和国职业分类大典
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Edition {
+    Edition2015,
+    Edition2022,
+}
+
+impl Edition {
+    /// Categories per level as published
+    pub fn totals(self) -> LevelCounts {
+        match self {
+            Edition::Edition2015 => LevelCounts {
+                major: 8,
+                middle: 75,
+                minor: 434,
+                detail: 1481,
+            },
+            Edition::Edition2022 => LevelCounts {
+                major: 8,
+                middle: 79,
+                minor: 449,
+                detail: 1636,
+            },
+        }
+    }
+}
+
+impl FromStr for Edition {
+    type Err = String;
+
+    /// `2015` or `2022`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "2015" => Ok(Edition::Edition2015),
+            "2022" => Ok(Edition::Edition2022),
+            _ => Err(format!("unknown edition {:?}, expected 2015 or 2022", s)),
+        }
+    }
+}
+
+impl TryFrom<String> for Edition {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Edition> for String {
+    fn from(edition: Edition) -> Self {
+        edition.to_string()
+    }
+}
+
+impl fmt::Display for Edition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Edition::Edition2015 => "2015",
+            Edition::Edition2022 => "2022",
+        })
+    }
+}
+
+/// A level whose number of distinct ids differs from the edition
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Discrepancy {
+    pub level: Level,
+    pub expected: usize,
+    pub found: usize,
+}
+
+impl fmt::Display for Discrepancy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} of {}", self.level, self.found, self.expected)?;
+        if self.found < self.expected {
+            write!(f, ", {} missing", self.expected - self.found)
+        } else {
+            write!(f, ", {} extra", self.found - self.expected)
+        }
+    }
+}
+
+/// Level counts of a tree next to those of an edition
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Verification {
+    pub edition: Edition,
+    pub expected: LevelCounts,
+    /// Distinct ids per level
+    pub found: LevelCounts,
+    /// The levels that differ, from 大类 down
+    pub discrepancies: Vec<Discrepancy>,
+}
+
+impl Verification {
+    pub fn is_ok(&self) -> bool {
+        self.discrepancies.is_empty()
+    }
+}
+
+impl CategoryTree {
+    /// Compare the number of distinct ids at each level with the totals
+    /// published for `edition`
+    pub fn verify(&self, edition: Edition) -> Verification {
+        let mut ids = Level::ALL.map(|_| HashSet::new());
+        for (_, cat) in self {
+            if let Some(level) = cat.level() {
+                ids[level as usize].insert(cat.id.as_str());
+            }
+        }
+        let [major, middle, minor, detail] = ids.map(|ids| ids.len());
+        let found = LevelCounts {
+            major,
+            middle,
+            minor,
+            detail,
+        };
+        let expected = edition.totals();
+        let discrepancies = Level::ALL
+            .into_iter()
+            .filter(|&level| found.get(level) != expected.get(level))
+            .map(|level| Discrepancy {
+                level,
+                expected: expected.get(level),
+                found: found.get(level),
+            })
+            .collect();
+        Verification {
+            edition,
+            expected,
+            found,
+            discrepancies,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify() {
+        let mut tree = CategoryTree::new();
+        for major in 1..=8 {
+            tree.parse_one_column(&format!("{}(GBM{}0000)第{}大类", major, major, major))
+                .unwrap();
+        }
+        tree.parse_one_column(
+            "2-04(GBM20400)交通运输工程技术人员\n2-04(GBM20400)交通运输工程技术人员",
+        )
+        .unwrap();
+
+        let verification = tree.verify("2022".parse().unwrap());
+        assert_eq!(verification.found.major, 8);
+        assert_eq!(verification.found.middle, 1);
+        let discrepancies = verification
+            .discrepancies
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            discrepancies,
+            [
+                "中类: 1 of 79, 78 missing",
+                "小类: 0 of 449, 449 missing",
+                "细类: 0 of 1636, 1636 missing",
+            ]
+        );
+        assert!(!verification.is_ok());
+    }
+}
@@ -0,0 +1,135 @@
+use crate::{Problem, RejectedChunk};
+use thiserror::Error;
+use umya_spreadsheet::XlsxError;
+
+/// Errors returned by kimi
+#[derive(Debug, Error)]
+pub enum KimiError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("failed to read spreadsheet: {0}")]
+    Spreadsheet(#[from] XlsxError),
+
+    #[cfg(feature = "calamine")]
+    #[error("failed to read spreadsheet: {0}")]
+    Calamine(#[from] calamine::XlsxError),
+
+    #[cfg(feature = "xls")]
+    #[error("failed to read spreadsheet: {0}")]
+    Xls(#[from] calamine::XlsError),
+
+    #[error("failed to read document: {0}")]
+    Document(String),
+
+    #[error("failed to read zip archive: {0}")]
+    Zip(#[from] zip::result::ZipError),
+
+    #[error("failed to parse {entry} in archive: {source}")]
+    ArchiveEntry {
+        entry: String,
+        #[source]
+        source: Box<KimiError>,
+    },
+
+    #[error("sheet {0} not found")]
+    SheetNotFound(usize),
+
+    #[error("sheet {0:?} not found")]
+    SheetNameNotFound(String),
+
+    #[error("JSON error: {0}")]
+    Json(#[source] serde_json::Error),
+
+    #[error("YAML error: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+
+    #[error("TOML error: {0}")]
+    Toml(#[from] toml::ser::Error),
+
+    #[error("invalid TOML: {0}")]
+    TomlDe(#[from] toml::de::Error),
+
+    #[error("CSV error: {0}")]
+    Csv(#[from] csv::Error),
+
+    #[cfg(feature = "arrow")]
+    #[error("Arrow error: {0}")]
+    Arrow(#[from] arrow_schema::ArrowError),
+
+    #[cfg(feature = "parquet")]
+    #[error("Parquet error: {0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
+
+    #[cfg(feature = "ods")]
+    #[error("ODS error: {0}")]
+    Ods(#[from] spreadsheet_ods::OdsError),
+
+    #[cfg(feature = "protobuf")]
+    #[error("protobuf decode error: {0}")]
+    Protobuf(#[from] prost::DecodeError),
+
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+
+    #[cfg(feature = "fetch")]
+    #[error("download failed: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("binary serialization error: {0}")]
+    Binary(String),
+
+    #[error("invalid regex: {0}")]
+    Regex(#[from] regex::Error),
+
+    #[error("invalid column group {0:?}, expected two or more 1-based columns")]
+    InvalidColumnGroup(Vec<u32>),
+
+    #[error("malformed category id {0:?}")]
+    MalformedId(String),
+
+    #[error("category id {id:?} found at position {path:?}")]
+    InconsistentId { id: String, path: String },
+
+    #[error("category id {0:?} not found")]
+    IdNotFound(String),
+
+    #[error("GBM code {0:?} not found")]
+    CodeNotFound(String),
+
+    #[error("category id {0:?} already exists")]
+    IdExists(String),
+
+    #[error("cannot move {from:?} to {to:?}")]
+    InvalidMove { from: String, to: String },
+
+    #[error("conflicting categories for id {0:?}")]
+    MergeConflict(String),
+
+    #[error("parsed tree has {} problems, the first being: {}", .0.len(), .0[0])]
+    Invalid(Vec<Problem>),
+
+    #[error("{} chunks are not categories, the first being: {}", .0.len(), .0[0])]
+    Rejected(Vec<RejectedChunk>),
+
+    #[error("failed to parse cell at row {row}, col {col}: {source}")]
+    CellParse {
+        row: u32,
+        col: u32,
+        #[source]
+        source: Box<KimiError>,
+    },
+}
+
+impl From<serde_json::Error> for KimiError {
+    /// A failure of the underlying reader or writer, such as a closed pipe,
+    /// is an I/O error rather than a JSON one
+    fn from(e: serde_json::Error) -> Self {
+        if e.is_io() {
+            KimiError::Io(e.into())
+        } else {
+            KimiError::Json(e)
+        }
+    }
+}
@@ -0,0 +1,37 @@
+//! Exporters to formats other than JSON
+
+#[cfg(feature = "arrow")]
+mod arrow;
+#[cfg(any(feature = "bincode", feature = "msgpack", feature = "cbor"))]
+mod binary;
+mod csv;
+mod dot;
+mod freemind;
+mod hierarchy;
+mod html;
+mod ltree;
+mod markdown;
+mod ndjson;
+#[cfg(feature = "ods")]
+mod ods;
+mod opml;
+#[cfg(feature = "protobuf")]
+mod protobuf;
+mod skos;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod toml;
+mod xlsx;
+mod yaml;
+
+#[cfg(feature = "arrow")]
+pub use arrow::arrow_schema;
+pub use markdown::MarkdownStyle;
+
+/// Escape text for XML and HTML content and attribute values
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,118 @@
+//! Arrow and Parquet export of the flat table, see `to_csv`
+//!
+//! Columns use ASCII names (`major_id`, `major_name`, …) so that the output
+//! loads cleanly into Spark or Polars; empty cells are null.
+
+use crate::{CategoryTree, KimiError};
+use arrow_array::{ArrayRef, BooleanArray, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+use std::sync::Arc;
+
+const COLUMNS: [&str; 11] = [
+    "major_id",
+    "major_name",
+    "middle_id",
+    "middle_name",
+    "minor_id",
+    "minor_name",
+    "detail_id",
+    "detail_name",
+    "gbm_code",
+    "is_green",
+    "is_digital",
+];
+
+/// Arrow schema of `CategoryTree::to_record_batch`
+pub fn arrow_schema() -> Schema {
+    let fields = COLUMNS.iter().enumerate().map(|(i, name)| {
+        let data_type = if i < 9 {
+            DataType::Utf8
+        } else {
+            DataType::Boolean
+        };
+        Field::new(*name, data_type, true)
+    });
+    Schema::new(fields.collect::<Vec<_>>())
+}
+
+impl CategoryTree {
+    /// The flat table as a single Arrow record batch, one row per leaf
+    pub fn to_record_batch(&self) -> Result<RecordBatch, KimiError> {
+        let mut strings: [Vec<Option<String>>; 9] = Default::default();
+        let mut is_green = Vec::new();
+        let mut is_digital = Vec::new();
+        for row in self.flat_rows() {
+            for (column, value) in strings.iter_mut().zip(row.columns) {
+                column.push(value);
+            }
+            is_green.push(row.is_green);
+            is_digital.push(row.is_digital);
+        }
+        let mut arrays = strings
+            .into_iter()
+            .map(|column| Arc::new(StringArray::from(column)) as ArrayRef)
+            .collect::<Vec<_>>();
+        arrays.push(Arc::new(BooleanArray::from(is_green)));
+        arrays.push(Arc::new(BooleanArray::from(is_digital)));
+        Ok(RecordBatch::try_new(Arc::new(arrow_schema()), arrays)?)
+    }
+
+    /// Write the flat table as a Parquet file to `w`
+    #[cfg(feature = "parquet")]
+    pub fn write_parquet<W: std::io::Write + Send>(&self, w: W) -> Result<(), KimiError> {
+        let batch = self.to_record_batch()?;
+        let mut writer = parquet::arrow::ArrowWriter::try_new(w, batch.schema(), None)?;
+        writer.write(&batch)?;
+        writer.close()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::Array;
+
+    fn tree() -> CategoryTree {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column(
+            "2(GBM20000)专业技术人员\n2-02(GBM20200)工程技术人员\n2-02-02(GBM20202)测绘和地理信息工程技术人员\n2-02-02-01大地测量工程技术人员L/S\n2-02-02-02工程测量工程技术人员S",
+        )
+        .unwrap();
+        tree
+    }
+
+    #[test]
+    fn test_record_batch() {
+        let batch = tree().to_record_batch().unwrap();
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.num_columns(), COLUMNS.len());
+        let names = batch
+            .column_by_name("detail_name")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(names.value(0), "大地测量工程技术人员");
+        let green = batch
+            .column_by_name("is_green")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<BooleanArray>()
+            .unwrap();
+        assert!(green.value(0));
+        assert!(!green.value(1));
+        assert_eq!(green.null_count(), 0);
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_parquet() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+
+        let mut buf = Vec::new();
+        tree().write_parquet(&mut buf).unwrap();
+        let reader = SerializedFileReader::new(bytes::Bytes::from(buf)).unwrap();
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 2);
+    }
+}
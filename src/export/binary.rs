@@ -0,0 +1,96 @@
+//! Compact binary encodings for caching a parsed tree
+//!
+//! The derived `level` of categories is only written to human-readable
+//! formats, so these encodings carry exactly the stored fields.
+
+use crate::{CategoryTree, KimiError};
+
+impl CategoryTree {
+    /// Encode the tree with bincode
+    #[cfg(feature = "bincode")]
+    pub fn to_bytes(&self) -> Result<Vec<u8>, KimiError> {
+        bincode::serde::encode_to_vec(self, bincode::config::standard())
+            .map_err(|e| KimiError::Binary(e.to_string()))
+    }
+
+    /// Decode a tree written by `to_bytes`
+    #[cfg(feature = "bincode")]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, KimiError> {
+        let (tree, _): (CategoryTree, _) =
+            bincode::serde::decode_from_slice(bytes, bincode::config::standard())
+                .map_err(|e| KimiError::Binary(e.to_string()))?;
+        tree.validate_ids()?;
+        Ok(tree)
+    }
+
+    /// Encode the tree as MessagePack
+    #[cfg(feature = "msgpack")]
+    pub fn to_msgpack(&self) -> Result<Vec<u8>, KimiError> {
+        rmp_serde::to_vec(self).map_err(|e| KimiError::Binary(e.to_string()))
+    }
+
+    /// Decode a tree written by `to_msgpack`
+    #[cfg(feature = "msgpack")]
+    pub fn from_msgpack(bytes: &[u8]) -> Result<Self, KimiError> {
+        let tree: CategoryTree =
+            rmp_serde::from_slice(bytes).map_err(|e| KimiError::Binary(e.to_string()))?;
+        tree.validate_ids()?;
+        Ok(tree)
+    }
+
+    /// Encode the tree as CBOR
+    #[cfg(feature = "cbor")]
+    pub fn to_cbor(&self) -> Result<Vec<u8>, KimiError> {
+        let mut buf = Vec::new();
+        ciborium::into_writer(self, &mut buf).map_err(|e| KimiError::Binary(e.to_string()))?;
+        Ok(buf)
+    }
+
+    /// Decode a tree written by `to_cbor`
+    #[cfg(feature = "cbor")]
+    pub fn from_cbor(bytes: &[u8]) -> Result<Self, KimiError> {
+        let tree: CategoryTree =
+            ciborium::from_reader(bytes).map_err(|e| KimiError::Binary(e.to_string()))?;
+        tree.validate_ids()?;
+        Ok(tree)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> CategoryTree {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column(
+            "1-01(GBM10100)机关负责人\n1-01-00-01中国共产党机关负责人\n2-02-02-01大地测量工程技术人员L/S",
+        )
+        .unwrap();
+        tree
+    }
+
+    #[cfg(feature = "bincode")]
+    #[test]
+    fn test_bincode_round_trip() {
+        let tree = tree();
+        let bytes = tree.to_bytes().unwrap();
+        assert_eq!(CategoryTree::from_bytes(&bytes).unwrap(), tree);
+        assert!(CategoryTree::from_bytes(&bytes[..bytes.len() / 2]).is_err());
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn test_msgpack_round_trip() {
+        let tree = tree();
+        let bytes = tree.to_msgpack().unwrap();
+        assert_eq!(CategoryTree::from_msgpack(&bytes).unwrap(), tree);
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn test_cbor_round_trip() {
+        let tree = tree();
+        let bytes = tree.to_cbor().unwrap();
+        assert_eq!(CategoryTree::from_cbor(&bytes).unwrap(), tree);
+    }
+}
@@ -0,0 +1,105 @@
+use crate::{CategoryTree, KimiError, Level};
+use std::io::Write;
+
+pub(super) const HEADER: [&str; 11] = [
+    "大类代码",
+    "大类名称",
+    "中类代码",
+    "中类名称",
+    "小类代码",
+    "小类名称",
+    "细类代码",
+    "细类名称",
+    "GBM代码",
+    "绿色职业",
+    "数字职业",
+];
+
+impl CategoryTree {
+    /// Flat CSV with one row per leaf (usually 细类) and one id/name column
+    /// pair per level
+    ///
+    /// The GBM column holds the code of the most specific category in the
+    /// row that has one, which is the 小类 code for most 细类.
+    pub fn to_csv(&self) -> Result<String, KimiError> {
+        let mut buf = Vec::new();
+        self.write_csv(&mut buf)?;
+        Ok(String::from_utf8(buf).unwrap())
+    }
+
+    /// Write the flat CSV table to `w`, see `to_csv`
+    pub fn write_csv<W: Write>(&self, w: W) -> Result<(), KimiError> {
+        let mut writer = csv::Writer::from_writer(w);
+        writer.write_record(HEADER)?;
+        for row in self.flat_rows() {
+            let flag = |f: Option<bool>| f.map(|f| f.to_string()).unwrap_or_default();
+            let record = row
+                .columns
+                .into_iter()
+                .map(Option::unwrap_or_default)
+                .chain([flag(row.is_green), flag(row.is_digital)]);
+            writer.write_record(record)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Rows of the flat table shared by the CSV and Arrow exporters
+    pub(super) fn flat_rows(&self) -> impl Iterator<Item = FlatRow> + '_ {
+        self.leaves().map(|leaf| {
+            let mut columns: [Option<String>; 9] = Default::default();
+            let mut code = None;
+            let chain = leaf.ancestors.iter().copied().chain([leaf.category]);
+            for cat in chain {
+                if let Some(level) = cat.level() {
+                    let col = (level.depth() - 1) * 2;
+                    columns[col] = Some(cat.id.clone());
+                    columns[col + 1] = cat.desc.clone();
+                }
+                code = cat.code.as_ref().or(code);
+            }
+            columns[8] = code.cloned();
+            // flags only apply to 细类
+            let is_detail = leaf.category.level() == Some(Level::Detail);
+            FlatRow {
+                columns,
+                is_green: is_detail.then_some(leaf.category.is_green),
+                is_digital: is_detail.then_some(leaf.category.is_digital),
+            }
+        })
+    }
+}
+
+/// One leaf of the flat table: id/name per level, then the GBM code
+pub(super) struct FlatRow {
+    pub(super) columns: [Option<String>; 9],
+    pub(super) is_green: Option<bool>,
+    pub(super) is_digital: Option<bool>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv() {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column(
+            "2(GBM20000)专业技术人员\n2-02(GBM20200)工程技术人员\n2-02-02(GBM20202)测绘和地理信息工程技术人员\n2-02-02-01大地测量工程技术人员L/S\n2-02-02-02工程测量工程技术人员,测绘S",
+        )
+        .unwrap();
+        let csv = tree.to_csv().unwrap();
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[0],
+            "大类代码,大类名称,中类代码,中类名称,小类代码,小类名称,细类代码,细类名称,GBM代码,绿色职业,数字职业"
+        );
+        assert_eq!(
+            lines[1],
+            "2,专业技术人员,2-02,工程技术人员,2-02-02,测绘和地理信息工程技术人员,2-02-02-01,大地测量工程技术人员,GBM20202,true,true"
+        );
+        // fields containing commas are quoted
+        assert!(lines[2].contains(",\"工程测量工程技术人员,测绘\",GBM20202,false,true"));
+    }
+}
@@ -0,0 +1,61 @@
+use crate::CategoryTree;
+use std::fmt::Write;
+
+impl CategoryTree {
+    /// GraphViz digraph of the hierarchy, one node per id labelled with its
+    /// name, down to `max_depth` levels (e.g. `Some(2)` for 大类 and 中类)
+    pub fn to_dot(&self, max_depth: Option<usize>) -> String {
+        let mut out = String::from("digraph kimi {\n    rankdir=LR;\n    node [shape=box];\n");
+        self.dot_inner(&mut out, "", max_depth.unwrap_or(usize::MAX));
+        out.push_str("}\n");
+        out
+    }
+
+    fn dot_inner(&self, out: &mut String, prefix: &str, depth_left: usize) {
+        if depth_left == 0 {
+            return;
+        }
+        for (key, child) in &self.children {
+            let id = if prefix.is_empty() {
+                key.clone()
+            } else {
+                format!("{}-{}", prefix, key)
+            };
+            let label = match child.items.first().and_then(|c| c.desc.as_deref()) {
+                Some(desc) => format!("{}\\n{}", id, escape(desc)),
+                None => id.clone(),
+            };
+            writeln!(out, "    \"{}\" [label=\"{}\"];", id, label).unwrap();
+            if !prefix.is_empty() {
+                writeln!(out, "    \"{}\" -> \"{}\";", prefix, id).unwrap();
+            }
+            child.dot_inner(out, &id, depth_left - 1);
+        }
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dot() {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column("1-01(GBM10100)机关负责人\n1-01-00-01中国共产党机关\"负责人\"")
+            .unwrap();
+        let dot = tree.to_dot(None);
+        assert!(dot.starts_with("digraph kimi {"));
+        assert!(dot.contains("    \"1\" [label=\"1\"];\n"));
+        assert!(dot.contains("    \"1-01\" [label=\"1-01\\n机关负责人\"];\n"));
+        assert!(dot.contains("    \"1-01\" -> \"1-01-00\";\n"));
+        assert!(dot.contains("中国共产党机关\\\"负责人\\\""));
+
+        let shallow = tree.to_dot(Some(2));
+        assert!(shallow.contains("\"1\" -> \"1-01\""));
+        assert!(!shallow.contains("1-01-00"));
+    }
+}
@@ -0,0 +1,72 @@
+use super::escape_xml;
+use crate::CategoryTree;
+use std::fmt::Write;
+
+impl CategoryTree {
+    /// FreeMind `.mm` mind map (also opened by XMind and Freeplane) with
+    /// `root_text` at the centre
+    ///
+    /// Nodes below 大类 start folded. The GBM code and L/S marker become
+    /// node attributes.
+    pub fn to_freemind(&self, root_text: &str) -> String {
+        let mut out = String::from("<map version=\"1.0.1\">\n");
+        writeln!(out, "<node TEXT=\"{}\">", escape_xml(root_text)).unwrap();
+        self.freemind_inner(&mut out, "");
+        out.push_str("</node>\n</map>\n");
+        out
+    }
+
+    fn freemind_inner(&self, out: &mut String, prefix: &str) {
+        for (key, child) in &self.children {
+            let id = if prefix.is_empty() {
+                key.clone()
+            } else {
+                format!("{}-{}", prefix, key)
+            };
+            let first = child.items.first();
+            let text = match first.and_then(|c| c.desc.as_deref()) {
+                Some(desc) => format!("{} {}", id, desc),
+                None => id.clone(),
+            };
+            let folded = !prefix.is_empty() && !child.children.is_empty();
+            write!(out, "<node ID=\"ID_{}\" TEXT=\"{}\"", id, escape_xml(&text)).unwrap();
+            if folded {
+                out.push_str(" FOLDED=\"true\"");
+            }
+            out.push_str(">\n");
+            if let Some(code) = first.and_then(|c| c.code.as_deref()) {
+                writeln!(
+                    out,
+                    "<attribute NAME=\"GBM\" VALUE=\"{}\"/>",
+                    escape_xml(code)
+                )
+                .unwrap();
+            }
+            if let Some(marker) = first.and_then(|c| c.marker()) {
+                writeln!(out, "<attribute NAME=\"标识\" VALUE=\"{}\"/>", marker).unwrap();
+            }
+            child.freemind_inner(out, &id);
+            out.push_str("</node>\n");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_freemind() {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column("1-01(GBM10100)机关负责人\n1-01-00-01中国共产党机关负责人L")
+            .unwrap();
+        let mm = tree.to_freemind("职业分类大典");
+        assert!(mm.starts_with("<map version=\"1.0.1\">\n<node TEXT=\"职业分类大典\">\n"));
+        assert!(mm.contains(
+            "<node ID=\"ID_1-01\" TEXT=\"1-01 机关负责人\" FOLDED=\"true\">\n<attribute NAME=\"GBM\" VALUE=\"GBM10100\"/>\n"
+        ));
+        assert!(mm.contains("<node ID=\"ID_1\" TEXT=\"1\">\n"));
+        assert!(mm.contains("<attribute NAME=\"标识\" VALUE=\"L\"/>"));
+        assert_eq!(mm.matches("<node ").count(), mm.matches("</node>").count());
+    }
+}
@@ -0,0 +1,88 @@
+use crate::CategoryTree;
+use serde::Serialize;
+
+/// Node in the `{name, value, children}` shape of D3 flare layouts and
+/// ECharts tree/treemap series
+#[derive(Serialize)]
+struct HierarchyNode {
+    name: String,
+    id: String,
+    value: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    children: Vec<HierarchyNode>,
+}
+
+impl CategoryTree {
+    /// JSON for D3 `d3.hierarchy` and ECharts tree/treemap charts
+    ///
+    /// The root is named `root_name`, every other node is named after its
+    /// category, and `value` is the number of leaves (usually 细类) below a
+    /// node, 1 for a leaf.
+    pub fn to_hierarchy_json(&self, root_name: &str) -> String {
+        let children = self.hierarchy_children("");
+        let root = HierarchyNode {
+            name: root_name.to_string(),
+            id: String::new(),
+            value: children.iter().map(|c| c.value).sum(),
+            children,
+        };
+        serde_json::to_string(&root).unwrap()
+    }
+
+    fn hierarchy_children(&self, prefix: &str) -> Vec<HierarchyNode> {
+        self.children
+            .iter()
+            .map(|(key, child)| {
+                let id = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}-{}", prefix, key)
+                };
+                let children = child.hierarchy_children(&id);
+                let value = if children.is_empty() {
+                    child.items.len().max(1)
+                } else {
+                    children.iter().map(|c| c.value).sum()
+                };
+                let name = child
+                    .items
+                    .first()
+                    .and_then(|c| c.desc.clone())
+                    .unwrap_or_else(|| id.clone());
+                HierarchyNode {
+                    name,
+                    id,
+                    value,
+                    children,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    #[test]
+    fn test_hierarchy_json() {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column(
+            "1-01(GBM10100)机关负责人\n1-01-00-01中国共产党机关负责人\n1-01-00-02中国共产党基层组织负责人\n1-02(GBM10200)国家机关负责人",
+        )
+        .unwrap();
+        let value: Value = serde_json::from_str(&tree.to_hierarchy_json("职业分类")).unwrap();
+        assert_eq!(value["name"], "职业分类");
+        assert_eq!(value["value"], 3);
+        let major = &value["children"][0];
+        assert_eq!(major["name"], "1");
+        let middle = &major["children"][0];
+        assert_eq!(middle["name"], "机关负责人");
+        assert_eq!(middle["value"], 2);
+        let leaf = &middle["children"][0]["children"][1];
+        assert_eq!(leaf["id"], "1-01-00-02");
+        assert_eq!(leaf["value"], 1);
+        assert!(leaf.get("children").is_none());
+    }
+}
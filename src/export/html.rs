@@ -0,0 +1,125 @@
+use super::escape_xml;
+use crate::{Category, CategoryTree};
+use std::fmt::Write;
+
+const HEAD: &str = r##"<!DOCTYPE html>
+<html lang="zh">
+<head>
+<meta charset="utf-8">
+<title>职业分类</title>
+<style>
+body { font-family: sans-serif; margin: 2em; }
+ul { list-style: none; padding-left: 1.5em; margin: 0; }
+summary { cursor: pointer; }
+.id { color: #666; font-family: monospace; }
+.code { color: #999; }
+.marker { color: #2a7; font-weight: bold; }
+.hidden { display: none; }
+#filter { width: 30em; padding: 0.3em; margin-bottom: 1em; }
+</style>
+</head>
+<body>
+<input id="filter" type="search" placeholder="筛选 (id, 名称, GBM 代码)">
+<ul id="tree">
+"##;
+
+const TAIL: &str = r##"</ul>
+<script>
+document.getElementById("filter").addEventListener("input", function () {
+  var query = this.value.trim().toLowerCase();
+  var items = Array.from(document.querySelectorAll("#tree li")).reverse();
+  items.forEach(function (li) {
+    var label = li.querySelector(":scope > details > summary, :scope > span");
+    var self = label.textContent.toLowerCase().indexOf(query) >= 0;
+    var child = li.querySelector(":scope > details > ul > li:not(.hidden)") !== null;
+    li.classList.toggle("hidden", query !== "" && !self && !child);
+    var details = li.querySelector(":scope > details");
+    if (details) details.open = query !== "" && child;
+  });
+});
+</script>
+</body>
+</html>
+"##;
+
+impl CategoryTree {
+    /// Standalone HTML page with a collapsible tree and a search box that
+    /// filters it in the browser
+    pub fn to_html(&self) -> String {
+        let mut out = String::from(HEAD);
+        self.html_inner(&mut out, "", 1);
+        out.push_str(TAIL);
+        out
+    }
+
+    fn html_inner(&self, out: &mut String, prefix: &str, depth: usize) {
+        let indent = "  ".repeat(depth);
+        for (key, child) in &self.children {
+            let id = if prefix.is_empty() {
+                key.clone()
+            } else {
+                format!("{}-{}", prefix, key)
+            };
+            let (first, rest) = match child.items.split_first() {
+                Some((first, rest)) => (label(first), rest),
+                None => (format!("<span class=\"id\">{}</span>", id), &[][..]),
+            };
+            if child.children.is_empty() {
+                writeln!(out, "{}<li><span>{}</span></li>", indent, first).unwrap();
+            } else {
+                writeln!(
+                    out,
+                    "{}<li><details><summary>{}</summary><ul>",
+                    indent, first
+                )
+                .unwrap();
+                child.html_inner(out, &id, depth + 1);
+                writeln!(out, "{}</ul></details></li>", indent).unwrap();
+            }
+            // repeated categories with the same id
+            for cat in rest {
+                writeln!(out, "{}<li><span>{}</span></li>", indent, label(cat)).unwrap();
+            }
+        }
+    }
+}
+
+fn label(cat: &Category) -> String {
+    let mut label = format!(
+        "<span class=\"id\">{}</span> {}",
+        escape_xml(&cat.id),
+        escape_xml(cat.desc.as_deref().unwrap_or(""))
+    );
+    if let Some(code) = &cat.code {
+        write!(label, " <span class=\"code\">{}</span>", escape_xml(code)).unwrap();
+    }
+    if let Some(marker) = cat.marker() {
+        write!(label, " <span class=\"marker\">{}</span>", marker).unwrap();
+    }
+    label
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html() {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column("1-01(GBM10100)机关&党组负责人\n1-01-00-01中国共产党机关负责人S")
+            .unwrap();
+        let html = tree.to_html();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.trim_end().ends_with("</html>"));
+        assert!(html.contains(
+            "<summary><span class=\"id\">1-01</span> 机关&amp;党组负责人 <span class=\"code\">GBM10100</span></summary>"
+        ));
+        assert!(html.contains(
+            "<li><span><span class=\"id\">1-01-00-01</span> 中国共产党机关负责人 <span class=\"marker\">S</span></span></li>"
+        ));
+        assert_eq!(
+            html.matches("<details>").count(),
+            html.matches("</details>").count()
+        );
+    }
+}
@@ -0,0 +1,91 @@
+//! PostgreSQL script using the `ltree` extension
+//!
+//! Each category becomes a row whose `path` is its id with dots, e.g.
+//! `1-01-00-01` → `1.01.00.01`, so the hierarchy can be queried with ltree
+//! operators: `SELECT * FROM categories WHERE path <@ '2.02'`.
+
+use crate::{CategoryTree, KimiError};
+use std::io::Write;
+
+const CREATE: &str = "\
+CREATE EXTENSION IF NOT EXISTS ltree;
+
+CREATE TABLE categories (
+    id text NOT NULL,
+    path ltree NOT NULL,
+    code text,
+    name text,
+    level smallint,
+    is_green boolean NOT NULL DEFAULT false,
+    is_digital boolean NOT NULL DEFAULT false
+);
+";
+
+const INDEXES: &str = "\
+CREATE INDEX categories_path_idx ON categories USING GIST (path);
+CREATE INDEX categories_id_idx ON categories (id);
+";
+
+impl CategoryTree {
+    /// SQL script that creates and fills a `categories` table with ltree
+    /// paths
+    pub fn to_ltree_sql(&self) -> String {
+        let mut buf = Vec::new();
+        self.write_ltree_sql(&mut buf).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    /// Write the ltree SQL script to `w`, see `to_ltree_sql`
+    pub fn write_ltree_sql<W: Write>(&self, mut w: W) -> Result<(), KimiError> {
+        writeln!(w, "BEGIN;\n")?;
+        writeln!(w, "{}", CREATE)?;
+        for (path, cat) in self {
+            writeln!(
+                w,
+                "INSERT INTO categories (id, path, code, name, level, is_green, is_digital) \
+                 VALUES ({}, {}, {}, {}, {}, {}, {});",
+                quote(Some(&cat.id)),
+                quote(Some(&path.join("."))),
+                quote(cat.code.as_deref()),
+                quote(cat.desc.as_deref()),
+                cat.level()
+                    .map_or("NULL".to_string(), |level| level.depth().to_string()),
+                cat.is_green,
+                cat.is_digital,
+            )?;
+        }
+        writeln!(w, "\n{}", INDEXES)?;
+        writeln!(w, "COMMIT;")?;
+        Ok(())
+    }
+}
+
+/// SQL string literal, or NULL
+fn quote(s: Option<&str>) -> String {
+    match s {
+        Some(s) => format!("'{}'", s.replace('\'', "''")),
+        None => "NULL".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ltree_sql() {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column("1-01(GBM10100)机关负责人\n1-01-00-01中国共产党机关'负责人'S")
+            .unwrap();
+        let sql = tree.to_ltree_sql();
+        assert!(sql.starts_with("BEGIN;"));
+        assert!(sql.contains("CREATE EXTENSION IF NOT EXISTS ltree;"));
+        assert!(
+            sql.contains("VALUES ('1-01', '1.01', 'GBM10100', '机关负责人', 2, false, false);")
+        );
+        assert!(sql.contains(
+            "VALUES ('1-01-00-01', '1.01.00.01', NULL, '中国共产党机关''负责人''', 4, false, true);"
+        ));
+        assert!(sql.trim_end().ends_with("COMMIT;"));
+    }
+}
@@ -0,0 +1,88 @@
+use crate::{Category, CategoryTree};
+use std::fmt::Write;
+
+/// Layout of `CategoryTree::to_markdown`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MarkdownStyle {
+    /// One bullet per category, indented by depth
+    #[default]
+    List,
+    /// `#`, `##`, `###` headings for 大类, 中类, 小类 and bullets for 细类
+    Headings,
+}
+
+impl CategoryTree {
+    /// Markdown rendering of the tree for reports and READMEs
+    pub fn to_markdown(&self, style: MarkdownStyle) -> String {
+        let mut out = String::new();
+        for (path, cat) in self {
+            let depth = path.len();
+            match style {
+                MarkdownStyle::Headings if depth < 4 => {
+                    // blank line after the bullets of the previous 小类
+                    if !out.is_empty() && !out.ends_with("\n\n") {
+                        out.push('\n');
+                    }
+                    writeln!(out, "{} {}\n", "#".repeat(depth), line(cat)).unwrap();
+                }
+                MarkdownStyle::Headings => writeln!(out, "- {}", line(cat)).unwrap(),
+                MarkdownStyle::List => {
+                    writeln!(out, "{}- {}", "  ".repeat(depth - 1), line(cat)).unwrap()
+                }
+            }
+        }
+        out
+    }
+}
+
+fn line(cat: &Category) -> String {
+    let mut line = format!("{} {}", cat.id, escape(cat.desc.as_deref().unwrap_or("")));
+    if let Some(code) = &cat.code {
+        write!(line, " ({})", code).unwrap();
+    }
+    if let Some(marker) = cat.marker() {
+        write!(line, " {}", marker).unwrap();
+    }
+    line
+}
+
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '\\' | '*' | '_' | '`' | '[' | ']' | '#') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> CategoryTree {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column(
+            "2(GBM20000)专业技术人员\n2-02(GBM20200)工程技术人员\n2-02-02(GBM20202)测绘和地理信息工程技术人员\n2-02-02-01大地测量工程技术人员L/S",
+        )
+        .unwrap();
+        tree
+    }
+
+    #[test]
+    fn test_markdown_list() {
+        assert_eq!(
+            tree().to_markdown(MarkdownStyle::List),
+            "- 2 专业技术人员 (GBM20000)\n  - 2-02 工程技术人员 (GBM20200)\n    - 2-02-02 测绘和地理信息工程技术人员 (GBM20202)\n      - 2-02-02-01 大地测量工程技术人员 L/S\n"
+        );
+    }
+
+    #[test]
+    fn test_markdown_headings() {
+        assert_eq!(
+            tree().to_markdown(MarkdownStyle::Headings),
+            "# 2 专业技术人员 (GBM20000)\n\n## 2-02 工程技术人员 (GBM20200)\n\n### 2-02-02 测绘和地理信息工程技术人员 (GBM20202)\n\n- 2-02-02-01 大地测量工程技术人员 L/S\n"
+        );
+    }
+}
@@ -0,0 +1,55 @@
+use crate::{Category, CategoryTree, KimiError};
+use serde::Serialize;
+use std::io::Write;
+
+#[derive(Serialize)]
+struct Record<'a> {
+    #[serde(flatten)]
+    category: &'a Category,
+    path: Vec<&'a str>,
+    parent: Option<String>,
+}
+
+impl CategoryTree {
+    /// Newline-delimited JSON: one object per category in depth-first
+    /// order, with its `path` segments, `level` and `parent` id
+    pub fn to_ndjson<W: Write>(&self, mut w: W) -> Result<(), KimiError> {
+        for (path, cat) in self {
+            let parent = (path.len() > 1).then(|| path[..path.len() - 1].join("-"));
+            let record = Record {
+                category: cat,
+                path,
+                parent,
+            };
+            serde_json::to_writer(&mut w, &record)?;
+            w.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ndjson() {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column("1-01(GBM10100)机关负责人\n1-01-00-01中国共产党机关负责人")
+            .unwrap();
+        let mut buf = Vec::new();
+        tree.to_ndjson(&mut buf).unwrap();
+        let text = String::from_utf8(buf).unwrap();
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+
+        let first: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(first["id"], "1-01");
+        assert_eq!(first["level"], "middle");
+        assert_eq!(first["path"], serde_json::json!(["1", "01"]));
+        assert_eq!(first["parent"], "1");
+
+        let second: Category = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(second.desc.as_deref(), Some("中国共产党机关负责人"));
+    }
+}
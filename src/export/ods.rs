@@ -0,0 +1,67 @@
+use super::csv::HEADER;
+use crate::{CategoryTree, KimiError};
+use spreadsheet_ods::{Sheet, WorkBook};
+use std::path::Path;
+
+impl CategoryTree {
+    /// Write the flat table of `to_csv` as an OpenDocument spreadsheet
+    pub fn export_ods(&self, path: impl AsRef<Path>) -> Result<(), KimiError> {
+        spreadsheet_ods::write_ods(&mut self.to_ods_workbook(), path)?;
+        Ok(())
+    }
+
+    /// The `.ods` file of `export_ods` as bytes
+    pub fn to_ods_bytes(&self) -> Result<Vec<u8>, KimiError> {
+        Ok(spreadsheet_ods::write_ods_buf(
+            &mut self.to_ods_workbook(),
+            Vec::new(),
+        )?)
+    }
+
+    fn to_ods_workbook(&self) -> WorkBook {
+        let mut sheet = Sheet::new("职业分类");
+        for (col, title) in HEADER.iter().enumerate() {
+            sheet.set_value(0, col as u32, *title);
+        }
+        for (row, flat) in self.flat_rows().enumerate() {
+            let row = row as u32 + 1;
+            for (col, value) in flat.columns.into_iter().enumerate() {
+                if let Some(value) = value {
+                    sheet.set_value(row, col as u32, value);
+                }
+            }
+            if let Some(is_green) = flat.is_green {
+                sheet.set_value(row, 9, is_green);
+            }
+            if let Some(is_digital) = flat.is_digital {
+                sheet.set_value(row, 10, is_digital);
+            }
+        }
+        let mut book = WorkBook::new_empty();
+        book.push_sheet(sheet);
+        book
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spreadsheet_ods::Value;
+
+    #[test]
+    fn test_ods() {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column(
+            "2(GBM20000)专业技术人员\n2-02(GBM20200)工程技术人员\n2-02-02(GBM20202)测绘和地理信息工程技术人员\n2-02-02-01大地测量工程技术人员L/S",
+        )
+        .unwrap();
+        let bytes = tree.to_ods_bytes().unwrap();
+        let book = spreadsheet_ods::read_ods_buf(&bytes).unwrap();
+        let sheet = book.sheet(0);
+        assert_eq!(sheet.name(), "职业分类");
+        assert_eq!(sheet.value(0, 0).as_str_or(""), "大类代码");
+        assert_eq!(sheet.value(1, 6).as_str_or(""), "2-02-02-01");
+        assert_eq!(sheet.value(1, 8).as_str_or(""), "GBM20202");
+        assert_eq!(*sheet.value(1, 9), Value::Boolean(true));
+    }
+}
@@ -0,0 +1,89 @@
+use super::escape_xml;
+use crate::{Category, CategoryTree};
+use std::fmt::Write;
+
+impl CategoryTree {
+    /// OPML 2.0 outline with one nested `<outline>` per category; the id
+    /// and GBM code are kept as `id` and `code` attributes
+    pub fn to_opml(&self, title: &str) -> String {
+        let mut out =
+            String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<opml version=\"2.0\">\n");
+        writeln!(
+            out,
+            "  <head>\n    <title>{}</title>\n  </head>",
+            escape_xml(title)
+        )
+        .unwrap();
+        out.push_str("  <body>\n");
+        self.opml_inner(&mut out, "", 2);
+        out.push_str("  </body>\n</opml>\n");
+        out
+    }
+
+    fn opml_inner(&self, out: &mut String, prefix: &str, depth: usize) {
+        let indent = "  ".repeat(depth);
+        for (key, child) in &self.children {
+            let id = if prefix.is_empty() {
+                key.clone()
+            } else {
+                format!("{}-{}", prefix, key)
+            };
+            let (attrs, rest) = match child.items.split_first() {
+                Some((first, rest)) => (attributes(first), rest),
+                None => (format!("text=\"{}\" id=\"{}\"", id, id), &[][..]),
+            };
+            if child.children.is_empty() {
+                writeln!(out, "{}<outline {}/>", indent, attrs).unwrap();
+            } else {
+                writeln!(out, "{}<outline {}>", indent, attrs).unwrap();
+                child.opml_inner(out, &id, depth + 1);
+                writeln!(out, "{}</outline>", indent).unwrap();
+            }
+            // repeated categories with the same id
+            for cat in rest {
+                writeln!(out, "{}<outline {}/>", indent, attributes(cat)).unwrap();
+            }
+        }
+    }
+}
+
+fn attributes(cat: &Category) -> String {
+    let text = match &cat.desc {
+        Some(desc) => format!("{} {}", cat.id, desc),
+        None => cat.id.clone(),
+    };
+    let mut attrs = format!(
+        "text=\"{}\" id=\"{}\"",
+        escape_xml(&text),
+        escape_xml(&cat.id)
+    );
+    if let Some(code) = &cat.code {
+        write!(attrs, " code=\"{}\"", escape_xml(code)).unwrap();
+    }
+    if let Some(marker) = cat.marker() {
+        write!(attrs, " marker=\"{}\"", marker).unwrap();
+    }
+    attrs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opml() {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column("1-01(GBM10100)机关负责人\n1-01-00-01中国共产党机关负责人S")
+            .unwrap();
+        let opml = tree.to_opml("职业分类");
+        assert!(opml.contains("<title>职业分类</title>"));
+        assert!(opml.contains("    <outline text=\"1\" id=\"1\">\n      <outline text=\"1-01 机关负责人\" id=\"1-01\" code=\"GBM10100\">\n"));
+        assert!(opml.contains(
+            "<outline text=\"1-01-00-01 中国共产党机关负责人\" id=\"1-01-00-01\" marker=\"S\"/>"
+        ));
+        assert_eq!(
+            opml.matches("<outline ").count() - opml.matches("/>").count(),
+            opml.matches("</outline>").count()
+        );
+    }
+}
@@ -0,0 +1,155 @@
+//! Protocol Buffers encoding, see `proto/kimi.proto`
+//!
+//! The message types are written by hand to match the schema, so building
+//! does not need `protoc`. `level` is only written for consumers in other
+//! languages; on decoding it is derived from the id as usual.
+
+use crate::{Category, CategoryTree, CellRef, KimiError, Tree};
+use prost::Message;
+
+#[derive(Clone, PartialEq, Message)]
+struct ProtoCategory {
+    #[prost(string, tag = "1")]
+    id: String,
+    #[prost(string, optional, tag = "2")]
+    code: Option<String>,
+    #[prost(string, optional, tag = "3")]
+    desc: Option<String>,
+    #[prost(int32, tag = "4")]
+    level: i32,
+    #[prost(bool, tag = "5")]
+    is_green: bool,
+    #[prost(bool, tag = "6")]
+    is_digital: bool,
+    #[prost(uint32, optional, tag = "7")]
+    confidence: Option<u32>,
+    #[prost(message, optional, tag = "8")]
+    source: Option<ProtoCellRef>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct ProtoCellRef {
+    #[prost(string, tag = "1")]
+    sheet: String,
+    #[prost(uint32, tag = "2")]
+    row: u32,
+    #[prost(uint32, tag = "3")]
+    col: u32,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct ProtoNode {
+    #[prost(string, tag = "1")]
+    key: String,
+    #[prost(message, repeated, tag = "2")]
+    categories: Vec<ProtoCategory>,
+    #[prost(message, repeated, tag = "3")]
+    children: Vec<ProtoNode>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct ProtoTree {
+    #[prost(message, repeated, tag = "1")]
+    children: Vec<ProtoNode>,
+}
+
+impl CategoryTree {
+    /// Encode the tree as a `kimi.CategoryTree` protobuf message
+    pub fn to_protobuf_bytes(&self) -> Vec<u8> {
+        ProtoTree {
+            children: self.proto_children(),
+        }
+        .encode_to_vec()
+    }
+
+    /// Decode a `kimi.CategoryTree` protobuf message
+    pub fn from_protobuf_bytes(bytes: &[u8]) -> Result<Self, KimiError> {
+        let proto = ProtoTree::decode(bytes)?;
+        let mut tree = CategoryTree::new();
+        for node in proto.children {
+            let (key, child) = from_proto(node);
+            tree.children.insert(key, child);
+        }
+        tree.validate_ids()?;
+        Ok(tree)
+    }
+
+    fn proto_children(&self) -> Vec<ProtoNode> {
+        self.children
+            .iter()
+            .map(|(key, child)| ProtoNode {
+                key: key.clone(),
+                categories: child
+                    .items
+                    .iter()
+                    .map(|cat| ProtoCategory {
+                        id: cat.id.clone(),
+                        code: cat.code.clone(),
+                        desc: cat.desc.clone(),
+                        level: cat.level().map_or(0, |level| level.depth() as i32),
+                        is_green: cat.is_green,
+                        is_digital: cat.is_digital,
+                        confidence: cat.confidence.map(u32::from),
+                        source: cat.source.as_ref().map(|source| ProtoCellRef {
+                            sheet: source.sheet.clone(),
+                            row: source.row,
+                            col: source.col,
+                        }),
+                    })
+                    .collect(),
+                children: child.proto_children(),
+            })
+            .collect()
+    }
+}
+
+fn from_proto(node: ProtoNode) -> (String, CategoryTree) {
+    let mut tree = Tree::new();
+    tree.items = node
+        .categories
+        .into_iter()
+        .map(|cat| Category {
+            id: cat.id,
+            code: cat.code,
+            desc: cat.desc,
+            is_green: cat.is_green,
+            is_digital: cat.is_digital,
+            // a confidence out of 0..=100 is not one written by the crate
+            confidence: cat
+                .confidence
+                .and_then(|confidence| u8::try_from(confidence).ok()),
+            source: cat.source.map(|source| CellRef {
+                sheet: source.sheet,
+                row: source.row,
+                col: source.col,
+            }),
+        })
+        .collect();
+    for child in node.children {
+        let (key, child) = from_proto(child);
+        tree.children.insert(key, child);
+    }
+    (node.key, tree)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protobuf_round_trip() {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column("1-01(GBM10100)机关负责人\n1-01-00-01中国共产党机关负责人L/S")
+            .unwrap();
+        let cat = &mut tree.children["1"].children["01"].children["00"].children["01"].items[0];
+        cat.confidence = Some(87);
+        cat.source = Some(CellRef {
+            sheet: "Sheet1".to_string(),
+            row: 12,
+            col: 5,
+        });
+        let bytes = tree.to_protobuf_bytes();
+        assert_eq!(CategoryTree::from_protobuf_bytes(&bytes).unwrap(), tree);
+        assert!(CategoryTree::from_protobuf_bytes(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
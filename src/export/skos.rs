@@ -0,0 +1,94 @@
+use crate::CategoryTree;
+use std::fmt::Write;
+
+impl CategoryTree {
+    /// SKOS concept scheme in Turtle, one `skos:Concept` per id under
+    /// `base_iri` (e.g. `https://example.org/occupation/` gives
+    /// `<https://example.org/occupation/1-01>`)
+    ///
+    /// `skos:prefLabel` is the name, `skos:notation` the GBM code, and
+    /// concepts are linked with `skos:broader`/`skos:narrower`; 大类 are the
+    /// top concepts of the scheme.
+    pub fn to_skos(&self, base_iri: &str) -> String {
+        let mut out = String::new();
+        out.push_str("@prefix skos: <http://www.w3.org/2004/02/skos/core#> .\n\n");
+        writeln!(out, "<{}> a skos:ConceptScheme ;", base_iri).unwrap();
+        out.push_str("    skos:prefLabel \"中华人民共和国职业分类大典\"@zh");
+        for key in self.children.keys() {
+            write!(out, " ;\n    skos:hasTopConcept <{}{}>", base_iri, key).unwrap();
+        }
+        out.push_str(" .\n");
+        self.skos_inner(&mut out, base_iri, "");
+        out
+    }
+
+    fn skos_inner(&self, out: &mut String, base_iri: &str, prefix: &str) {
+        for (key, child) in &self.children {
+            let id = if prefix.is_empty() {
+                key.clone()
+            } else {
+                format!("{}-{}", prefix, key)
+            };
+            writeln!(out, "\n<{}{}> a skos:Concept ;", base_iri, id).unwrap();
+            write!(out, "    skos:inScheme <{}>", base_iri).unwrap();
+            if prefix.is_empty() {
+                write!(out, " ;\n    skos:topConceptOf <{}>", base_iri).unwrap();
+            } else {
+                write!(out, " ;\n    skos:broader <{}{}>", base_iri, prefix).unwrap();
+            }
+            if let Some(cat) = child.items.first() {
+                if let Some(desc) = &cat.desc {
+                    write!(out, " ;\n    skos:prefLabel \"{}\"@zh", escape(desc)).unwrap();
+                }
+                if let Some(code) = &cat.code {
+                    write!(out, " ;\n    skos:notation \"{}\"", escape(code)).unwrap();
+                }
+            }
+            for grandchild in child.children.keys() {
+                write!(
+                    out,
+                    " ;\n    skos:narrower <{}{}-{}>",
+                    base_iri, id, grandchild
+                )
+                .unwrap();
+            }
+            out.push_str(" .\n");
+            child.skos_inner(out, base_iri, &id);
+        }
+    }
+}
+
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skos() {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column("1-01(GBM10100)机关负责人\n1-01-00-01中国共产党机关负责人")
+            .unwrap();
+        let ttl = tree.to_skos("http://example.org/occ/");
+        assert!(ttl.starts_with("@prefix skos:"));
+        assert!(ttl.contains("skos:hasTopConcept <http://example.org/occ/1> .\n"));
+        assert!(ttl.contains(
+            "<http://example.org/occ/1-01> a skos:Concept ;\n    skos:inScheme <http://example.org/occ/> ;\n    skos:broader <http://example.org/occ/1> ;\n    skos:prefLabel \"机关负责人\"@zh ;\n    skos:notation \"GBM10100\" ;\n    skos:narrower <http://example.org/occ/1-01-00> .\n"
+        ));
+        assert!(ttl.contains(
+            "<http://example.org/occ/1> a skos:Concept ;\n    skos:inScheme <http://example.org/occ/> ;\n    skos:topConceptOf <http://example.org/occ/> ;\n    skos:narrower <http://example.org/occ/1-01> .\n"
+        ));
+    }
+}
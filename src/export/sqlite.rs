@@ -0,0 +1,139 @@
+//! SQLite database export
+//!
+//! Three tables are written:
+//!
+//! - `categories`: one row per category with its `parent_id` and `level`
+//!   (1 = 大类 … 4 = 细类); ids are not unique when the 大典 repeats one
+//! - `category_children`: parent/child pairs of tree nodes
+//! - `category_closure`: every ancestor/descendant pair with the distance
+//!   between them, including each node with itself at depth 0, so
+//!   `SELECT descendant_id FROM category_closure WHERE ancestor_id = '2-02'`
+//!   lists the whole subtree
+
+use crate::{CategoryTree, KimiError};
+use rusqlite::{Connection, params};
+use std::path::Path;
+
+const SCHEMA: &str = "
+CREATE TABLE categories (
+    id TEXT NOT NULL,
+    code TEXT,
+    name TEXT,
+    level INTEGER,
+    is_green INTEGER NOT NULL,
+    is_digital INTEGER NOT NULL,
+    parent_id TEXT
+);
+CREATE INDEX categories_id ON categories (id);
+CREATE INDEX categories_code ON categories (code);
+CREATE TABLE category_children (
+    parent_id TEXT NOT NULL,
+    child_id TEXT NOT NULL,
+    PRIMARY KEY (parent_id, child_id)
+);
+CREATE TABLE category_closure (
+    ancestor_id TEXT NOT NULL,
+    descendant_id TEXT NOT NULL,
+    depth INTEGER NOT NULL,
+    PRIMARY KEY (ancestor_id, descendant_id)
+);
+CREATE INDEX category_closure_descendant ON category_closure (descendant_id);
+";
+
+impl CategoryTree {
+    /// Write the tree to a new SQLite database at `path`, see the module
+    /// docs for the tables
+    ///
+    /// Fails if the tables already exist.
+    pub fn export_sqlite(&self, path: impl AsRef<Path>) -> Result<(), KimiError> {
+        let mut conn = Connection::open(path)?;
+        self.write_sqlite(&mut conn)
+    }
+
+    /// Create the tables in an open connection and fill them in one
+    /// transaction
+    pub fn write_sqlite(&self, conn: &mut Connection) -> Result<(), KimiError> {
+        let tx = conn.transaction()?;
+        tx.execute_batch(SCHEMA)?;
+        {
+            let mut insert_category = tx.prepare(
+                "INSERT INTO categories (id, code, name, level, is_green, is_digital, parent_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            )?;
+            for (path, cat) in self {
+                let parent = (path.len() > 1).then(|| path[..path.len() - 1].join("-"));
+                insert_category.execute(params![
+                    cat.id,
+                    cat.code,
+                    cat.desc,
+                    cat.level().map(|level| level.depth() as i64),
+                    cat.is_green,
+                    cat.is_digital,
+                    parent,
+                ])?;
+            }
+
+            let mut insert_child =
+                tx.prepare("INSERT INTO category_children (parent_id, child_id) VALUES (?1, ?2)")?;
+            let mut insert_closure = tx.prepare(
+                "INSERT INTO category_closure (ancestor_id, descendant_id, depth)
+                 VALUES (?1, ?2, ?3)",
+            )?;
+            let mut stack = vec![(Vec::new(), self)];
+            while let Some((path, node)) = stack.pop() {
+                let id = path.join("-");
+                for (key, child) in node.children() {
+                    let mut child_path = path.clone();
+                    child_path.push(key);
+                    let child_id = child_path.join("-");
+                    if !path.is_empty() {
+                        insert_child.execute(params![id, child_id])?;
+                    }
+                    for depth in 0..child_path.len() {
+                        let ancestor = child_path[..child_path.len() - depth].join("-");
+                        insert_closure.execute(params![ancestor, child_id, depth as i64])?;
+                    }
+                    stack.push((child_path, child));
+                }
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sqlite() {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column(
+            "2(GBM20000)专业技术人员\n2-02(GBM20200)工程技术人员\n2-02-02(GBM20202)测绘和地理信息工程技术人员\n2-02-02-01大地测量工程技术人员L/S\n2-02-02-02工程测量工程技术人员S",
+        )
+        .unwrap();
+        let mut conn = Connection::open_in_memory().unwrap();
+        tree.write_sqlite(&mut conn).unwrap();
+
+        let count = |sql: &str| conn.query_row(sql, [], |row| row.get::<_, i64>(0)).unwrap();
+        assert_eq!(count("SELECT COUNT(*) FROM categories"), 5);
+        assert_eq!(count("SELECT COUNT(*) FROM category_children"), 4);
+        assert_eq!(
+            count("SELECT COUNT(*) FROM category_closure WHERE ancestor_id = '2-02' AND depth > 0"),
+            3
+        );
+        let (name, parent): (String, String) = conn
+            .query_row(
+                "SELECT name, parent_id FROM categories WHERE is_green = 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(name, "大地测量工程技术人员");
+        assert_eq!(parent, "2-02-02");
+
+        // the tables already exist
+        assert!(tree.write_sqlite(&mut conn).is_err());
+    }
+}
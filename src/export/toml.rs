@@ -0,0 +1,75 @@
+use crate::{CategoryTree, KimiError};
+use toml::{Table, Value};
+
+impl CategoryTree {
+    /// TOML with one nested table per node, keyed by id segment
+    ///
+    /// ```toml
+    /// [1.01]
+    /// id = "1-01"
+    /// code = "GBM10100"
+    /// desc = "中国共产党机关和基层组织负责人"
+    /// level = "middle"
+    /// ```
+    ///
+    /// TOML has no null, so absent codes and descriptions are omitted. Only
+    /// the first category of a node is written.
+    pub fn to_toml_string(&self) -> Result<String, KimiError> {
+        Ok(toml::to_string(&self.toml_table())?)
+    }
+
+    fn toml_table(&self) -> Table {
+        let mut table = Table::new();
+        if let Some(cat) = self.items.first() {
+            table.insert("id".to_string(), Value::from(cat.id.clone()));
+            if let Some(code) = &cat.code {
+                table.insert("code".to_string(), Value::from(code.clone()));
+            }
+            if let Some(desc) = &cat.desc {
+                table.insert("desc".to_string(), Value::from(desc.clone()));
+            }
+            if let Some(level) = cat.level() {
+                table.insert("level".to_string(), Value::try_from(level).unwrap());
+            }
+            if cat.is_green {
+                table.insert("is_green".to_string(), Value::from(true));
+            }
+            if cat.is_digital {
+                table.insert("is_digital".to_string(), Value::from(true));
+            }
+        }
+        for (key, child) in &self.children {
+            table.insert(key.clone(), Value::Table(child.toml_table()));
+        }
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toml() {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column(
+            "1-01(GBM10100)机关负责人\n1-01-00-01中国共产党机关负责人\n2-02-02-02工程测量工程技术人员S",
+        )
+        .unwrap();
+        let text = tree.to_toml_string().unwrap();
+        let value: Table = text.parse().unwrap();
+        let middle = &value["1"]["01"];
+        assert_eq!(middle["id"].as_str(), Some("1-01"));
+        assert_eq!(middle["code"].as_str(), Some("GBM10100"));
+        assert_eq!(middle["level"].as_str(), Some("middle"));
+        assert_eq!(
+            middle["00"]["01"]["desc"].as_str(),
+            Some("中国共产党机关负责人")
+        );
+        assert!(middle["00"].get("id").is_none());
+        assert_eq!(
+            value["2"]["02"]["02"]["02"]["is_digital"].as_bool(),
+            Some(true)
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{CategoryTree, KimiError};
+use std::io::Write;
+use std::path::Path;
+use umya_spreadsheet::{Spreadsheet, Worksheet, writer};
+
+const SHEET: &str = "职业分类";
+
+const HEADER: [&str; 8] = [
+    "大类",
+    "中类",
+    "小类",
+    "细类",
+    "名称",
+    "GBM代码",
+    "绿色职业",
+    "数字职业",
+];
+
+const WIDTHS: [(&str, f64); 8] = [
+    ("A", 6.0),
+    ("B", 8.0),
+    ("C", 10.0),
+    ("D", 12.0),
+    ("E", 40.0),
+    ("F", 12.0),
+    ("G", 10.0),
+    ("H", 10.0),
+];
+
+impl CategoryTree {
+    /// Write a clean workbook with one row per category, in tree order
+    ///
+    /// The id goes into the column of its level (大类 … 细类) so rows can
+    /// be filtered by level; 大类 rows are bold. umya-spreadsheet cannot
+    /// write row outline levels, so there is no Excel grouping.
+    pub fn export_xlsx(&self, path: impl AsRef<Path>) -> Result<(), KimiError> {
+        writer::xlsx::write(&self.to_workbook(), path)?;
+        Ok(())
+    }
+
+    /// Write the workbook of `export_xlsx` to `w`
+    pub fn write_xlsx<W: Write>(&self, w: W) -> Result<(), KimiError> {
+        writer::xlsx::write_writer(&self.to_workbook(), w)?;
+        Ok(())
+    }
+
+    fn to_workbook(&self) -> Spreadsheet {
+        let mut book = umya_spreadsheet::new_file();
+        let sheet = book.get_sheet_mut(&0).unwrap();
+        sheet.set_name(SHEET);
+        for (col, title) in HEADER.iter().enumerate() {
+            set_text(sheet, col as u32 + 1, 1, title);
+            sheet
+                .get_style_mut((col as u32 + 1, 1))
+                .get_font_mut()
+                .set_bold(true);
+        }
+        for (col, width) in WIDTHS {
+            sheet.get_column_dimension_mut(col).set_width(width);
+        }
+
+        let mut row = 1;
+        for (path, cat) in self {
+            row += 1;
+            let col = path.len().clamp(1, 4) as u32;
+            set_text(sheet, col, row, &cat.id);
+            set_text(sheet, 5, row, cat.desc.as_deref().unwrap_or(""));
+            set_text(sheet, 6, row, cat.code.as_deref().unwrap_or(""));
+            if cat.is_green {
+                set_text(sheet, 7, row, "L");
+            }
+            if cat.is_digital {
+                set_text(sheet, 8, row, "S");
+            }
+            if path.len() == 1 {
+                for col in 1..=HEADER.len() as u32 {
+                    sheet
+                        .get_style_mut((col, row))
+                        .get_font_mut()
+                        .set_bold(true);
+                }
+            }
+        }
+        sheet.set_auto_filter(format!("A1:H{}", row));
+        book
+    }
+}
+
+fn set_text(sheet: &mut Worksheet, col: u32, row: u32, value: &str) {
+    if !value.is_empty() {
+        sheet.get_cell_mut((col, row)).set_value_string(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use umya_spreadsheet::reader;
+
+    #[test]
+    fn test_xlsx() {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column("1-01(GBM10100)机关负责人\n1-01-00-01中国共产党机关负责人S")
+            .unwrap();
+        let mut buf = Vec::new();
+        tree.write_xlsx(&mut buf).unwrap();
+
+        let book = reader::xlsx::read_reader(Cursor::new(buf), true).unwrap();
+        let sheet = book.get_sheet_by_name(SHEET).unwrap();
+        let cell = |col: u32, row: u32| sheet.get_value((col, row));
+        assert_eq!(cell(1, 1), "大类");
+        assert_eq!(cell(2, 2), "1-01");
+        assert_eq!(cell(5, 2), "机关负责人");
+        assert_eq!(cell(6, 2), "GBM10100");
+        assert_eq!(cell(4, 3), "1-01-00-01");
+        assert_eq!(cell(8, 3), "S");
+        assert_eq!(cell(7, 3), "");
+    }
+}
@@ -0,0 +1,41 @@
+use crate::{CategoryTree, KimiError};
+use std::io::Write;
+
+impl CategoryTree {
+    /// YAML in the v2 layout (see `JsonFormat::V2`)
+    pub fn to_yaml_string(&self) -> Result<String, KimiError> {
+        Ok(serde_yaml::to_string(&self.to_v2())?)
+    }
+
+    /// Write YAML in the v2 layout to `w`
+    pub fn to_yaml_writer<W: Write>(&self, w: W) -> Result<(), KimiError> {
+        Ok(serde_yaml::to_writer(w, &self.to_v2())?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_yaml() {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column("1-01(GBM10100)机关负责人\n1-01-00-01中国共产党机关负责人")
+            .unwrap();
+        let yaml = tree.to_yaml_string().unwrap();
+        assert!(yaml.starts_with("version: 2\n"));
+        assert!(yaml.contains("id: 1-01\n"));
+        assert!(yaml.contains("code: GBM10100\n"));
+        assert!(yaml.contains("level: detail\n"));
+
+        let value: serde_yaml::Value = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(
+            value["children"][0]["children"][0]["desc"].as_str(),
+            Some("机关负责人")
+        );
+
+        let mut buf = Vec::new();
+        tree.to_yaml_writer(&mut buf).unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), yaml);
+    }
+}
@@ -0,0 +1,107 @@
+//! Download workbooks over HTTP (cargo feature `fetch`)
+
+use crate::{CategoryTree, KimiError, ParseOptions, ParseReport};
+use std::fs::{self, File};
+use std::io::{BufWriter, Read, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+impl CategoryTree {
+    /// Download the workbook at `url` and parse it like `build_from`
+    pub fn build_from_url(&mut self, url: &str) -> Result<ParseReport, KimiError> {
+        self.build_from_url_with(url, &ParseOptions::default(), |_, _| {})
+    }
+
+    /// Like `build_from_url`, with parse options and a callback receiving
+    /// the bytes downloaded so far and the total size when known
+    pub fn build_from_url_with<F>(
+        &mut self,
+        url: &str,
+        options: &ParseOptions,
+        progress: F,
+    ) -> Result<ParseReport, KimiError>
+    where
+        F: FnMut(u64, Option<u64>),
+    {
+        let path = temp_path();
+        let result = download(url, &path, progress)
+            .and_then(|()| self.build_from_with(&path.to_string_lossy(), options));
+        let _ = fs::remove_file(&path);
+        result
+    }
+}
+
+fn temp_path() -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+    std::env::temp_dir().join(format!("kimi-{}-{}.xlsx", std::process::id(), n))
+}
+
+fn download<F>(url: &str, path: &PathBuf, mut progress: F) -> Result<(), KimiError>
+where
+    F: FnMut(u64, Option<u64>),
+{
+    let mut response = reqwest::blocking::get(url)?.error_for_status()?;
+    let total = response.content_length();
+    let mut file = BufWriter::new(File::create(path)?);
+    let mut buf = vec![0; 64 * 1024];
+    let mut done = 0;
+    loop {
+        let n = response.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        file.write_all(&buf[..n])?;
+        done += n as u64;
+        progress(done, total);
+    }
+    file.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufRead;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_build_from_url() {
+        let mut book = umya_spreadsheet::new_file();
+        book.get_sheet_mut(&0)
+            .unwrap()
+            .get_cell_mut((3, 1))
+            .set_value("2-01(GBM20100)科学研究人员");
+        let mut body = Vec::new();
+        umya_spreadsheet::writer::xlsx::write_writer(&book, &mut body).unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/dict.xlsx", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = std::io::BufReader::new(&stream);
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            let mut stream = &stream;
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n",
+                body.len()
+            )
+            .unwrap();
+            stream.write_all(&body).unwrap();
+        });
+
+        let mut tree = CategoryTree::new();
+        let mut last = (0, None);
+        tree.build_from_url_with(&url, &ParseOptions::default(), |done, total| {
+            last = (done, total)
+        })
+        .unwrap();
+        server.join().unwrap();
+        assert_eq!(last.0, last.1.unwrap());
+        assert!(tree.get("2-01").is_some());
+    }
+}
@@ -0,0 +1,203 @@
+//! `<table>` input from HTML pages
+//!
+//! Each table of the page becomes a sheet: rows and cells are laid out on a
+//! grid honouring `rowspan` and `colspan`, which turn into merged regions,
+//! and `<br>` and block elements inside a cell end a line. The page must be
+//! UTF-8.
+
+use crate::{
+    CategoryTree, CellRange, KimiError, ParseOptions, ParseReport, Sheet, SpreadsheetSource,
+};
+use scraper::{ElementRef, Html, Node, Selector};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/// Tables of an HTML page, one sheet each
+pub struct HtmlSource {
+    sheets: Vec<Sheet>,
+}
+
+impl HtmlSource {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, KimiError> {
+        Ok(Self::parse(&fs::read_to_string(path)?))
+    }
+
+    /// Read the tables of an HTML document
+    pub fn parse(html: &str) -> Self {
+        let document = Html::parse_document(html);
+        let tables = Selector::parse("table").unwrap();
+        let sheets = document
+            .select(&tables)
+            .enumerate()
+            .map(|(i, table)| {
+                let name = table
+                    .attr("id")
+                    .map_or_else(|| format!("Table{}", i + 1), str::to_string);
+                table_sheet(table, name)
+            })
+            .collect();
+        HtmlSource { sheets }
+    }
+}
+
+impl SpreadsheetSource for HtmlSource {
+    fn sheet_names(&self) -> Vec<String> {
+        self.sheets
+            .iter()
+            .map(|sheet| sheet.name().to_string())
+            .collect()
+    }
+
+    fn read_sheet(&mut self, index: usize) -> Result<Sheet, KimiError> {
+        self.sheets
+            .get(index)
+            .cloned()
+            .ok_or(KimiError::SheetNotFound(index))
+    }
+}
+
+impl CategoryTree {
+    /// Parse the tables of an HTML page, e.g. a government portal
+    /// republishing the 大典, like `build_from_with`
+    pub fn build_from_html(
+        &mut self,
+        input_file: &str,
+        options: &ParseOptions,
+    ) -> Result<ParseReport, KimiError> {
+        self.build_from_source(&mut HtmlSource::open(input_file)?, options)
+    }
+}
+
+fn table_sheet(table: ElementRef, name: String) -> Sheet {
+    let mut sheet = Sheet::new(name);
+    // cells covered by a rowspan or colspan from an earlier cell
+    let mut taken = HashSet::new();
+    for (row, tr) in (1..).zip(rows(table)) {
+        let mut col = 1;
+        for cell in children(tr).filter(|cell| matches!(cell.value().name(), "td" | "th")) {
+            while taken.contains(&(col, row)) {
+                col += 1;
+            }
+            let span = |attr| {
+                cell.attr(attr)
+                    .and_then(|n| n.trim().parse::<u32>().ok())
+                    .map_or(1, |n| n.max(1))
+            };
+            let last = (col + span("colspan") - 1, row + span("rowspan") - 1);
+            for c in col..=last.0 {
+                for r in row..=last.1 {
+                    taken.insert((c, r));
+                }
+            }
+            sheet.set_text(col, row, cell_text(cell));
+            if last != (col, row) {
+                sheet.add_merged(CellRange {
+                    first: (col, row),
+                    last,
+                });
+            }
+            col = last.0 + 1;
+        }
+    }
+    sheet
+}
+
+/// Rows of `table`, directly or in its head, bodies and foot, but not
+/// those of nested tables
+fn rows(table: ElementRef) -> Vec<ElementRef> {
+    let mut rows = Vec::new();
+    for child in children(table) {
+        match child.value().name() {
+            "tr" => rows.push(child),
+            "thead" | "tbody" | "tfoot" => {
+                rows.extend(children(child).filter(|row| row.value().name() == "tr"))
+            }
+            _ => {}
+        }
+    }
+    rows
+}
+
+fn children(element: ElementRef) -> impl Iterator<Item = ElementRef> {
+    element.children().filter_map(ElementRef::wrap)
+}
+
+/// Text of a cell with source whitespace collapsed and a line per `<br>`,
+/// paragraph or list item
+fn cell_text(cell: ElementRef) -> String {
+    let mut text = String::new();
+    push_text(cell, &mut text);
+    let lines = text.lines().map(str::trim).collect::<Vec<_>>();
+    // blank lines separate categories, so only the outer ones are dropped
+    lines.join("\n").trim_matches('\n').to_string()
+}
+
+fn push_text(element: ElementRef, text: &mut String) {
+    for node in element.children() {
+        match node.value() {
+            Node::Text(t) => {
+                for c in t.chars() {
+                    if !c.is_whitespace() {
+                        text.push(c);
+                    } else if !text.is_empty() && !text.ends_with([' ', '\n']) {
+                        text.push(' ');
+                    }
+                }
+            }
+            Node::Element(e) if e.name() == "br" => text.push('\n'),
+            Node::Element(e) => {
+                let block = matches!(e.name(), "p" | "div" | "li" | "tr" | "table");
+                let end_line = |text: &mut String| {
+                    if block && !text.is_empty() && !text.ends_with('\n') {
+                        text.push('\n');
+                    }
+                };
+                end_line(text);
+                if let Some(child) = ElementRef::wrap(node) {
+                    push_text(child, text);
+                }
+                end_line(text);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LayoutProfile;
+
+    #[test]
+    fn test_html_tables() {
+        let html = r#"<html><body><p>中华人民共和国职业分类大典</p>
+            <table id="dict"><tbody>
+            <tr><td colspan="4">分类体系表</td><td>细类</td><td></td></tr>
+            <tr><td></td><td></td>
+                <td>2-04-02(GBM20402)<br>船舶指挥和引航人员</td><td></td>
+                <td rowspan="2">2-04-02-01<br>2-04-02-02</td><td>船舶驾驶员</td></tr>
+            <tr><td></td><td></td><td></td><td></td><td>船舶<b>引航员</b> L</td></tr>
+            </tbody></table></body></html>"#;
+        let mut source = HtmlSource::parse(html);
+        assert_eq!(source.sheet_names(), ["dict"]);
+        let sheet = source.read_sheet(0).unwrap();
+        assert_eq!(sheet.text(3, 2), "2-04-02(GBM20402)\n船舶指挥和引航人员");
+        assert_eq!(sheet.text(6, 3), "船舶引航员 L");
+        assert_eq!(sheet.merged().rows(5, 3), (2, 3));
+
+        let options = ParseOptions {
+            layout: Some(LayoutProfile::default()),
+            ..ParseOptions::default()
+        };
+        let mut tree = CategoryTree::new();
+        tree.build_from_source(&mut source, &options).unwrap();
+        assert_eq!(
+            tree.get("2-04-02").unwrap().desc.as_deref(),
+            Some("船舶指挥和引航人员")
+        );
+        let pilot = tree.get("2-04-02-02").unwrap();
+        assert_eq!(pilot.desc.as_deref(), Some("船舶引航员"));
+        assert!(pilot.is_green);
+    }
+}
@@ -0,0 +1,250 @@
+use crate::{KimiError, Level};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Dash-separated hierarchical id such as "2-02-38-07"
+///
+/// Ids are ordered segment by segment, numerically, see `compare_ids`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CategoryId(String);
+
+impl CategoryId {
+    pub fn new(id: impl Into<String>) -> Self {
+        CategoryId(id.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Path segments, e.g. ["2", "02", "38", "07"]
+    pub fn segments(&self) -> impl Iterator<Item = &str> {
+        self.0.split('-')
+    }
+
+    pub fn depth(&self) -> usize {
+        self.segments().count()
+    }
+
+    pub fn level(&self) -> Option<Level> {
+        Level::from_depth(self.depth())
+    }
+
+    /// Id one level up, e.g. "2-02-38" for "2-02-38-07"; `None` for a 大类
+    pub fn parent_id(&self) -> Option<String> {
+        self.0
+            .rsplit_once('-')
+            .map(|(parent, _)| parent.to_string())
+    }
+
+    pub fn parent(&self) -> Option<CategoryId> {
+        self.parent_id().map(CategoryId)
+    }
+}
+
+impl Ord for CategoryId {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare_ids(&self.0, &other.0)
+    }
+}
+
+impl PartialOrd for CategoryId {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Compare two id segments numerically ("2" < "10"), falling back to
+/// string order for non-numeric segments, which sort after numeric ones
+pub fn compare_segments(a: &str, b: &str) -> Ordering {
+    match (a.parse::<u64>(), b.parse::<u64>()) {
+        (Ok(x), Ok(y)) => x.cmp(&y).then_with(|| a.cmp(b)),
+        (Ok(_), Err(_)) => Ordering::Less,
+        (Err(_), Ok(_)) => Ordering::Greater,
+        (Err(_), Err(_)) => a.cmp(b),
+    }
+}
+
+/// Compare two dash-separated ids segment by segment, parents first
+pub fn compare_ids(a: &str, b: &str) -> Ordering {
+    let mut a = a.split('-');
+    let mut b = b.split('-');
+    loop {
+        match (a.next(), b.next()) {
+            (Some(x), Some(y)) => match compare_segments(x, y) {
+                Ordering::Equal => continue,
+                ord => return ord,
+            },
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (None, None) => return Ordering::Equal,
+        }
+    }
+}
+
+impl fmt::Display for CategoryId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<&str> for CategoryId {
+    fn from(id: &str) -> Self {
+        CategoryId::new(id)
+    }
+}
+
+impl From<String> for CategoryId {
+    fn from(id: String) -> Self {
+        CategoryId(id)
+    }
+}
+
+impl AsRef<str> for CategoryId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+/// How ids and codes are written in the source text
+///
+/// Parsed ids are dash-separated whatever `separator` the source uses, so
+/// "1.01.02" is kept as "1-01-02". Levels still follow the four of the
+/// 大典 by the depth of an id, so with more than four segments the ids
+/// below a 细类 have no `Level`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IdFormat {
+    /// Between the segments of an id
+    pub separator: String,
+    /// Segments in the id of the deepest level, 4 for a 细类; only tells
+    /// the line merger where a line with a whole id ends a chunk, it does
+    /// not change the `Level` of an id
+    pub segments: usize,
+    /// Regular expression for one segment, e.g. `[A-Z]|\d+` for a letter
+    /// at the top level
+    pub segment_pattern: String,
+    /// Regular expression for the start of the code in brackets after an
+    /// id, which digits follow
+    pub code_prefix: String,
+}
+
+impl Default for IdFormat {
+    /// Ids such as "2-02-38-07" and codes such as "GBM20238" of the 大典
+    fn default() -> Self {
+        IdFormat {
+            separator: "-".to_string(),
+            segments: 4,
+            segment_pattern: r"\d+".to_string(),
+            code_prefix: "GBM".to_string(),
+        }
+    }
+}
+
+impl IdFormat {
+    /// `id` as read from the source, with its segments joined by dashes
+    pub fn normalize(&self, id: &str) -> String {
+        if self.separator == "-" || self.separator.is_empty() {
+            id.to_string()
+        } else {
+            id.replace(self.separator.as_str(), "-")
+        }
+    }
+
+    /// Whether `text` has as many separators as an id of the deepest level
+    pub(crate) fn is_deepest(&self, text: &str) -> bool {
+        !self.separator.is_empty()
+            && text.matches(self.separator.as_str()).count() + 1 == self.segments
+    }
+
+    /// An id of two or more segments
+    fn nested_id(&self) -> String {
+        format!(
+            "(?:{segment})(?:{separator}(?:{segment}))+",
+            segment = self.segment_pattern,
+            separator = regex::escape(&self.separator)
+        )
+    }
+
+    fn code(&self) -> String {
+        format!(r"(?:{})\s*\d+", self.code_prefix)
+    }
+
+    /// The prefix at the start of a code, e.g. "GBM" of "GBM10100"
+    pub(crate) fn code_prefix_regex(&self) -> Result<Regex, KimiError> {
+        Ok(Regex::new(&format!("^(?:{})", self.code_prefix))?)
+    }
+
+    /// A line starting with a category id, e.g. "2-02-38" or "第一大类 1"
+    pub(crate) fn line_regex(&self) -> Result<Regex, KimiError> {
+        Ok(Regex::new(&format!(
+            r"^\s*(?:第\S{{1,2}}大类\s*\d+|{})",
+            self.nested_id()
+        ))?)
+    }
+
+    /// A line holding nothing but an id, code and marker; its name is in the
+    /// next column
+    pub(crate) fn bare_line_regex(&self) -> Result<Regex, KimiError> {
+        Ok(Regex::new(&format!(
+            r"^\s*{}\s*(?:\(\s*{}\s*\))?\s*(?:L/S|S/L|L|S)?\s*$",
+            self.nested_id(),
+            self.code()
+        ))?)
+    }
+
+    /// A line starting with an id, captured with the rest of the line
+    pub(crate) fn leading_regex(&self) -> Result<Regex, KimiError> {
+        Ok(Regex::new(&format!(r"^\s*({})\s*(.*)$", self.nested_id()))?)
+    }
+
+    /// A chunk of one category:
+    /// - id: one or more segments at the start, e.g. 1-01 or 1-01-01-01;
+    ///   an empty segment is caught as malformed
+    /// - optional code in brackets, e.g. (GBM10100)
+    /// - description: rest of the string
+    /// - optional marker: L (绿色职业), S (数字职业) or L/S
+    pub(crate) fn chunk_regex(&self) -> Result<Regex, KimiError> {
+        Ok(Regex::new(&format!(
+            r"^\s*(?P<id>(?:{segment})(?:{separator}(?:{segment})?)*)(?:\s*\(\s*(?P<code>{code})\s*\))?\s*(?P<desc>.*?)?\s*(?P<marker>L/S|S/L|L|S)?$",
+            segment = self.segment_pattern,
+            separator = regex::escape(&self.separator),
+            code = self.code()
+        ))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parent_id() {
+        let id = CategoryId::from("2-02-38-07");
+        assert_eq!(id.depth(), 4);
+        assert_eq!(id.level(), Some(Level::Detail));
+        assert_eq!(id.parent_id().as_deref(), Some("2-02-38"));
+        assert_eq!(
+            id.parent().and_then(|p| p.parent()),
+            Some(CategoryId::from("2-02"))
+        );
+        assert_eq!(CategoryId::from("2").parent_id(), None);
+    }
+
+    #[test]
+    fn test_compare_ids() {
+        let mut ids = vec!["1-10", "1-9", "1-09-01", "1", "10", "2-01", "1-9-00"]
+            .into_iter()
+            .map(CategoryId::from)
+            .collect::<Vec<_>>();
+        ids.sort();
+        let ids = ids.iter().map(|id| id.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            vec!["1", "1-09-01", "1-9", "1-9-00", "1-10", "2-01", "10"]
+        );
+    }
+}
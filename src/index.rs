@@ -0,0 +1,71 @@
+use crate::{Category, CategoryTree};
+use std::collections::HashMap;
+
+/// Secondary index from GBM code to category
+///
+/// Several ids share one code (a 中类 and its only 小类 "xx-00"), in which
+/// case the shallowest id wins.
+#[derive(Debug, Default)]
+pub struct CodeIndex<'a> {
+    entries: HashMap<String, (Vec<&'a str>, &'a Category)>,
+}
+
+impl<'a> CodeIndex<'a> {
+    pub fn new(tree: &'a CategoryTree) -> Self {
+        let mut entries = HashMap::new();
+        for (path, cat) in tree {
+            if let Some(code) = &cat.code {
+                entries.entry(normalize_code(code)).or_insert((path, cat));
+            }
+        }
+        CodeIndex { entries }
+    }
+
+    /// Look up a code such as "GBM10100" (spaces and case are ignored),
+    /// returning the path segments of the category and the category itself
+    pub fn get(&self, code: &str) -> Option<(&[&'a str], &'a Category)> {
+        self.entries
+            .get(&normalize_code(code))
+            .map(|(path, cat)| (path.as_slice(), *cat))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+fn normalize_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_ascii_uppercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_by_code() {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column(
+            "1-01(GBM10100)中国共产党机关和基层组织负责人\n1-01-00(GBM10100)中国共产党机关和基层组织负责人\n1-02-01(GBM10201)国家权力机关负责人",
+        )
+        .unwrap();
+
+        let (path, cat) = tree.find_by_code("GBM10100").unwrap();
+        assert_eq!(path, vec!["1", "01"]);
+        assert_eq!(cat.id, "1-01");
+
+        let index = tree.build_code_index();
+        assert_eq!(index.len(), 2);
+        let (path, cat) = index.get("gbm 10201").unwrap();
+        assert_eq!(path, ["1", "02", "01"]);
+        assert_eq!(cat.desc.as_deref(), Some("国家权力机关负责人"));
+        assert!(index.get("GBM99999").is_none());
+    }
+}
@@ -0,0 +1,222 @@
+use crate::{Category, Tree};
+use std::collections::VecDeque;
+
+/// Depth-first iterator over a tree, yielding each category with the path
+/// segments of the node it lives in, in insertion order
+pub struct Iter<'a, T = Category> {
+    stack: Vec<Frame<'a, T>>,
+}
+
+struct Frame<'a, T> {
+    path: Vec<&'a str>,
+    items: std::slice::Iter<'a, T>,
+    children: indexmap::map::Iter<'a, String, Tree<T>>,
+}
+
+impl<'a, T> Frame<'a, T> {
+    fn new(node: &'a Tree<T>, path: Vec<&'a str>) -> Self {
+        Frame {
+            path,
+            items: node.items.iter(),
+            children: node.children.iter(),
+        }
+    }
+}
+
+impl<'a, T> Iter<'a, T> {
+    pub(crate) fn new(tree: &'a Tree<T>) -> Self {
+        Iter {
+            stack: vec![Frame::new(tree, Vec::new())],
+        }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = (Vec<&'a str>, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let frame = self.stack.last_mut()?;
+            if let Some(item) = frame.items.next() {
+                return Some((frame.path.clone(), item));
+            }
+            if let Some((key, child)) = frame.children.next() {
+                let mut path = frame.path.clone();
+                path.push(key);
+                self.stack.push(Frame::new(child, path));
+                continue;
+            }
+            self.stack.pop();
+        }
+    }
+}
+
+/// Breadth-first iterator over a tree, yielding all categories of one depth
+/// (大类, then 中类, …) before descending
+pub struct BfsIter<'a, T = Category> {
+    queue: VecDeque<(&'a Tree<T>, Vec<&'a str>)>,
+    path: Vec<&'a str>,
+    items: std::slice::Iter<'a, T>,
+}
+
+impl<'a, T> BfsIter<'a, T> {
+    pub(crate) fn new(tree: &'a Tree<T>) -> Self {
+        BfsIter {
+            queue: VecDeque::from([(tree, Vec::new())]),
+            path: Vec::new(),
+            items: [].iter(),
+        }
+    }
+}
+
+impl<'a, T> Iterator for BfsIter<'a, T> {
+    type Item = (Vec<&'a str>, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.items.next() {
+                return Some((self.path.clone(), item));
+            }
+            let (node, path) = self.queue.pop_front()?;
+            for (key, child) in &node.children {
+                let mut child_path = path.clone();
+                child_path.push(key);
+                self.queue.push_back((child, child_path));
+            }
+            self.path = path;
+            self.items = node.items.iter();
+        }
+    }
+}
+
+/// A leaf (细类) category together with its ancestry
+#[derive(Debug, Clone)]
+pub struct Leaf<'a, T = Category> {
+    /// Path segments of the leaf node, e.g. ["2", "02", "01", "01"]
+    pub path: Vec<&'a str>,
+    /// Categories above the leaf, from 大类 down to the direct parent
+    pub ancestors: Vec<&'a T>,
+    pub category: &'a T,
+}
+
+/// Depth-first iterator over categories in nodes without children
+pub struct Leaves<'a, T = Category> {
+    stack: Vec<(&'a Tree<T>, Vec<&'a str>, Vec<&'a T>)>,
+    current: Option<(Vec<&'a str>, Vec<&'a T>)>,
+    items: std::slice::Iter<'a, T>,
+}
+
+impl<'a, T> Leaves<'a, T> {
+    pub(crate) fn new(tree: &'a Tree<T>) -> Self {
+        Leaves {
+            stack: vec![(tree, Vec::new(), Vec::new())],
+            current: None,
+            items: [].iter(),
+        }
+    }
+}
+
+impl<'a, T> Iterator for Leaves<'a, T> {
+    type Item = Leaf<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let (Some(item), Some((path, ancestors))) = (self.items.next(), &self.current) {
+                return Some(Leaf {
+                    path: path.clone(),
+                    ancestors: ancestors.clone(),
+                    category: item,
+                });
+            }
+            let (node, path, ancestors) = self.stack.pop()?;
+            if node.children.is_empty() {
+                self.items = node.items.iter();
+                self.current = Some((path, ancestors));
+                continue;
+            }
+            let mut child_ancestors = ancestors;
+            child_ancestors.extend(node.items.first());
+            for (key, child) in node.children.iter().rev() {
+                let mut child_path = path.clone();
+                child_path.push(key);
+                self.stack
+                    .push((child, child_path, child_ancestors.clone()));
+            }
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Tree<T> {
+    type Item = (Vec<&'a str>, &'a T);
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::CategoryTree;
+
+    #[test]
+    fn test_iter_dfs() {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column(
+            "1(GBM10)负责人\n2(GBM20000)专业技术人员\n1-01(GBM10100)机关负责人\n1-01-00-01中国共产党机关负责人\n2-01(GBM20100)科学研究人员",
+        )
+        .unwrap();
+        let ids = tree.iter().map(|(_, c)| c.id.as_str()).collect::<Vec<_>>();
+        assert_eq!(ids, vec!["1", "1-01", "1-01-00-01", "2", "2-01"]);
+
+        let (path, cat) = (&tree).into_iter().nth(2).unwrap();
+        assert_eq!(path, vec!["1", "01", "00", "01"]);
+        assert_eq!(cat.desc.as_deref(), Some("中国共产党机关负责人"));
+
+        let mut count = 0;
+        for (path, cat) in &tree {
+            assert_eq!(path.join("-"), cat.id);
+            count += 1;
+        }
+        assert_eq!(count, 5);
+        assert_eq!(CategoryTree::new().iter().count(), 0);
+    }
+
+    #[test]
+    fn test_iter_bfs() {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column(
+            "1(GBM10)负责人\n1-01(GBM10100)机关负责人\n1-01-00-01中国共产党机关负责人\n2(GBM20000)专业技术人员\n2-01(GBM20100)科学研究人员\n2-01-01(GBM20101)哲学研究人员",
+        )
+        .unwrap();
+        let ids = tree
+            .iter_bfs()
+            .map(|(_, c)| c.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["1", "2", "1-01", "2-01", "2-01-01", "1-01-00-01"]);
+        let depths = tree.iter_bfs().map(|(p, _)| p.len()).collect::<Vec<_>>();
+        assert!(depths.is_sorted());
+    }
+
+    #[test]
+    fn test_leaves() {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column(
+            "1(GBM10)负责人\n1-01(GBM10100)机关负责人\n1-01-00(GBM10100)机关负责人\n1-01-00-01中国共产党机关负责人\n1-01-00-02中国共产党基层组织负责人\n2(GBM20000)专业技术人员",
+        )
+        .unwrap();
+        let leaves = tree.leaves().collect::<Vec<_>>();
+        assert_eq!(leaves.len(), 3);
+        assert_eq!(leaves[0].category.id, "1-01-00-01");
+        assert_eq!(leaves[0].path, vec!["1", "01", "00", "01"]);
+        let ancestors = leaves[1]
+            .ancestors
+            .iter()
+            .map(|c| c.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ancestors, vec!["1", "1-01", "1-01-00"]);
+        // a 大类 without any children is a leaf too
+        assert_eq!(leaves[2].category.id, "2");
+        assert!(leaves[2].ancestors.is_empty());
+    }
+}
@@ -0,0 +1,417 @@
+//! JSON import and export
+//!
+//! Two layouts are supported, see `JsonFormat`. The v2 layout is the stable,
+//! documented one:
+//!
+//! ```json
+//! {
+//!   "version": 2,
+//!   "children": [
+//!     {
+//!       "id": "1",
+//!       "code": "GBM10",
+//!       "desc": "党的机关、国家机关、群众团体和社会组织、企事业单位负责人",
+//!       "level": "major",
+//!       "is_green": false,
+//!       "is_digital": false,
+//!       "children": [ ... ]
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! Every node carries its full id. `level` is one of `major`, `middle`,
+//! `minor`, `detail` (大类/中类/小类/细类). Intermediate nodes that have no
+//! category of their own are marked `"implicit": true`, with `code` and
+//! `desc` set to null, and repeated categories with the same id appear as
+//! siblings.
+
+use crate::{Category, CategoryTree, CellRef, KimiError, Level};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::fs::File;
+use std::io::{BufReader, Read, Write};
+use std::path::Path;
+
+/// Layout of exported JSON
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JsonFormat {
+    /// Mirrors the internal structure: `children` maps keyed by id segment
+    /// and `categories` arrays per node
+    #[default]
+    V1,
+    /// Nested nodes with `id`, `code`, `desc`, `level` and a `children` array
+    V2,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct DocumentV2 {
+    version: u32,
+    children: Vec<NodeV2>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct NodeV2 {
+    id: String,
+    code: Option<String>,
+    desc: Option<String>,
+    level: Option<Level>,
+    #[serde(default)]
+    is_green: bool,
+    #[serde(default)]
+    is_digital: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    confidence: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source: Option<CellRef>,
+    /// Only there to hold its children, not a category
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    implicit: bool,
+    #[serde(default)]
+    children: Vec<NodeV2>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AnyDocument {
+    V2(DocumentV2),
+    V1(CategoryTree),
+}
+
+/// JSON Schema (draft 2020-12) describing the v2 layout
+pub fn json_schema() -> Value {
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "kimi category tree",
+        "description": "职业分类大典 categories as a nested tree (kimi JSON v2)",
+        "type": "object",
+        "required": ["version", "children"],
+        "properties": {
+            "version": { "const": 2 },
+            "children": {
+                "type": "array",
+                "items": { "$ref": "#/$defs/node" }
+            }
+        },
+        "$defs": {
+            "node": {
+                "type": "object",
+                "required": ["id", "code", "desc", "level", "children"],
+                "properties": {
+                    "id": {
+                        "description": "Dash-separated hierarchical id, e.g. 1-01-00-01, or A-1 for a taxonomy with letters",
+                        "type": "string",
+                        "pattern": "^[^-]+(-[^-]+)*$"
+                    },
+                    "code": {
+                        "description": "Code such as GBM10100; null if absent",
+                        "type": ["string", "null"]
+                    },
+                    "desc": {
+                        "description": "Category name; null for intermediate nodes",
+                        "type": ["string", "null"]
+                    },
+                    "level": {
+                        "description": "大类/中类/小类/细类",
+                        "enum": ["major", "middle", "minor", "detail", null]
+                    },
+                    "is_green": {
+                        "description": "Marked L (绿色职业)",
+                        "type": "boolean"
+                    },
+                    "is_digital": {
+                        "description": "Marked S (数字职业)",
+                        "type": "boolean"
+                    },
+                    "confidence": {
+                        "description": "Lowest OCR word confidence of the category text",
+                        "type": "integer",
+                        "minimum": 0,
+                        "maximum": 100
+                    },
+                    "source": {
+                        "description": "Cell the category was read from",
+                        "type": "object",
+                        "required": ["sheet", "row", "col"],
+                        "properties": {
+                            "sheet": { "type": "string" },
+                            "row": { "type": "integer", "minimum": 1 },
+                            "col": { "type": "integer", "minimum": 1 }
+                        }
+                    },
+                    "implicit": {
+                        "description": "True for an intermediate node holding only its children; absent otherwise",
+                        "type": "boolean"
+                    },
+                    "children": {
+                        "type": "array",
+                        "items": { "$ref": "#/$defs/node" }
+                    }
+                }
+            }
+        }
+    })
+}
+
+impl CategoryTree {
+    /// Pretty JSON in the given layout
+    pub fn to_json_string_with(&self, format: JsonFormat) -> String {
+        let mut buf = Vec::new();
+        self.write_json(&mut buf, format, true).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    /// Write JSON in the given layout to `w`, pretty-printed or compact
+    pub fn write_json<W: Write>(
+        &self,
+        w: W,
+        format: JsonFormat,
+        pretty: bool,
+    ) -> Result<(), KimiError> {
+        match (format, pretty) {
+            (JsonFormat::V1, true) => serde_json::to_writer_pretty(w, self)?,
+            (JsonFormat::V1, false) => serde_json::to_writer(w, self)?,
+            (JsonFormat::V2, true) => serde_json::to_writer_pretty(w, &self.to_v2())?,
+            (JsonFormat::V2, false) => serde_json::to_writer(w, &self.to_v2())?,
+        }
+        Ok(())
+    }
+
+    /// Load a tree previously written with `to_json_string` or
+    /// `write_json`, in either layout
+    ///
+    /// Every category id must match its position in the tree.
+    pub fn from_json_reader<R: Read>(reader: R) -> Result<Self, KimiError> {
+        let tree = match serde_json::from_reader(reader)? {
+            AnyDocument::V1(tree) => tree,
+            AnyDocument::V2(doc) => Self::from_v2(doc),
+        };
+        tree.validate_ids()?;
+        Ok(tree)
+    }
+
+    /// Load a tree from a JSON file, see `from_json_reader`
+    pub fn from_json_file(path: impl AsRef<Path>) -> Result<Self, KimiError> {
+        let file = File::open(path)?;
+        Self::from_json_reader(BufReader::new(file))
+    }
+
+    pub(crate) fn validate_ids(&self) -> Result<(), KimiError> {
+        for (path, cat) in self {
+            let expected = path.join("-");
+            if cat.id != expected {
+                return Err(KimiError::InconsistentId {
+                    id: cat.id.clone(),
+                    path: expected,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn to_v2(&self) -> DocumentV2 {
+        DocumentV2 {
+            version: 2,
+            children: self.v2_children(""),
+        }
+    }
+
+    fn v2_children(&self, prefix: &str) -> Vec<NodeV2> {
+        let mut nodes = Vec::new();
+        for (key, child) in &self.children {
+            let id = if prefix.is_empty() {
+                key.clone()
+            } else {
+                format!("{}-{}", prefix, key)
+            };
+            let children = child.v2_children(&id);
+            match child.items.split_first() {
+                Some((first, rest)) => {
+                    nodes.push(NodeV2::new(first, children));
+                    nodes.extend(rest.iter().map(|cat| NodeV2::new(cat, Vec::new())));
+                }
+                None => nodes.push(NodeV2 {
+                    level: Level::from_depth(id.split('-').count()),
+                    id,
+                    code: None,
+                    desc: None,
+                    is_green: false,
+                    is_digital: false,
+                    confidence: None,
+                    source: None,
+                    implicit: true,
+                    children,
+                }),
+            }
+        }
+        nodes
+    }
+
+    fn from_v2(doc: DocumentV2) -> Self {
+        let mut tree = CategoryTree::new();
+        let mut stack = doc.children;
+        stack.reverse();
+        while let Some(mut node) = stack.pop() {
+            stack.extend(node.children.drain(..).rev());
+            if node.implicit {
+                // created again by inserting its children
+                continue;
+            }
+            tree.insert(
+                node.id.clone(),
+                Category {
+                    id: node.id,
+                    code: node.code,
+                    desc: node.desc,
+                    is_green: node.is_green,
+                    is_digital: node.is_digital,
+                    confidence: node.confidence,
+                    source: node.source,
+                },
+            );
+        }
+        tree
+    }
+}
+
+impl NodeV2 {
+    fn new(cat: &Category, children: Vec<NodeV2>) -> Self {
+        NodeV2 {
+            id: cat.id.clone(),
+            code: cat.code.clone(),
+            desc: cat.desc.clone(),
+            level: cat.level(),
+            is_green: cat.is_green,
+            is_digital: cat.is_digital,
+            confidence: cat.confidence,
+            source: cat.source.clone(),
+            implicit: false,
+            children,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> CategoryTree {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column(
+            "1-01(GBM10100)机关负责人\n1-01-00-01中国共产党机关负责人\n2-02-01-02地球物理地球化学与遥感勘查工程技术人员L/S",
+        )
+        .unwrap();
+        tree
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let tree = tree();
+        let json = tree.to_json_string();
+        let loaded = CategoryTree::from_json_reader(json.as_bytes()).unwrap();
+        assert_eq!(loaded, tree);
+    }
+
+    #[test]
+    fn test_json_v2() {
+        let tree = tree();
+        let json = tree.to_json_string_with(JsonFormat::V2);
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["version"], 2);
+        let major = &value["children"][0];
+        assert_eq!(major["id"], "1");
+        assert!(major["code"].is_null());
+        assert_eq!(major["implicit"], true);
+        let middle = &major["children"][0];
+        assert_eq!(middle["id"], "1-01");
+        assert_eq!(middle["code"], "GBM10100");
+        assert_eq!(middle["level"], "middle");
+        assert!(middle.get("implicit").is_none());
+        assert_eq!(middle["children"][0]["children"][0]["level"], "detail");
+        let digital = &value["children"][1]["children"][0]["children"][0]["children"][0];
+        assert_eq!(digital["is_digital"], true);
+
+        let loaded = CategoryTree::from_json_reader(json.as_bytes()).unwrap();
+        assert_eq!(loaded, tree);
+
+        // a category without code or name is kept, unlike an implicit node
+        let mut tree = tree;
+        tree.insert(
+            "2-02-01-03",
+            Category {
+                id: "2-02-01-03".to_string(),
+                code: None,
+                desc: None,
+                is_green: false,
+                is_digital: false,
+                confidence: None,
+                source: None,
+            },
+        );
+        let json = tree.to_json_string_with(JsonFormat::V2);
+        let loaded = CategoryTree::from_json_reader(json.as_bytes()).unwrap();
+        assert_eq!(loaded, tree);
+
+        let mut compact = Vec::new();
+        tree.write_json(&mut compact, JsonFormat::V2, false)
+            .unwrap();
+        assert!(!compact.contains(&b'\n'));
+    }
+
+    #[test]
+    fn test_json_schema() {
+        let schema = json_schema();
+        assert_eq!(schema["properties"]["version"]["const"], 2);
+        // every field emitted for a node is described by the schema
+        let json = tree().to_json_string_with(JsonFormat::V2);
+        let value: Value = serde_json::from_str(&json).unwrap();
+        let properties = schema["$defs"]["node"]["properties"].as_object().unwrap();
+        for key in value["children"][0].as_object().unwrap().keys() {
+            assert!(properties.contains_key(key), "{} missing from schema", key);
+        }
+        // ids of any `IdFormat` are kept dash-separated
+        let id = regex::Regex::new(properties["id"]["pattern"].as_str().unwrap()).unwrap();
+        assert!(id.is_match("1-01-00-01") && id.is_match("A-1"));
+        assert!(!id.is_match("1--01") && !id.is_match("1-01-"));
+    }
+
+    #[test]
+    fn test_json_inconsistent_id() {
+        let json = r#"{
+            "children": {
+                "1": {
+                    "children": {},
+                    "categories": [{"id": "2", "code": null, "desc": "x"}]
+                }
+            },
+            "categories": []
+        }"#;
+        let err = CategoryTree::from_json_reader(json.as_bytes()).unwrap_err();
+        assert!(matches!(err, KimiError::InconsistentId { id, path } if id == "2" && path == "1"));
+
+        let err = CategoryTree::from_json_reader("{".as_bytes()).unwrap_err();
+        assert!(matches!(err, KimiError::Json(_)));
+    }
+
+    #[test]
+    fn test_write_json_io_error() {
+        struct BrokenPipe;
+        impl Write for BrokenPipe {
+            fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::ErrorKind::BrokenPipe.into())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+        let err = tree()
+            .write_json(BrokenPipe, JsonFormat::V2, true)
+            .unwrap_err();
+        assert!(
+            matches!(&err, KimiError::Io(e) if e.kind() == std::io::ErrorKind::BrokenPipe),
+            "{:?}",
+            err
+        );
+        assert!(err.to_string().starts_with("I/O error: "));
+    }
+}
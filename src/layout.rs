@@ -0,0 +1,510 @@
+use crate::{CategoryTree, IdFormat, KimiError, ParseOptions, ParseReport, Sheet};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Rows looked at per column when detecting the layout
+const SAMPLE_ROWS: u32 = 2000;
+
+/// Where the category text sits on a worksheet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LayoutProfile {
+    /// 1-based columns holding one category per line
+    pub columns: Vec<u32>,
+    /// Groups of 1-based columns whose text is read together, e.g. the
+    /// id/code column and the name column of the 细类 table joined side by
+    /// side
+    pub paired_columns: Vec<ColumnGroup>,
+    /// Cells never read, e.g. a title block at `A1:F2`
+    pub ignore: Vec<CellRange>,
+}
+
+impl Default for LayoutProfile {
+    /// The layout of the 2022 大典 scan
+    fn default() -> Self {
+        LayoutProfile {
+            columns: vec![1, 3],
+            paired_columns: vec![ColumnGroup::pair(5, 6)],
+            ignore: Vec::new(),
+        }
+    }
+}
+
+impl LayoutProfile {
+    pub(crate) fn is_ignored(&self, col: u32, row: u32) -> bool {
+        self.ignore.iter().any(|range| range.contains(col, row))
+    }
+
+    /// Whether the text of `col` is parsed, alone or as the first of a group
+    pub(crate) fn reads(&self, col: u32) -> bool {
+        self.columns.contains(&col) || self.group_at(col).is_some()
+    }
+
+    /// The column group whose first column is `col`
+    pub(crate) fn group_at(&self, col: u32) -> Option<&ColumnGroup> {
+        self.paired_columns
+            .iter()
+            .find(|group| group.first() == col)
+    }
+
+    /// Infer the layout of `sheet` from how many lines of each column start
+    /// with a category id, or `None` if no column has any
+    ///
+    /// A column whose id lines are mostly bare ids is paired with the next
+    /// non-empty column when that one has no ids of its own.
+    pub fn detect(sheet: &Sheet) -> Option<LayoutProfile> {
+        Self::detect_with(sheet, &IdFormat::default()).expect("the default id format is valid")
+    }
+
+    /// Like `detect`, looking for ids written in `format`
+    pub fn detect_with(
+        sheet: &Sheet,
+        format: &IdFormat,
+    ) -> Result<Option<LayoutProfile>, KimiError> {
+        let (id_line, bare_id_line) = (format.line_regex()?, format.bare_line_regex()?);
+        let max_row = sheet.highest_row().min(SAMPLE_ROWS);
+        let scores = (1..=sheet.highest_column())
+            .map(|col| {
+                let mut score = ColumnScore::default();
+                for row in 1..=max_row {
+                    score.add(sheet.text(col, row), &id_line, &bare_id_line);
+                }
+                (col, score)
+            })
+            .collect::<Vec<_>>();
+
+        let mut layout = LayoutProfile {
+            columns: Vec::new(),
+            paired_columns: Vec::new(),
+            ignore: Vec::new(),
+        };
+        let mut paired = Vec::new();
+        for (i, (col, score)) in scores.iter().enumerate() {
+            if paired.contains(col) || !score.has_categories() {
+                continue;
+            }
+            let partner = scores[i + 1..]
+                .iter()
+                .find(|(_, next)| next.lines > 0)
+                .filter(|(_, next)| next.id_lines == 0);
+            match partner {
+                Some((next, _)) if score.bare_id_lines * 2 > score.id_lines => {
+                    layout.paired_columns.push(ColumnGroup::pair(*col, *next));
+                    paired.push(*next);
+                }
+                _ => layout.columns.push(*col),
+            }
+        }
+        Ok((!layout.columns.is_empty() || !layout.paired_columns.is_empty()).then_some(layout))
+    }
+}
+
+/// Line counts of one column, used by `LayoutProfile::detect`
+#[derive(Debug, Default)]
+struct ColumnScore {
+    lines: usize,
+    id_lines: usize,
+    bare_id_lines: usize,
+}
+
+impl ColumnScore {
+    fn add(&mut self, cell_text: &str, id_line: &Regex, bare_id_line: &Regex) {
+        for line in cell_text.lines().filter(|line| !line.trim().is_empty()) {
+            self.lines += 1;
+            if id_line.is_match(line) {
+                self.id_lines += 1;
+                if bare_id_line.is_match(line) {
+                    self.bare_id_lines += 1;
+                }
+            }
+        }
+    }
+
+    /// At least one line in ten starts with an id
+    fn has_categories(&self) -> bool {
+        self.id_lines > 0 && self.id_lines * 10 >= self.lines
+    }
+}
+
+/// How the texts of a column group are put together
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JoinRule {
+    /// Each line of the first column next to the lines beside it in the
+    /// others, as an id column and its name column
+    #[default]
+    SideBySide,
+    /// The text of each column following that of the one before, as a
+    /// column of names split over two by the scan
+    Stacked,
+}
+
+/// Two or more 1-based columns read together, written as `[5, 6]` or, with
+/// a join rule, as `{ columns = [2, 3, 4], join = "stacked" }`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "ColumnGroupSpec", into = "ColumnGroupSpec")]
+pub struct ColumnGroup {
+    columns: Vec<u32>,
+    join: JoinRule,
+}
+
+impl ColumnGroup {
+    /// `columns` read together with `join`; fails unless there are two or
+    /// more of them, all 1-based
+    pub fn new(columns: Vec<u32>, join: JoinRule) -> Result<Self, KimiError> {
+        if columns.len() < 2 || columns.contains(&0) {
+            return Err(KimiError::InvalidColumnGroup(columns));
+        }
+        Ok(ColumnGroup { columns, join })
+    }
+
+    /// An id column and the name column right of it, joined side by side
+    pub fn pair(first: u32, second: u32) -> Self {
+        ColumnGroup {
+            columns: vec![first, second],
+            join: JoinRule::SideBySide,
+        }
+    }
+
+    /// The column the text of the group is reported at
+    pub fn first(&self) -> u32 {
+        self.columns[0]
+    }
+
+    pub fn columns(&self) -> &[u32] {
+        &self.columns
+    }
+
+    pub fn join(&self) -> JoinRule {
+        self.join
+    }
+}
+
+/// `ColumnGroup` as written in a config file
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum ColumnGroupSpec {
+    Columns(Vec<u32>),
+    Table(ColumnGroupTable),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ColumnGroupTable {
+    columns: Vec<u32>,
+    #[serde(default)]
+    join: JoinRule,
+}
+
+impl TryFrom<ColumnGroupSpec> for ColumnGroup {
+    type Error = String;
+
+    fn try_from(spec: ColumnGroupSpec) -> Result<Self, Self::Error> {
+        let (columns, join) = match spec {
+            ColumnGroupSpec::Columns(columns) => (columns, JoinRule::default()),
+            ColumnGroupSpec::Table(table) => (table.columns, table.join),
+        };
+        ColumnGroup::new(columns, join).map_err(|e| e.to_string())
+    }
+}
+
+impl From<ColumnGroup> for ColumnGroupSpec {
+    fn from(group: ColumnGroup) -> Self {
+        match group.join {
+            JoinRule::SideBySide => ColumnGroupSpec::Columns(group.columns),
+            join => ColumnGroupSpec::Table(ColumnGroupTable {
+                columns: group.columns,
+                join,
+            }),
+        }
+    }
+}
+
+/// A rectangular block of cells written as `A1:F3` or `B7`, bounds included
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct CellRange {
+    /// 1-based (column, row) of the top left cell
+    pub first: (u32, u32),
+    /// 1-based (column, row) of the bottom right cell
+    pub last: (u32, u32),
+}
+
+impl CellRange {
+    pub fn contains(&self, col: u32, row: u32) -> bool {
+        (self.first.0..=self.last.0).contains(&col) && (self.first.1..=self.last.1).contains(&row)
+    }
+}
+
+/// The cell a category was read from, shown as `Sheet1!C5`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CellRef {
+    pub sheet: String,
+    /// 1-based row
+    pub row: u32,
+    /// 1-based column, the first of a column group
+    pub col: u32,
+}
+
+impl fmt::Display for CellRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}!{}{}", self.sheet, column_name(self.col), self.row)
+    }
+}
+
+/// 1-based column number of a column name, e.g. 1 for `A` and 27 for `AA`
+pub(crate) fn column_number(name: &str) -> Option<u32> {
+    if name.is_empty() || name.len() > 3 {
+        return None;
+    }
+    name.chars().try_fold(0, |acc, c| {
+        c.is_ascii_alphabetic()
+            .then(|| acc * 26 + (c.to_ascii_uppercase() as u32 - 'A' as u32 + 1))
+    })
+}
+
+fn column_name(mut number: u32) -> String {
+    let mut name = Vec::new();
+    while number > 0 {
+        number -= 1;
+        name.push(b'A' + (number % 26) as u8);
+        number /= 26;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap()
+}
+
+pub(crate) fn parse_cell(cell: &str) -> Option<(u32, u32)> {
+    let split = cell.find(|c: char| c.is_ascii_digit())?;
+    let col = column_number(&cell[..split])?;
+    let row = cell[split..].parse().ok().filter(|&row| row > 0)?;
+    Some((col, row))
+}
+
+impl FromStr for CellRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid cell range {:?}, expected e.g. A1:F3", s);
+        let (first, last) = s.trim().split_once(':').unwrap_or((s.trim(), s.trim()));
+        let first = parse_cell(first).ok_or_else(invalid)?;
+        let last = parse_cell(last).ok_or_else(invalid)?;
+        if first.0 > last.0 || first.1 > last.1 {
+            return Err(invalid());
+        }
+        Ok(CellRange { first, last })
+    }
+}
+
+impl TryFrom<String> for CellRange {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<CellRange> for String {
+    fn from(range: CellRange) -> Self {
+        range.to_string()
+    }
+}
+
+impl fmt::Display for CellRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}:{}{}",
+            column_name(self.first.0),
+            self.first.1,
+            column_name(self.last.0),
+            self.last.1
+        )
+    }
+}
+
+/// Inclusive 1-based bounds of the rows or columns to read, written as
+/// `3:500`, `A:F`, `3:` (open end) or `C` (a single one); columns may be
+/// given by number or letter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Span {
+    pub first: u32,
+    pub last: Option<u32>,
+}
+
+impl Span {
+    pub fn contains(&self, n: u32) -> bool {
+        n >= self.first && self.last.is_none_or(|last| n <= last)
+    }
+
+    /// The bounds clamped to `1..=max`
+    pub(crate) fn clamp(span: Option<&Span>, max: u32) -> std::ops::RangeInclusive<u32> {
+        match span {
+            Some(span) => span.first..=span.last.map_or(max, |last| last.min(max)),
+            None => 1..=max,
+        }
+    }
+}
+
+impl FromStr for Span {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid range {:?}, expected e.g. 3:500 or A:F", s);
+        let bound = |b: &str| {
+            let b = b.trim();
+            b.parse()
+                .ok()
+                .filter(|&n| n > 0)
+                .or_else(|| column_number(b))
+        };
+        let (first, last) = match s.split_once(':') {
+            Some((first, last)) => {
+                let first = if first.trim().is_empty() {
+                    1
+                } else {
+                    bound(first).ok_or_else(invalid)?
+                };
+                let last = if last.trim().is_empty() {
+                    None
+                } else {
+                    Some(bound(last).ok_or_else(invalid)?)
+                };
+                (first, last)
+            }
+            None => {
+                let n = bound(s).ok_or_else(invalid)?;
+                (n, Some(n))
+            }
+        };
+        if last.is_some_and(|last| last < first) {
+            return Err(invalid());
+        }
+        Ok(Span { first, last })
+    }
+}
+
+impl TryFrom<String> for Span {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Span> for String {
+    fn from(span: Span) -> Self {
+        span.to_string()
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.last {
+            Some(last) => write!(f, "{}:{}", self.first, last),
+            None => write!(f, "{}:", self.first),
+        }
+    }
+}
+
+/// Merged regions of a worksheet, whose text only sits in the top left cell
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MergedCells(Vec<CellRange>);
+
+impl MergedCells {
+    pub(crate) fn push(&mut self, range: CellRange) {
+        self.0.push(range);
+    }
+
+    /// The region containing the cell, if it is merged
+    pub(crate) fn find(&self, col: u32, row: u32) -> Option<&CellRange> {
+        self.0.iter().find(|range| range.contains(col, row))
+    }
+
+    /// First and last row of the region containing the cell
+    pub(crate) fn rows(&self, col: u32, row: u32) -> (u32, u32) {
+        self.find(col, row)
+            .map_or((row, row), |range| (range.first.1, range.last.1))
+    }
+}
+
+impl CategoryTree {
+    /// Like `build_from`, reading the columns given by `layout`
+    pub fn build_from_with_layout(
+        &mut self,
+        input_file: &str,
+        layout: &LayoutProfile,
+    ) -> Result<ParseReport, KimiError> {
+        let options = ParseOptions {
+            layout: Some(layout.clone()),
+            ..ParseOptions::default()
+        };
+        self.build_from_with(input_file, &options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cell_range() {
+        let range: CellRange = "B2:AA10".parse().unwrap();
+        assert_eq!(range.first, (2, 2));
+        assert_eq!(range.last, (27, 10));
+        assert!(range.contains(27, 2));
+        assert!(!range.contains(1, 5));
+        assert_eq!(range.to_string(), "B2:AA10");
+        assert_eq!("c3".parse::<CellRange>().unwrap().to_string(), "C3:C3");
+        assert!("F3:A1".parse::<CellRange>().is_err());
+        assert!("A0".parse::<CellRange>().is_err());
+
+        let span: Span = "3:500".parse().unwrap();
+        assert!(span.contains(3) && span.contains(500) && !span.contains(501));
+        assert_eq!("B:F".parse::<Span>().unwrap().to_string(), "2:6");
+        assert_eq!("3:".parse::<Span>().unwrap().last, None);
+        assert_eq!(Span::clamp(Some(&"3:".parse().unwrap()), 9), 3..=9);
+        assert!("5:2".parse::<Span>().is_err());
+    }
+
+    #[test]
+    fn test_column_group() {
+        let group = ColumnGroup::new(vec![2, 3, 4], JoinRule::Stacked).unwrap();
+        assert_eq!((group.first(), group.columns()), (2, &[2, 3, 4][..]));
+        assert_eq!(ColumnGroup::pair(5, 6).join(), JoinRule::SideBySide);
+        for columns in [vec![], vec![2], vec![0, 1]] {
+            assert!(matches!(
+                ColumnGroup::new(columns, JoinRule::SideBySide),
+                Err(KimiError::InvalidColumnGroup(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_detect() {
+        let mut sheet = Sheet::new("Sheet1");
+        assert_eq!(LayoutProfile::detect(&sheet), None);
+
+        sheet.set_text(1, 1, "分类体系表");
+        sheet.set_text(
+            2,
+            2,
+            "第二大类 2 (GBM20000) 专业技术人员\n2-01(GBM20100)科学研究人员",
+        );
+        sheet.set_text(3, 2, "12");
+        sheet.set_text(4, 2, "2-01-01(GBM20101)哲学研究人员");
+        sheet.set_text(6, 2, "2-01-01-01\n2-01-01-02\n2-01-01-03 S");
+        sheet.set_text(7, 2, "哲学研究\n人员\n经济学研究人员\n法学研究人员");
+        assert_eq!(
+            LayoutProfile::detect(&sheet),
+            Some(LayoutProfile {
+                columns: vec![2, 4],
+                paired_columns: vec![ColumnGroup::pair(6, 7)],
+                ignore: Vec::new(),
+            })
+        );
+    }
+}
@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Hierarchy level of a category, derived from the number of id segments
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Level {
+    /// 大类, e.g. "1"
+    Major,
+    /// 中类, e.g. "1-01"
+    Middle,
+    /// 小类, e.g. "1-01-00"
+    Minor,
+    /// 细类 (职业), e.g. "1-01-00-01"
+    Detail,
+}
+
+impl Level {
+    pub const ALL: [Level; 4] = [Level::Major, Level::Middle, Level::Minor, Level::Detail];
+
+    /// Level for an id with `depth` segments
+    pub fn from_depth(depth: usize) -> Option<Level> {
+        match depth {
+            1 => Some(Level::Major),
+            2 => Some(Level::Middle),
+            3 => Some(Level::Minor),
+            4 => Some(Level::Detail),
+            _ => None,
+        }
+    }
+
+    /// Number of id segments at this level
+    pub fn depth(self) -> usize {
+        self as usize + 1
+    }
+
+    /// Chinese name as used in the 大典
+    pub fn name(self) -> &'static str {
+        match self {
+            Level::Major => "大类",
+            Level::Middle => "中类",
+            Level::Minor => "小类",
+            Level::Detail => "细类",
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level_depth() {
+        for level in Level::ALL {
+            assert_eq!(Level::from_depth(level.depth()), Some(level));
+        }
+        assert_eq!(Level::from_depth(0), None);
+        assert_eq!(Level::from_depth(5), None);
+        assert_eq!(Level::Detail.to_string(), "细类");
+    }
+}
@@ -1,104 +1,338 @@
-use indexmap::IndexMap;
-use regex::Regex;
-use serde::{Deserialize, Serialize};
-use serde_json;
-use std::error::Error;
-use umya_spreadsheet::reader::xlsx;
+mod align;
+mod append;
+mod archive;
+#[cfg(feature = "browse")]
+mod browse;
+#[cfg(feature = "cache")]
+mod cache;
+mod compress;
+mod config;
+mod diagnostics;
+mod diff;
+#[cfg(feature = "docx")]
+mod docx;
+mod edit;
+mod edition;
+mod error;
+mod export;
+#[cfg(feature = "fetch")]
+mod fetch;
+#[cfg(feature = "html")]
+mod html;
+mod id;
+mod index;
+mod iter;
+mod json;
+mod layout;
+mod level;
+mod lines;
+mod merge;
+#[cfg(feature = "ocr")]
+mod ocr;
+#[cfg(any(feature = "pdf", feature = "ocr"))]
+mod page;
+#[cfg(feature = "pdf")]
+mod pdf;
+#[cfg(feature = "reference")]
+mod reference;
+mod report;
+mod rich_text;
+mod rows;
+mod search;
+#[cfg(feature = "serve")]
+mod serve;
+#[cfg(feature = "shell")]
+mod shell;
+mod source;
+mod stats;
+mod transform;
+mod tree;
+mod validate;
+
+use align::align_pair;
+#[cfg(feature = "browse")]
+pub use browse::{Browser, Row, browse};
+#[cfg(feature = "cache")]
+pub use cache::ParseCache;
+pub use compress::{Compression, write_compressed};
+pub use config::{
+    ArtifactPatterns, CacheConfig, Config, OutputConfig, ParseMode, ParseOptions, SheetSelection,
+    read_suffixes,
+};
+pub use diagnostics::{Diagnostics, Warning};
+use diagnostics::{LogWarnings, check_desc};
+pub use diff::{CategoryChange, TreeDiff};
+#[cfg(feature = "docx")]
+pub use docx::DocxSource;
+pub use edition::{Discrepancy, Edition, Verification};
+pub use error::KimiError;
+pub use export::MarkdownStyle;
+#[cfg(feature = "arrow")]
+pub use export::arrow_schema;
+#[cfg(feature = "html")]
+pub use html::HtmlSource;
+pub use id::{CategoryId, IdFormat, compare_ids};
+pub use index::CodeIndex;
+pub use iter::{BfsIter, Iter, Leaf, Leaves};
+pub use json::{JsonFormat, json_schema};
+pub use layout::{CellRange, CellRef, ColumnGroup, JoinRule, LayoutProfile, Span};
+pub use level::Level;
+pub use lines::{ChunkEnd, Line, LineMerger, MergeStep, MergeTrace, Merger, SuffixMerger};
+pub use merge::{MergeReport, MergeStrategy, VolumeConflict, VolumeMerger, VolumeOverlap};
+#[cfg(feature = "ocr")]
+pub use ocr::OcrSource;
+#[cfg(feature = "pdf")]
+pub use pdf::PdfSource;
+#[cfg(feature = "reference")]
+pub use reference::{Mismatch, ReferenceCategory, reference_categories};
+use report::check_mode;
+pub use report::{CellReport, Correction, ParseReport, RejectReason, RejectedChunk};
+pub use rows::{
+    CellText, OnProgress, Progress, RowCells, parse_rows, parse_rows_from, parse_rows_with,
+};
+#[cfg(feature = "serve")]
+pub use serve::{router, serve};
+#[cfg(feature = "shell")]
+pub use shell::{Shell, run_shell};
+#[cfg(feature = "calamine")]
+pub use source::CalamineSource;
+#[cfg(feature = "ods")]
+pub use source::OdsSource;
+#[cfg(feature = "xls")]
+pub use source::XlsSource;
+use source::open_source;
+pub use source::{Backend, Confidence, Sheet, SpreadsheetSource, UmyaSource};
+pub use stats::{LevelCounts, TreeStats};
+pub use tree::Tree;
+pub use validate::Problem;
+
+use regex::RegexSet;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize, Serializer};
+use std::fmt;
+use std::io;
+use std::path::Path;
+use unicode_normalization::UnicodeNormalization;
 
 /// Define Category
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct Category {
     pub id: String,           // e.g. "1-01"
     pub code: Option<String>, // e.g. "GBM10100"
     pub desc: Option<String>, // e.g. "中国共产党机关和基层组织负责人"
+    #[serde(default)]
+    pub is_green: bool, // marked "L" (绿色职业)
+    #[serde(default)]
+    pub is_digital: bool, // marked "S" (数字职业)
+    /// Lowest OCR word confidence, 0 to 100, of the text it was read from;
+    /// `None` unless read from hOCR or ALTO output
+    #[serde(default)]
+    pub confidence: Option<u8>,
+    /// Cell the category was read from; `None` unless built from a
+    /// workbook or document
+    #[serde(default)]
+    pub source: Option<CellRef>,
 }
 
-/// Hierarchical tree structure
-#[derive(Debug, Serialize, Deserialize)]
-pub struct CategoryTree {
-    children: IndexMap<String, CategoryTree>,
-    categories: Vec<Category>,
+// Where a category was read from is not part of what it is: the same
+// category read from two volumes or two editions compares equal
+impl PartialEq for Category {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+            && self.code == other.code
+            && self.desc == other.desc
+            && self.is_green == other.is_green
+            && self.is_digital == other.is_digital
+            && self.confidence == other.confidence
+    }
 }
 
-impl CategoryTree {
-    pub fn new() -> Self {
-        Self {
-            children: IndexMap::new(),
-            categories: Vec::new(),
+impl Eq for Category {}
+
+impl Category {
+    /// Marker suffix as printed in the 大典, e.g. "L/S"
+    pub fn marker(&self) -> Option<&'static str> {
+        match (self.is_green, self.is_digital) {
+            (true, true) => Some("L/S"),
+            (true, false) => Some("L"),
+            (false, true) => Some("S"),
+            (false, false) => None,
         }
     }
 
-    pub fn insert(&mut self, id: String, category: Category) {
-        let mut node = self;
-        let path = id.split('-').collect::<Vec<&str>>();
-        for level in path {
-            node = node
-                .children
-                .entry(level.to_string())
-                .or_insert_with(CategoryTree::new);
+    /// Level derived from the number of id segments
+    pub fn level(&self) -> Option<Level> {
+        Level::from_depth(self.id.split('-').count())
+    }
+}
+
+// Serialized by hand to include the derived level in human-readable formats;
+// binary formats keep exactly the fields `Deserialize` expects. The OCR
+// confidence and the source cell are left out of human-readable output when
+// unknown
+impl Serialize for Category {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let human_readable = serializer.is_human_readable();
+        let len = if human_readable { 8 } else { 7 };
+        let mut state = serializer.serialize_struct("Category", len)?;
+        state.serialize_field("id", &self.id)?;
+        state.serialize_field("code", &self.code)?;
+        state.serialize_field("desc", &self.desc)?;
+        if human_readable {
+            state.serialize_field("level", &self.level())?;
+        }
+        state.serialize_field("is_green", &self.is_green)?;
+        state.serialize_field("is_digital", &self.is_digital)?;
+        if human_readable && self.confidence.is_none() {
+            state.skip_field("confidence")?;
+        } else {
+            state.serialize_field("confidence", &self.confidence)?;
+        }
+        if human_readable && self.source.is_none() {
+            state.skip_field("source")?;
+        } else {
+            state.serialize_field("source", &self.source)?;
         }
-        node.categories.push(category);
+        state.end()
+    }
+}
+
+// One-line summary: level, id, name, GBM code and marker
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(level) = self.level() {
+            write!(f, "{} ", level)?;
+        }
+        f.write_str(&self.id)?;
+        if let Some(desc) = &self.desc {
+            write!(f, " {}", desc)?;
+        }
+        if let Some(code) = &self.code {
+            write!(f, " [{}]", code)?;
+        }
+        if let Some(marker) = self.marker() {
+            write!(f, " {}", marker)?;
+        }
+        Ok(())
+    }
+}
+
+/// Tree of categories, see `Tree`
+pub type CategoryTree = Tree<Category>;
+
+impl CategoryTree {
+    /// Build a tree from categories obtained elsewhere (database rows, API
+    /// responses), placing each one by its id
+    pub fn from_categories(categories: Vec<Category>) -> Self {
+        categories.into_iter().collect()
+    }
+
+    /// Find the category with the given id, e.g. "2-02-38-07"
+    pub fn get(&self, id: &str) -> Option<&Category> {
+        self.get_node(id)?.items.iter().find(|c| c.id == id)
+    }
+
+    /// Category one level above `id`, if present in the tree
+    pub fn parent_of(&self, id: &str) -> Option<&Category> {
+        self.get(&CategoryId::from(id).parent_id()?)
+    }
+
+    /// Build a GBM code index for repeated lookups
+    pub fn build_code_index(&self) -> CodeIndex<'_> {
+        CodeIndex::new(self)
+    }
+
+    /// Find the category with the given GBM code, e.g. "GBM10100",
+    /// together with its path segments
+    pub fn find_by_code(&self, code: &str) -> Option<(Vec<&str>, &Category)> {
+        self.build_code_index()
+            .get(code)
+            .map(|(path, cat)| (path.to_vec(), cat))
     }
 
     pub fn pretty_print_json(&self) {
-        let json = serde_json::to_string_pretty(&self).unwrap();
-        println!("{}", json);
+        println!("{}", self.to_json_string());
     }
 
     pub fn pretty_print(&self) {
-        self.pretty_print_inner("", true);
+        self.pretty_print_to(io::stdout().lock())
+            .expect("failed to write to stdout");
+    }
+
+    /// Pretty JSON representation of the tree
+    pub fn to_json_string(&self) -> String {
+        self.to_json_string_with(JsonFormat::V1)
+    }
+
+    /// Tree representation as printed by `pretty_print`
+    pub fn to_tree_string(&self) -> String {
+        let mut buf = Vec::new();
+        self.pretty_print_to(&mut buf).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    /// Write the tree representation to `w`
+    pub fn pretty_print_to<W: io::Write>(&self, mut w: W) -> io::Result<()> {
+        self.pretty_print_inner(&mut w, "", true)
     }
 
-    fn pretty_print_inner(&self, prefix: &str, is_last: bool) {
+    fn pretty_print_inner<W: io::Write>(
+        &self,
+        w: &mut W,
+        prefix: &str,
+        is_last: bool,
+    ) -> io::Result<()> {
         let branch = if is_last { "└── " } else { "├── " };
 
-        for (i, cat) in self.categories.iter().enumerate() {
-            let connector = if i == self.categories.len() - 1 && self.children.is_empty() {
+        for (i, cat) in self.items.iter().enumerate() {
+            let connector = if i == self.items.len() - 1 && self.children.is_empty() {
                 "└── "
             } else {
                 "├── "
             };
+            let marker = cat.marker().map_or(String::new(), |m| format!(" {}", m));
             if let Some(code) = &cat.code {
-                println!(
-                    "{}{}{} [{}, {}]",
+                writeln!(
+                    w,
+                    "{}{}{} [{}, {}]{}",
                     prefix,
                     connector,
                     cat.id,
                     code,
-                    cat.desc.as_ref().map_or("", |v| v)
-                );
+                    cat.desc.as_ref().map_or("", |v| v),
+                    marker
+                )?;
             } else {
-                println!(
-                    "{}{}{} [{}]",
+                writeln!(
+                    w,
+                    "{}{}{} [{}]{}",
                     prefix,
                     connector,
                     cat.id,
-                    cat.desc.as_ref().map_or("", |v| v)
-                );
+                    cat.desc.as_ref().map_or("", |v| v),
+                    marker
+                )?;
             }
         }
 
         let child_count = self.children.len();
         for (i, (key, child)) in self.children.iter().enumerate() {
             let is_last_child = i == child_count - 1;
-            println!("{}{}{}", prefix, branch, key);
+            writeln!(w, "{}{}{}", prefix, branch, key)?;
 
             let new_prefix = if is_last {
                 format!("{}    ", prefix)
             } else {
                 format!("{}│   ", prefix)
             };
-            child.pretty_print_inner(&new_prefix, is_last_child);
+            child.pretty_print_inner(w, &new_prefix, is_last_child)?;
         }
+        Ok(())
     }
 
-    pub fn parse_one_column(&mut self, cell_text: &str) -> Result<(), Box<dyn Error>> {
-        let chunks = construct_lines(cell_text);
-        let parsed = parse_categories(&chunks)?;
-        for cat in parsed {
-            let cat_clone = cat.clone();
-            self.insert(cat.id, cat_clone);
-        }
+    pub fn parse_one_column(&mut self, cell_text: &str) -> Result<(), KimiError> {
+        let parsed = column_categories(cell_text, &ParseOptions::default(), &mut |_| {})?;
+        self.extend(parsed.matched.into_categories()?);
         Ok(())
     }
 
@@ -106,161 +340,564 @@ impl CategoryTree {
         &mut self,
         cell_first: &str,
         cell_second: &str,
-    ) -> Result<(), Box<dyn Error>> {
-        let lines_first = construct_lines(cell_first);
-        let lines_second = construct_lines(cell_second);
-
-        // if lines_first.len() != lines_second.len() {
-        //     println!(
-        //         "{}, {}, {:?}, {:?}",
-        //         lines_first.len(),
-        //         lines_second.len(),
-        //         lines_first,
-        //         lines_second
-        //     );
-        //     return Ok(());
-        // }
-
-        // Zip the lines together and concatenate each pair
-        let mut concatenated_lines = Vec::new();
-        for (a, b) in lines_first.iter().zip(lines_second.iter()) {
-            let combined = format!("{} {}", a, b).trim().to_string();
-            if !combined.is_empty() {
-                concatenated_lines.push(combined);
-            }
-        }
+    ) -> Result<(), KimiError> {
+        let parsed = group_categories(
+            &[cell_first, cell_second],
+            JoinRule::SideBySide,
+            &ParseOptions::default(),
+            &mut |_| {},
+        )?;
+        self.extend(parsed.matched.into_categories()?);
+        Ok(())
+    }
 
-        let final_text = concatenated_lines.join("\n\n\n");
-        let chunks = construct_lines(&final_text);
-        let parsed = parse_categories(&chunks)?;
-        for cat in parsed {
-            let cat_clone = cat.clone();
-            self.insert(cat.id, cat_clone);
+    pub fn build_from(&mut self, input_file: &str) -> Result<ParseReport, KimiError> {
+        self.build_from_with(input_file, &ParseOptions::default())
+    }
+
+    /// Like `build_from`, reading the columns and applying the heuristics
+    /// given by `options`
+    pub fn build_from_with(
+        &mut self,
+        input_file: &str,
+        options: &ParseOptions,
+    ) -> Result<ParseReport, KimiError> {
+        self.build_from_with_diagnostics(input_file, options, &mut LogWarnings)
+    }
+
+    /// Like `build_from_with`, handing the warnings of the parse to
+    /// `diagnostics` instead of logging them
+    #[tracing::instrument(name = "build_from", skip(self, options, diagnostics))]
+    pub fn build_from_with_diagnostics(
+        &mut self,
+        input_file: &str,
+        options: &ParseOptions,
+        diagnostics: &mut dyn Diagnostics,
+    ) -> Result<ParseReport, KimiError> {
+        let path = Path::new(input_file);
+        if path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("zip"))
+        {
+            return self.zip_entries(
+                io::BufReader::new(std::fs::File::open(input_file)?),
+                options,
+                diagnostics,
+            );
         }
+        self.build_from_source_with_diagnostics(
+            &mut *open_source(path, options.backend)?,
+            options,
+            diagnostics,
+        )
+    }
 
+    /// Like `build_from_with`, reading the workbook bytes from `reader`,
+    /// e.g. stdin buffered in a `Cursor`
+    pub fn build_from_reader<R: io::Read + io::Seek>(
+        &mut self,
+        reader: R,
+        options: &ParseOptions,
+    ) -> Result<ParseReport, KimiError> {
+        self.build_from_source(&mut *options.backend.from_reader(reader)?, options)
+    }
+
+    /// Parse the sheets of `source` selected by `options`; its backend
+    /// setting is ignored
+    pub fn build_from_source(
+        &mut self,
+        source: &mut dyn SpreadsheetSource,
+        options: &ParseOptions,
+    ) -> Result<ParseReport, KimiError> {
+        self.build_from_source_with_diagnostics(source, options, &mut LogWarnings)
+    }
+
+    /// Like `build_from_source`, handing the warnings of the parse to
+    /// `diagnostics` instead of logging them
+    pub fn build_from_source_with_diagnostics(
+        &mut self,
+        source: &mut dyn SpreadsheetSource,
+        options: &ParseOptions,
+        diagnostics: &mut dyn Diagnostics,
+    ) -> Result<ParseReport, KimiError> {
+        let mut report = ParseReport::default();
+        parse_rows_from(source, options, |row| {
+            for cat in row_categories(row, options, &mut report, diagnostics)? {
+                if let Some(first) = self.get(&cat.id) {
+                    diagnostics.warn(Warning::DuplicateId {
+                        id: cat.id.clone(),
+                        first: first.source.clone(),
+                        second: cat.source.clone(),
+                    });
+                }
+                self.insert(cat.id.clone(), cat);
+            }
+            Ok(())
+        })?;
+        check_mode(options, &report)?;
+        self.check_valid(options)?;
+        Ok(report)
+    }
+
+    /// With `options.validate`, fail if the tree does not pass `validate`
+    fn check_valid(&self, options: &ParseOptions) -> Result<(), KimiError> {
+        if options.validate {
+            let problems = self.validate_with(&options.id_format)?;
+            if !problems.is_empty() {
+                return Err(KimiError::Invalid(problems));
+            }
+        }
         Ok(())
     }
+}
 
-    pub fn build_from(&mut self, input_file: &str) -> Result<(), Box<dyn std::error::Error>> {
-        let mut book = xlsx::read(input_file)?;
-        let sheet = book.get_sheet_mut(&0).unwrap();
+/// Categories read from one cell
+struct CellCategories {
+    matched: MatchedChunks,
+    /// Line counts of the lines joined so far and of the next column of a
+    /// group, for each column where they differ
+    uneven: Vec<(usize, usize)>,
+    /// Lines of a column of a group, by its offset from the first, left
+    /// without a partner
+    leftover: Vec<(u32, Vec<String>)>,
+}
+
+/// Categories of one cell with a category per line
+fn column_categories(
+    cell_text: &str,
+    options: &ParseOptions,
+    trace: &mut dyn FnMut(MergeStep),
+) -> Result<CellCategories, KimiError> {
+    let chunks = construct_lines_traced(cell_text, options, trace);
+    Ok(CellCategories {
+        matched: match_chunks(&chunks, &options.id_format)?,
+        uneven: Vec::new(),
+        leftover: Vec::new(),
+    })
+}
+
+/// Categories of a column group whose texts are joined by `join`
+fn group_categories(
+    texts: &[&str],
+    join: JoinRule,
+    options: &ParseOptions,
+    trace: &mut dyn FnMut(MergeStep),
+) -> Result<CellCategories, KimiError> {
+    if join == JoinRule::Stacked {
+        return column_categories(&texts.join("\n"), options, trace);
+    }
+
+    let id_line = options.id_format.line_regex()?;
+    let mut uneven = Vec::new();
+    let mut leftover = Vec::new();
+    let mut joined = texts[0].to_string();
+    for (offset, text) in (1..).zip(&texts[1..]) {
+        let lines_first = construct_lines_traced(&joined, options, trace);
+        let lines_second = construct_lines_traced(text, options, trace);
 
-        let max_row = sheet.get_highest_row();
-        let max_col = sheet.get_highest_column();
-
-        for row in 1..=max_row {
-            for col in 1..=max_col {
-                let cell_value = sheet.get_cell_value((col, row));
-                let cell_text = cell_value.get_value().to_string();
-
-                if col == 1 {
-                    if let Some(first_text) = normalize_first_category(&cell_text) {
-                        self.parse_one_column(&first_text)?;
-                    } else {
-                        self.parse_one_column(&cell_text)?;
-                    }
-                } else if col == 3 {
-                    self.parse_one_column(&cell_text)?;
-                } else if col == 5 {
-                    let cell_first = sheet.get_cell_value((col, row));
-                    let cell_first = cell_first.get_value().to_string();
-
-                    let cell_second = sheet.get_cell_value((col + 1, row));
-                    let cell_second = cell_second.get_value().to_string();
-                    self.parse_two_columns(cell_first.trim(), cell_second.trim())?;
+        let mut concatenated_lines = Vec::new();
+        if lines_first.len() != lines_second.len() {
+            uneven.push((lines_first.len(), lines_second.len()));
+            // cut the names anew, one for each id
+            let alignment = align_pair(&joined, text, &id_line, options);
+            concatenated_lines = alignment.lines;
+            for (offset, lines) in [
+                (0, alignment.leftover_first),
+                (offset, alignment.leftover_second),
+            ] {
+                if !lines.is_empty() {
+                    leftover.push((offset, lines));
+                }
+            }
+        } else {
+            // Zip the lines together and concatenate each pair
+            for (a, b) in lines_first.iter().zip(lines_second.iter()) {
+                let combined = format!("{} {}", a, b).trim().to_string();
+                if !combined.is_empty() {
+                    concatenated_lines.push(combined);
                 }
             }
         }
+        joined = concatenated_lines.join("\n\n\n");
+    }
 
-        Ok(())
+    let chunks = construct_lines_traced(&joined, options, trace);
+    Ok(CellCategories {
+        matched: match_chunks(&chunks, &options.id_format)?,
+        uneven,
+        leftover,
+    })
+}
+
+/// Categories of one row read by `parse_rows`, in column order, noting
+/// the cells read and the chunks rejected in `report`
+pub(crate) fn row_categories(
+    row: RowCells,
+    options: &ParseOptions,
+    report: &mut ParseReport,
+    diagnostics: &mut dyn Diagnostics,
+) -> Result<Vec<Category>, KimiError> {
+    let mut categories = Vec::new();
+    for cell in row.cells {
+        let cell = if options.nfkc { cell.nfkc() } else { cell };
+        let col = cell.col();
+        let mut steps = Vec::new();
+        let mut trace = |step| {
+            if options.trace_merges {
+                steps.push(step);
+            }
+        };
+        let parsed = match &cell {
+            CellText::Column { text, .. } => match normalize_first_category(text) {
+                Some(first_text) => column_categories(&first_text, options, &mut trace),
+                None => column_categories(text, options, &mut trace),
+            },
+            CellText::Group { texts, join, .. } => {
+                let texts = texts.iter().map(|text| text.trim()).collect::<Vec<_>>();
+                group_categories(&texts, *join, options, &mut trace)
+            }
+        };
+        let in_cell = |e| KimiError::CellParse {
+            row: row.row,
+            col,
+            source: Box::new(e),
+        };
+        let CellCategories {
+            matched:
+                MatchedChunks {
+                    categories: mut parsed,
+                    unmatched,
+                    malformed,
+                },
+            uneven,
+            leftover,
+        } = parsed.map_err(in_cell)?;
+        if options.mode == ParseMode::Strict
+            && let Some((id, _)) = malformed.first()
+        {
+            return Err(in_cell(KimiError::MalformedId(id.clone())));
+        }
+        let source = CellRef {
+            sheet: row.sheet.clone(),
+            row: row.row,
+            col,
+        };
+        if !steps.is_empty() {
+            report.merges.push(MergeTrace {
+                cell: source.clone(),
+                steps,
+            });
+        }
+        for (first, second) in uneven {
+            diagnostics.warn(Warning::UnevenPair {
+                cell: source.clone(),
+                first,
+                second,
+            });
+        }
+        for (offset, lines) in leftover {
+            diagnostics.warn(Warning::LeftoverLines {
+                cell: CellRef {
+                    col: col + offset,
+                    ..source.clone()
+                },
+                lines,
+            });
+        }
+        for cat in &mut parsed {
+            cat.source = Some(source.clone());
+            #[cfg(feature = "reference")]
+            if options.correct_names
+                && let Some(correction) = reference::correct_desc(cat)
+            {
+                report.corrections.push(correction);
+            }
+            check_desc(cat, diagnostics);
+        }
+        let reject = |text: String, reason| RejectedChunk {
+            sheet: row.sheet.clone(),
+            row: row.row,
+            col,
+            text,
+            reason,
+        };
+        report.cells.push(CellReport {
+            sheet: row.sheet.clone(),
+            row: row.row,
+            col,
+            categories: parsed.len(),
+        });
+        report.matched += parsed.len();
+        report.rejected.extend(
+            unmatched
+                .into_iter()
+                .map(|chunk| reject(chunk, RejectReason::Unmatched)),
+        );
+        report.rejected.extend(
+            malformed
+                .into_iter()
+                .map(|(_, chunk)| reject(chunk, RejectReason::MalformedId)),
+        );
+        report.rejected.extend(
+            parsed
+                .iter()
+                .filter(|cat| cat.desc.as_deref().is_none_or(str::is_empty))
+                .map(|cat| reject(cat.id.clone(), RejectReason::EmptyDesc)),
+        );
+        match &cell {
+            CellText::Column { confidence, .. } => score_categories(&mut parsed, confidence, None),
+            CellText::Group {
+                join: JoinRule::Stacked,
+                confidence,
+                ..
+            } => {
+                let mut all = Confidence::default();
+                confidence.iter().for_each(|column| all.append(column));
+                score_categories(&mut parsed, &all, None)
+            }
+            CellText::Group { confidence, .. } => {
+                let mut descs = Confidence::default();
+                confidence[1..]
+                    .iter()
+                    .for_each(|column| descs.append(column));
+                score_categories(&mut parsed, &confidence[0], Some(&descs))
+            }
+        }
+        categories.extend(parsed);
+    }
+    Ok(categories)
+}
+
+/// Set the confidence of each category to the lowest of the characters of
+/// its id, code and name, looked up in order in `ids`, or in `descs` for the
+/// code and name of a column group joined side by side
+fn score_categories(categories: &mut [Category], ids: &Confidence, descs: Option<&Confidence>) {
+    if ids.is_empty() && descs.is_none_or(Confidence::is_empty) {
+        return;
+    }
+    let (mut at_id, mut at_desc) = (0, 0);
+    for cat in categories {
+        let id = ids.lowest(&cat.id, &mut at_id);
+        let (descs, at) = match descs {
+            Some(descs) => (descs, &mut at_desc),
+            None => (ids, &mut at_id),
+        };
+        let rest = [&cat.code, &cat.desc]
+            .into_iter()
+            .flatten()
+            .map(|text| descs.lowest(text, at))
+            .collect::<Vec<_>>();
+        cat.confidence = std::iter::once(id).chain(rest).flatten().min();
+    }
+}
+
+impl FromIterator<Category> for CategoryTree {
+    fn from_iter<I: IntoIterator<Item = Category>>(iter: I) -> Self {
+        let mut tree = CategoryTree::new();
+        tree.extend(iter);
+        tree
+    }
+}
+
+impl Extend<Category> for CategoryTree {
+    fn extend<I: IntoIterator<Item = Category>>(&mut self, iter: I) {
+        for cat in iter {
+            self.insert(cat.id.clone(), cat);
+        }
     }
 }
 
 /// Parse categories
-pub fn parse_categories(chunks: &Vec<String>) -> Result<Vec<Category>, Box<dyn Error>> {
-    let mut categories = Vec::new();
+pub fn parse_categories(chunks: &[String]) -> Result<Vec<Category>, KimiError> {
+    match_chunks(chunks, &IdFormat::default())?.into_categories()
+}
 
-    // Regex:
-    // - id: one or more numbers separated by '-' at the start
-    // - optional code: (GBM digits)
-    // - description: rest of the string
-    let re = Regex::new(
-        r"(?x)
-        ^\s*
-        (?P<id>(?:\d+-?)+)          # id: 1-01 or 1-01-01-01
-        (?:\s*\(\s*(?P<code>GBM\s*\d+)\s*\))?  # optional code
-        \s*(?P<desc>.*)?$            # description
-    ",
-    )?;
+/// Chunks sorted by whether they parse as a category
+#[derive(Default)]
+struct MatchedChunks {
+    categories: Vec<Category>,
+    /// Chunks without a category id
+    unmatched: Vec<String>,
+    /// Ids with an empty segment, e.g. "2-04-", and their chunks
+    malformed: Vec<(String, String)>,
+}
+
+impl MatchedChunks {
+    /// The categories, or an error with the first malformed id
+    fn into_categories(self) -> Result<Vec<Category>, KimiError> {
+        match self.malformed.into_iter().next() {
+            Some((id, _)) => Err(KimiError::MalformedId(id)),
+            None => Ok(self.categories),
+        }
+    }
+}
+
+/// Like `parse_categories`, also returning the chunks that are not
+/// categories
+#[tracing::instrument(name = "parse_categories", skip_all, fields(chunks = chunks.len()))]
+fn match_chunks(chunks: &[String], format: &IdFormat) -> Result<MatchedChunks, KimiError> {
+    let mut matched = MatchedChunks::default();
+    let re = format.chunk_regex()?;
 
     for chunk in chunks {
         if let Some(cap) = re.captures(chunk) {
             let id = cap
                 .name("id")
-                .map(|m| m.as_str().trim().to_string())
+                .map(|m| format.normalize(m.as_str().trim()))
                 .unwrap_or_default();
+            if id.split('-').any(|seg| seg.is_empty()) {
+                tracing::debug!(chunk, id, "empty segment in category id");
+                matched.malformed.push((id, chunk.clone()));
+                continue;
+            }
             let code = cap.name("code").map(|m| m.as_str().replace(' ', ""));
             let desc = cap.name("desc").map(|m| m.as_str().replace(' ', ""));
-            // println!("{}\n", chunk);
-            categories.push(Category { id, code, desc });
+            let marker = cap.name("marker").map_or("", |m| m.as_str());
+            matched.categories.push(Category {
+                id,
+                code,
+                desc,
+                is_green: marker.contains('L'),
+                is_digital: marker.contains('S'),
+                confidence: None,
+                source: None,
+            });
+        } else {
+            tracing::debug!(chunk, "no category id at the start of chunk");
+            matched.unmatched.push(chunk.clone());
         }
     }
 
-    Ok(categories)
+    Ok(matched)
 }
 
 /// Construct lines
 /// TODO: this function is tricy to make it robust
 pub fn construct_lines(text: &str) -> Vec<String> {
-    let mut result = Vec::new();
-    let mut buffer = String::new();
+    construct_lines_with(text, &ParseOptions::default())
+}
+
+/// Like `construct_lines`, cleaning the lines with the artifacts of
+/// `options` and joining them with its `merger`
+pub fn construct_lines_with(text: &str, options: &ParseOptions) -> Vec<String> {
+    construct_lines_traced(text, options, &mut |_| {})
+}
+
+/// Like `construct_lines_with`, handing each decision of the merger to
+/// `trace`
+#[tracing::instrument(name = "construct_lines", skip_all, fields(lines = text.lines().count()))]
+pub fn construct_lines_traced(
+    text: &str,
+    options: &ParseOptions,
+    trace: &mut dyn FnMut(MergeStep),
+) -> Vec<String> {
+    let lines = clean_lines(text, options);
+    options.merger.merge_traced(&lines, options, trace)
+}
 
+/// The lines of `text` as handed to the line merger: without whitespace,
+/// in NFKC unless turned off, with confused id characters replaced and the
+/// artifacts of `options` removed, and the marker split off
+pub(crate) fn clean_lines(text: &str, options: &ParseOptions) -> Vec<Line> {
     let lines: Vec<String> = text
         .lines()
         .map(|l| l.chars().filter(|c| !c.is_whitespace()).collect())
         .collect();
 
-    let mut i = 0;
-    while i < lines.len() {
-        let line = &lines[i].replace("L", "").replace("S", "").replace("/", "");
-        let line = line.trim();
-        // println!("{} {}", line, line.len());
-        if line.is_empty()
-            || line.ends_with("责人")
-            || line.ends_with("员")
-            || line.ends_with("护士")
-            || line.ends_with("制片人")
-            || line.ends_with("师")
-            || line.ends_with("官")
-            || line.ends_with("律师")
-            || line.ends_with("医生")
-            || line.ends_with("顾问")
-            || line.ends_with("教师")
-            || line.ends_with("警察")
-            || line.ends_with("经理")
-            || line.ends_with("董事")
-            || (line.ends_with("工") && i + 1 < lines.len() && !lines[i + 1].contains("技术人员"))
-            || line.matches('-').count() == 3
-        {
-            buffer.push_str(line);
-            if !buffer.is_empty() {
-                // println!("{}", buffer);
-                result.push(buffer.clone());
+    let lines: Vec<String> = lines
+        .iter()
+        .map(|line| {
+            if options.nfkc {
+                fix_id(&line.nfkc().collect::<String>(), options)
+            } else {
+                fix_id(line, options)
             }
-            buffer.clear();
-            i += 1;
-            continue;
-        } else {
-            buffer.push_str(line);
-            i += 1;
-        }
+        })
+        .collect();
+
+    lines
+        .iter()
+        .map(|line| {
+            let (line, marker) = split_marker(line);
+            let mut line = options
+                .artifacts
+                .iter()
+                .fold(line.to_string(), |line, artifact| {
+                    line.replace(artifact.as_str(), "")
+                });
+            if let Some(re) = options.artifact_patterns.regex() {
+                line = re.replace_all(&line, "").into_owned();
+            }
+            Line {
+                text: line.trim().to_string(),
+                marker: marker.to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Drop the lines of a cell that are page headers, page numbers or banners
+fn strip_headers(cell_text: &str, headers: &RegexSet) -> String {
+    if headers.is_empty() || !cell_text.lines().any(|line| headers.is_match(line)) {
+        return cell_text.to_string();
     }
+    cell_text
+        .lines()
+        .filter(|line| {
+            let header = headers.is_match(line);
+            if header {
+                tracing::debug!(line, "header line dropped");
+            }
+            !header
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
 
-    if !buffer.is_empty() {
-        result.push(buffer);
+/// `line` with the characters of `confusions` replaced in its leading id,
+/// e.g. "1-O1" read for "1-01"; kept as is unless the id then has the
+/// digits and separators of a full id, so "B超" or a name after the id
+/// starting with "一" are left alone
+fn fix_id(line: &str, options: &ParseOptions) -> String {
+    let confusions = &options.confusions;
+    let separator = options.id_format.separator.as_str();
+    let id_char = |c: char| c.is_ascii_digit() || separator.contains(c);
+    let mut id = String::new();
+    let mut end = 0;
+    for (i, c) in line.char_indices() {
+        let fixed = match confusions.get(&c) {
+            Some(&fixed) if !id_char(c) => fixed,
+            _ => c,
+        };
+        if !id_char(fixed) {
+            break;
+        }
+        id.push(fixed);
+        end = i + c.len_utf8();
+    }
+    // the id ends with its last digit
+    while id.ends_with(|c: char| !c.is_ascii_digit()) {
+        id.pop();
+        end = line[..end].char_indices().last().map_or(0, |(i, _)| i);
     }
+    let is_id = !separator.is_empty()
+        && id.split(separator).count() > 1
+        && id.split(separator).all(|seg| !seg.is_empty());
+    if !is_id || id == line[..end] {
+        return line.to_string();
+    }
+    tracing::debug!(line, id, "id characters replaced");
+    format!("{}{}", id, &line[end..])
+}
 
-    result
+/// Split a trailing 绿色职业/数字职业 marker ("L", "S" or "L/S") off a line
+fn split_marker(line: &str) -> (&str, &str) {
+    for marker in ["L/S", "S/L", "L", "S"] {
+        if let Some(rest) = line.strip_suffix(marker) {
+            return (rest, marker);
+        }
+    }
+    (line, "")
 }
 
 pub fn normalize_first_category(text: &str) -> Option<String> {
@@ -271,7 +908,7 @@ pub fn normalize_first_category(text: &str) -> Option<String> {
         .map(|(i, _)| i)?;
 
     let name = text.get(..first_digit_idx)?.trim();
-    if name.len() == 0 {
+    if name.is_empty() {
         return None;
     }
 
@@ -388,4 +1025,495 @@ mod tests {
         let expected = "1(GBM10) 第一大类党的机关、国家机关、群众团体和社会组织、企事业单位负责人";
         assert_eq!(normalize_first_category(input).unwrap(), expected);
     }
+
+    #[test]
+    fn test_marker_merge() {
+        let text = "大地测量工程技\n术人员 L/S\n工程测量工程技\n术人员 S\n节水工程技术人\n员 L";
+        let result = construct_lines(text);
+        let expected = vec![
+            "大地测量工程技术人员L/S".to_string(),
+            "工程测量工程技术人员S".to_string(),
+            "节水工程技术人员L".to_string(),
+        ];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_confused_ids() {
+        let text = "1-O1(GBM10100)中国共产党机关和基层组织负责人\n\
+                    ２-０4-02-0l一般船舶驾驶员\nB超医生\n1一02国家机关负责人";
+        let result = construct_lines_with(text, &ParseOptions::default());
+        let expected = vec![
+            "1-01(GBM10100)中国共产党机关和基层组织负责人".to_string(),
+            "2-04-02-01一般船舶驾驶员".to_string(),
+            "B超医生".to_string(),
+            "1-02国家机关负责人".to_string(),
+        ];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_id_format() {
+        let options = ParseOptions {
+            id_format: IdFormat {
+                separator: ".".to_string(),
+                segment_pattern: r"[A-Z]|\d+".to_string(),
+                code_prefix: "NOC".to_string(),
+                ..IdFormat::default()
+            },
+            ..ParseOptions::default()
+        };
+        let parsed = column_categories(
+            "A.1(NOC 11)行政管理人员\nA.1.01.02首席执行官",
+            &options,
+            &mut |_| {},
+        )
+        .unwrap();
+        let cats = parsed.matched.categories;
+        assert_eq!(cats.len(), 2);
+        assert_eq!(
+            (cats[0].id.as_str(), cats[0].code.as_deref()),
+            ("A-1", Some("NOC11"))
+        );
+        assert_eq!(cats[1].id, "A-1-01-02");
+        assert_eq!(cats[1].desc.as_deref(), Some("首席执行官"));
+        assert!(parsed.matched.unmatched.is_empty());
+    }
+
+    #[test]
+    fn test_full_width() {
+        let chunks = construct_lines_with(
+            "２－０４（ＧＢＭ２０４００）交通运输工程技术人员",
+            &ParseOptions::default(),
+        );
+        let categories = match_chunks(&chunks, &IdFormat::default())
+            .unwrap()
+            .categories;
+        assert_eq!(categories[0].id, "2-04");
+        assert_eq!(categories[0].code.as_deref(), Some("GBM20400"));
+        assert_eq!(categories[0].desc.as_deref(), Some("交通运输工程技术人员"));
+    }
+
+    #[test]
+    fn test_artifact_patterns() {
+        let options = ParseOptions {
+            artifacts: Vec::new(),
+            artifact_patterns: ArtifactPatterns::new(["[·•]", "^#\\d+"]).unwrap(),
+            ..ParseOptions::default()
+        };
+        let text = "#12船舶·驾驶员\n•船舶引航员";
+        assert_eq!(
+            construct_lines_with(text, &options),
+            ["船舶驾驶员", "船舶引航员"]
+        );
+        assert!(matches!(
+            ArtifactPatterns::new(["["]),
+            Err(KimiError::Regex(_))
+        ));
+        assert!(Config::from_toml_str("[parse]\nartifact_patterns = [\"[\"]").is_err());
+    }
+
+    #[test]
+    fn test_marker_own_line() {
+        let text = "摄影测量与遥感\n工 程 技 术 人 员\nL/S\n地图制图工程技\n术人员 S";
+        let result = construct_lines(text);
+        let expected = vec![
+            "摄影测量与遥感工程技术人员L/S".to_string(),
+            "地图制图工程技术人员S".to_string(),
+        ];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_marked_categories() {
+        let chunks = vec![
+            "2-02-02-01大地测量工程技术人员L/S".to_string(),
+            "2-02-02-02工程测量工程技术人员S".to_string(),
+            "2-02-01-03水工环地质工程技术人员L".to_string(),
+            "2-02-01-04地质矿产调查工程技术人员".to_string(),
+        ];
+        let result = parse_categories(&chunks).unwrap();
+        assert_eq!(result.len(), 4);
+        assert_eq!(result[0].desc.as_deref(), Some("大地测量工程技术人员"));
+        assert!(result[0].is_green && result[0].is_digital);
+        assert_eq!(result[1].desc.as_deref(), Some("工程测量工程技术人员"));
+        assert!(!result[1].is_green && result[1].is_digital);
+        assert_eq!(result[2].desc.as_deref(), Some("水工环地质工程技术人员"));
+        assert!(result[2].is_green && !result[2].is_digital);
+        assert!(!result[3].is_green && !result[3].is_digital);
+        assert_eq!(result[3].marker(), None);
+        assert_eq!(
+            result[0].to_string(),
+            "细类 2-02-02-01 大地测量工程技术人员 L/S"
+        );
+    }
+
+    #[test]
+    fn test_marked_two_columns() {
+        let mut tree = CategoryTree::new();
+        tree.parse_two_columns(
+            "2-02-02-02\n\n2-02-02-03",
+            "工程测量工程技\n术人员 S\n摄影测量与遥感\n工 程 技 术 人 员\nL/S",
+        )
+        .unwrap();
+        let node = &tree.children["2"].children["02"].children["02"];
+        let cat = &node.children["02"].items[0];
+        assert_eq!(cat.desc.as_deref(), Some("工程测量工程技术人员"));
+        assert_eq!(cat.marker(), Some("S"));
+        let cat = &node.children["03"].items[0];
+        assert_eq!(cat.desc.as_deref(), Some("摄影测量与遥感工程技术人员"));
+        assert_eq!(cat.marker(), Some("L/S"));
+    }
+
+    #[test]
+    fn test_column_groups() {
+        let options = ParseOptions::default();
+        let parsed = group_categories(
+            &[
+                "2-02-02\n\n2-02-03",
+                "(GBM20202)\n\n(GBM20203)",
+                "测绘工程技术人员\n化工工程技术人员",
+            ],
+            JoinRule::SideBySide,
+            &options,
+            &mut |_| {},
+        )
+        .unwrap();
+        let cats = parsed.matched.categories;
+        assert_eq!(cats.len(), 2);
+        assert_eq!(cats[1].id, "2-02-03");
+        assert_eq!(cats[1].code.as_deref(), Some("GBM20203"));
+        assert_eq!(cats[1].desc.as_deref(), Some("化工工程技术人员"));
+
+        let parsed = group_categories(
+            &[
+                "2-02-02(GBM20202)测绘和地理信息",
+                "工程技术人员\n2-02-03(GBM20203)化工工程技术人员",
+            ],
+            JoinRule::Stacked,
+            &options,
+            &mut |_| {},
+        )
+        .unwrap();
+        let descs = parsed
+            .matched
+            .categories
+            .iter()
+            .map(|cat| cat.desc.as_deref());
+        assert!(descs.eq([Some("测绘和地理信息工程技术人员"), Some("化工工程技术人员")]));
+    }
+
+    #[test]
+    fn test_malformed_id() {
+        let chunks = vec!["1-01-(GBM10100)中国共产党机关负责人".to_string()];
+        let err = parse_categories(&chunks).unwrap_err();
+        assert!(matches!(err, KimiError::MalformedId(id) if id == "1-01-"));
+    }
+
+    #[test]
+    fn test_to_tree_string() {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column(
+            "1-01(GBM10100)中国共产党机关负责人\n1-01-00-01 中国共产党机关负责人",
+        )
+        .unwrap();
+        let expected = "\
+└── 1
+    └── 01
+        ├── 1-01 [GBM10100, 中国共产党机关负责人]
+        └── 00
+            └── 01
+                └── 1-01-00-01 [中国共产党机关负责人]
+";
+        assert_eq!(tree.to_tree_string(), expected);
+
+        let json: serde_json::Value = serde_json::from_str(&tree.to_json_string()).unwrap();
+        assert_eq!(
+            json["children"]["1"]["children"]["01"]["categories"][0]["code"],
+            "GBM10100"
+        );
+    }
+
+    #[test]
+    fn test_get() {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column(
+            "1-01(GBM10100)中国共产党机关负责人\n1-01-00-01 中国共产党机关负责人",
+        )
+        .unwrap();
+        assert_eq!(tree.get("1-01").unwrap().code.as_deref(), Some("GBM10100"));
+        assert_eq!(
+            tree.get("1-01-00-01").unwrap().desc.as_deref(),
+            Some("中国共产党机关负责人")
+        );
+        // intermediate node without a category of its own
+        assert!(tree.get_node("1-01-00").is_some());
+        assert!(tree.get("1-01-00").is_none());
+        assert!(tree.get("1-02").is_none());
+        assert!(tree.get_node("9").is_none());
+    }
+
+    #[test]
+    fn test_ancestors() {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column(
+            "1(GBM10)第一大类负责人\n1-01(GBM10100)中国共产党机关和基层组织负责人\n1-01-00(GBM10100)中国共产党机关和基层组织负责人\n1-01-00-01中国共产党机关负责人",
+        )
+        .unwrap();
+        let chain = tree
+            .ancestors("1-01-00-01")
+            .iter()
+            .map(|c| c.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(chain, vec!["1", "1-01", "1-01-00"]);
+        assert_eq!(tree.ancestors("1-01").len(), 1);
+        assert!(tree.ancestors("1").is_empty());
+        assert!(tree.ancestors("2-01-01-01").is_empty());
+    }
+
+    #[test]
+    fn test_subtree() {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column(
+            "2(GBM20000)专业技术人员\n2-01(GBM20100)科学研究人员\n2-02(GBM20200)工程技术人员\n2-02-01(GBM20201)地质勘探工程技术人员\n2-02-01-01地质实验测试工程技术人员",
+        )
+        .unwrap();
+        let sub = tree.subtree("2-02").unwrap();
+        assert!(sub.get("2").is_none());
+        assert!(sub.get("2-01").is_none());
+        assert_eq!(sub.get("2-02").unwrap().code.as_deref(), Some("GBM20200"));
+        assert!(sub.get("2-02-01-01").is_some());
+        assert!(tree.subtree("2-03").is_none());
+    }
+
+    #[test]
+    fn test_category_level() {
+        let chunks = vec![
+            "1(GBM10)负责人".to_string(),
+            "1-01(GBM10100)机关负责人".to_string(),
+            "1-01-00(GBM10100)机关负责人".to_string(),
+            "1-01-00-01中国共产党机关负责人".to_string(),
+        ];
+        let result = parse_categories(&chunks).unwrap();
+        let levels = result.iter().map(|c| c.level()).collect::<Vec<_>>();
+        assert_eq!(
+            levels,
+            vec![
+                Some(Level::Major),
+                Some(Level::Middle),
+                Some(Level::Minor),
+                Some(Level::Detail)
+            ]
+        );
+
+        let json = serde_json::to_value(&result[3]).unwrap();
+        assert_eq!(json["level"], "detail");
+        let back: Category = serde_json::from_value(json).unwrap();
+        assert_eq!(back.id, "1-01-00-01");
+    }
+
+    #[test]
+    fn test_parent_of() {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column(
+            "1-01(GBM10100)机关负责人\n1-01-00(GBM10100)机关负责人\n1-01-00-01中国共产党机关负责人",
+        )
+        .unwrap();
+        assert_eq!(tree.parent_of("1-01-00-01").unwrap().id, "1-01-00");
+        assert_eq!(tree.parent_of("1-01-00").unwrap().id, "1-01");
+        // the 大类 itself was not inserted
+        assert!(tree.parent_of("1-01").is_none());
+        assert!(tree.parent_of("1").is_none());
+    }
+
+    #[test]
+    fn test_tree_eq() {
+        let mut a = CategoryTree::new();
+        a.parse_one_column("1-01(GBM10100)机关负责人\n1-02(GBM10200)国家机关负责人")
+            .unwrap();
+        let mut b = CategoryTree::new();
+        b.parse_one_column("1-02(GBM10200)国家机关负责人\n1-01(GBM10100)机关负责人")
+            .unwrap();
+        assert_ne!(a, b);
+        assert!(a.eq_ignore_order(&b));
+        b.sort();
+        assert_eq!(a, b);
+
+        b.parse_one_column("1-03 民主党派负责人").unwrap();
+        assert!(!a.eq_ignore_order(&b));
+        assert_eq!(a.get("1-01"), b.get("1-01"));
+    }
+
+    #[test]
+    fn test_build_from_reader() {
+        let mut book = umya_spreadsheet::new_file();
+        let sheet = book.get_sheet_mut(&0).unwrap();
+        sheet
+            .get_cell_mut((1, 1))
+            .set_value("第二大类 2(GBM20000)专业技术人员");
+        sheet
+            .get_cell_mut((3, 1))
+            .set_value("2-01(GBM20100)科学研究人员");
+        let mut bytes = Vec::new();
+        umya_spreadsheet::writer::xlsx::write_writer(&book, &mut bytes).unwrap();
+
+        let mut tree = CategoryTree::new();
+        tree.build_from_reader(io::Cursor::new(bytes), &ParseOptions::default())
+            .unwrap();
+        assert_eq!(
+            tree.get("2").unwrap().desc.as_deref(),
+            Some("第二大类专业技术人员")
+        );
+        assert_eq!(tree.get("2-01").unwrap().code.as_deref(), Some("GBM20100"));
+    }
+
+    #[test]
+    fn test_strip_headers() {
+        let options = ParseOptions::default();
+        let headers = RegexSet::new(&options.header_patterns).unwrap();
+        assert_eq!(
+            strip_headers(
+                "中华人民共和国职业分类大典\n2-01(GBM20100)科学\n- 12 -\n研究人员\n\n细类（职业）",
+                &headers
+            ),
+            "2-01(GBM20100)科学\n研究人员\n"
+        );
+        assert_eq!(strip_headers("1 (GBM10)\n中类", &headers), "1 (GBM10)");
+        // the banner and continuation label as the cells of the scan hold them
+        assert_eq!(
+            strip_headers("中华人民共和国\n职 业 分 类 大 典", &headers),
+            ""
+        );
+        assert_eq!(strip_headers("续表", &headers), "");
+        assert_eq!(strip_headers("职业指导师\n", &headers), "职业指导师\n");
+    }
+
+    #[test]
+    fn test_merged_cells() {
+        let mut book = umya_spreadsheet::new_file();
+        let sheet = book.get_sheet_mut(&0).unwrap();
+        sheet
+            .get_cell_mut((3, 1))
+            .set_value("2-04-02(GBM20402)船舶指挥和引航人员");
+        // ids merged over two rows next to names in two cells
+        sheet
+            .get_cell_mut((5, 1))
+            .set_value("2-04-02-01\n2-04-02-02");
+        sheet.get_cell_mut((6, 1)).set_value("船舶驾驶员");
+        sheet.get_cell_mut((6, 2)).set_value("船舶引航员 L");
+        sheet.add_merge_cells("E1:E2");
+        // one cell spanning both columns of the pair
+        sheet
+            .get_cell_mut((5, 3))
+            .set_value("2-04-02-03   船舶交通\n                管理员\n2-04-02-04   船舶报务员");
+        sheet.add_merge_cells("E3:F3");
+        let mut bytes = Vec::new();
+        umya_spreadsheet::writer::xlsx::write_writer(&book, &mut bytes).unwrap();
+
+        let options = ParseOptions {
+            layout: Some(LayoutProfile::default()),
+            ..ParseOptions::default()
+        };
+        let mut tree = CategoryTree::new();
+        tree.build_from_reader(io::Cursor::new(bytes), &options)
+            .unwrap();
+        let desc = |id| tree.get(id).and_then(|cat| cat.desc.as_deref());
+        assert_eq!(desc("2-04-02-01"), Some("船舶驾驶员"));
+        assert_eq!(desc("2-04-02-02"), Some("船舶引航员"));
+        assert!(tree.get("2-04-02-02").unwrap().is_green);
+        assert_eq!(desc("2-04-02-03"), Some("船舶交通管理员"));
+        assert_eq!(desc("2-04-02-04"), Some("船舶报务员"));
+    }
+
+    #[test]
+    fn test_category_source() {
+        let mut book = umya_spreadsheet::new_file();
+        let sheet = book.get_sheet_mut(&0).unwrap();
+        sheet
+            .get_cell_mut((1, 1))
+            .set_value("2-04(GBM20400)交通运输工程技术人员");
+        sheet.add_merge_cells("A1:A2");
+        sheet
+            .get_cell_mut((3, 3))
+            .set_value("2-04-02(GBM20402)船舶指挥和引航人员");
+        sheet
+            .get_cell_mut((5, 3))
+            .set_value("2-04-02-01\n2-04-02-02");
+        sheet.get_cell_mut((6, 3)).set_value("船舶驾驶员");
+        sheet.get_cell_mut((6, 4)).set_value("船舶引航员");
+        sheet.add_merge_cells("E3:E4");
+        sheet
+            .get_cell_mut((5, 5))
+            .set_value("2-04-02-03   船舶报务员");
+        sheet.add_merge_cells("E5:F5");
+        let mut bytes = Vec::new();
+        umya_spreadsheet::writer::xlsx::write_writer(&book, &mut bytes).unwrap();
+
+        let options = ParseOptions {
+            layout: Some(LayoutProfile::default()),
+            ..ParseOptions::default()
+        };
+        let mut tree = CategoryTree::new();
+        tree.build_from_reader(io::Cursor::new(bytes), &options)
+            .unwrap();
+        let source = |id| {
+            let source = tree.get(id).unwrap().source.clone().unwrap();
+            (source.sheet, source.row, source.col)
+        };
+        let at = |row, col| ("Sheet1".to_string(), row, col);
+        assert_eq!(source("2-04"), at(1, 1));
+        assert_eq!(source("2-04-02"), at(3, 3));
+        assert_eq!(source("2-04-02-01"), at(3, 5));
+        assert_eq!(source("2-04-02-02"), at(3, 5));
+        assert_eq!(source("2-04-02-03"), at(5, 5));
+    }
+
+    #[test]
+    fn test_sheet_selection() {
+        let mut book = umya_spreadsheet::new_file();
+        book.get_sheet_mut(&0)
+            .unwrap()
+            .get_cell_mut((3, 1))
+            .set_value("2-01(GBM20100)科学研究人员");
+        book.new_sheet("第三大类")
+            .unwrap()
+            .get_cell_mut((3, 1))
+            .set_value("3-01(GBM30100)行政办事及辅助人员");
+        let mut bytes = Vec::new();
+        umya_spreadsheet::writer::xlsx::write_writer(&book, &mut bytes).unwrap();
+
+        let parse = |sheet: &str| {
+            let options = ParseOptions {
+                sheet: sheet.parse().unwrap(),
+                ..ParseOptions::default()
+            };
+            let mut tree = CategoryTree::new();
+            tree.build_from_reader(io::Cursor::new(&bytes), &options)
+                .map(|_| {
+                    tree.iter()
+                        .map(|(_, cat)| cat.id.clone())
+                        .collect::<Vec<_>>()
+                })
+        };
+        assert_eq!(parse("0").unwrap(), ["2-01"]);
+        assert_eq!(parse("第三大类").unwrap(), ["3-01"]);
+        assert_eq!(parse("all").unwrap(), ["2-01", "3-01"]);
+        assert!(matches!(parse("2"), Err(KimiError::SheetNotFound(2))));
+    }
+
+    #[test]
+    fn test_from_categories() {
+        let chunks = vec![
+            "1-01(GBM10100)机关负责人".to_string(),
+            "1-01-00-01中国共产党机关负责人".to_string(),
+            "2(GBM20000)专业技术人员".to_string(),
+        ];
+        let categories = parse_categories(&chunks).unwrap();
+        let tree = CategoryTree::from_categories(categories.clone());
+        assert_eq!(tree.get("1-01-00-01"), Some(&categories[1]));
+        assert_eq!(tree.iter().count(), 3);
+
+        let collected: CategoryTree = categories.into_iter().rev().collect();
+        assert!(collected.eq_ignore_order(&tree));
+    }
 }
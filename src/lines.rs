@@ -0,0 +1,313 @@
+//! Reassembling the lines of a cell
+//!
+//! The scan wraps long names over several printed lines, so the lines of a
+//! cell are joined back into one chunk per category before the chunks are
+//! matched against the category regex. `SuffixMerger`, the default, ends a
+//! chunk at a line closing with a known name ending; another `LineMerger`
+//! can be set in `ParseOptions::merger`, e.g. one anchored on ids or on the
+//! geometry of the page. With `ParseOptions::trace_merges` every decision of
+//! the merger is kept in the `ParseReport`, to see why two names ran
+//! together or one was cut in half.
+
+use crate::{CellRef, ParseOptions};
+use serde::Serialize;
+use std::fmt;
+use std::sync::Arc;
+
+/// A printed line of a cell, without whitespace, OCR artifacts or the
+/// 绿色职业/数字职业 marker at its end
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Line {
+    pub text: String,
+    /// "L", "S", "L/S" or empty
+    pub marker: String,
+}
+
+impl Line {
+    /// The text followed by the marker
+    pub fn joined(&self) -> String {
+        format!("{}{}", self.text, self.marker)
+    }
+}
+
+/// Why a chunk was closed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ChunkEnd {
+    /// An empty line
+    EmptyLine,
+    /// The last line ends with one of the name endings
+    Suffix { suffix: String },
+    /// The last line ends with 工 and the next one is not about 技术人员
+    Worker,
+    /// The last line holds a 细类 id
+    DetailId,
+    /// The cell has no more lines
+    EndOfCell,
+}
+
+/// A decision of a `LineMerger`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "step", rename_all = "snake_case")]
+pub enum MergeStep {
+    /// The line was added to the chunk being read
+    Buffered { line: String },
+    /// The chunk was closed after its last line
+    Flushed { chunk: String, end: ChunkEnd },
+    /// A marker alone on its line was added to the chunk before it
+    MarkerAttached { marker: String },
+}
+
+impl fmt::Display for MergeStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MergeStep::Buffered { line } => write!(f, "buffered {:?}", line),
+            MergeStep::Flushed { chunk, end } => {
+                write!(f, "flushed {:?}: ", chunk)?;
+                match end {
+                    ChunkEnd::EmptyLine => f.write_str("empty line"),
+                    ChunkEnd::Suffix { suffix } => write!(f, "ends with {}", suffix),
+                    ChunkEnd::Worker => f.write_str("ends with 工"),
+                    ChunkEnd::DetailId => f.write_str("细类 id"),
+                    ChunkEnd::EndOfCell => f.write_str("end of cell"),
+                }
+            }
+            MergeStep::MarkerAttached { marker } => {
+                write!(f, "marker {} added to the chunk before", marker)
+            }
+        }
+    }
+}
+
+/// The merge steps of one cell
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MergeTrace {
+    pub cell: CellRef,
+    pub steps: Vec<MergeStep>,
+}
+
+/// Strategy joining the lines of a cell into chunks, one per category
+pub trait LineMerger: Send + Sync {
+    /// Chunks of `lines`, in order; each chunk carries the markers of its
+    /// lines at its end
+    fn merge(&self, lines: &[Line], options: &ParseOptions) -> Vec<String> {
+        self.merge_traced(lines, options, &mut |_| {})
+    }
+
+    /// Like `merge`, handing each decision to `trace`
+    fn merge_traced(
+        &self,
+        lines: &[Line],
+        options: &ParseOptions,
+        trace: &mut dyn FnMut(MergeStep),
+    ) -> Vec<String>;
+
+    /// Name telling strategies apart, the type name by default
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+}
+
+/// Ends a chunk at a line closing with one of `ParseOptions::suffixes`, at
+/// a 细类 id, or at a line ending in 工 unless the next line names 技术人员;
+/// a marker alone on a line belongs to the chunk before it
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SuffixMerger;
+
+impl LineMerger for SuffixMerger {
+    fn merge_traced(
+        &self,
+        lines: &[Line],
+        options: &ParseOptions,
+        trace: &mut dyn FnMut(MergeStep),
+    ) -> Vec<String> {
+        let mut result: Vec<String> = Vec::new();
+        let mut buffer = String::new();
+        for (i, line) in lines.iter().enumerate() {
+            let (text, marker) = (line.text.as_str(), line.marker.as_str());
+            if text.is_empty() && !marker.is_empty() {
+                // A marker wrapped onto its own line belongs to the previous phrase
+                match result.last_mut() {
+                    Some(last) if buffer.is_empty() => last.push_str(marker),
+                    _ => buffer.push_str(marker),
+                }
+                trace(MergeStep::MarkerAttached {
+                    marker: marker.to_string(),
+                });
+                continue;
+            }
+            let end = if text.is_empty() {
+                Some(ChunkEnd::EmptyLine)
+            } else if let Some(suffix) = options
+                .suffixes
+                .iter()
+                .find(|suffix| text.ends_with(suffix.as_str()))
+            {
+                Some(ChunkEnd::Suffix {
+                    suffix: suffix.clone(),
+                })
+            } else if text.ends_with("工")
+                && lines
+                    .get(i + 1)
+                    .is_some_and(|next| !next.text.contains("技术人员"))
+            {
+                Some(ChunkEnd::Worker)
+            } else if options.id_format.is_deepest(text) {
+                Some(ChunkEnd::DetailId)
+            } else {
+                None
+            };
+            if end.is_some() && !buffer.is_empty() {
+                tracing::debug!(buffer, line = text, "chunk merged with the lines before it");
+            }
+            buffer.push_str(text);
+            buffer.push_str(marker);
+            if !text.is_empty() {
+                trace(MergeStep::Buffered {
+                    line: format!("{}{}", text, marker),
+                });
+            }
+            if let Some(end) = end
+                && !buffer.is_empty()
+            {
+                trace(MergeStep::Flushed {
+                    chunk: buffer.clone(),
+                    end,
+                });
+                result.push(std::mem::take(&mut buffer));
+            }
+        }
+        if !buffer.is_empty() {
+            trace(MergeStep::Flushed {
+                chunk: buffer.clone(),
+                end: ChunkEnd::EndOfCell,
+            });
+            result.push(buffer);
+        }
+        result
+    }
+}
+
+/// The `LineMerger` of a parse, `SuffixMerger` by default; options compare
+/// equal when their mergers have the same name
+#[derive(Clone)]
+pub struct Merger(Arc<dyn LineMerger>);
+
+impl Merger {
+    pub fn new(merger: impl LineMerger + 'static) -> Self {
+        Merger(Arc::new(merger))
+    }
+
+    pub fn name(&self) -> &str {
+        self.0.name()
+    }
+}
+
+impl std::ops::Deref for Merger {
+    type Target = dyn LineMerger;
+
+    fn deref(&self) -> &Self::Target {
+        &*self.0
+    }
+}
+
+impl Default for Merger {
+    fn default() -> Self {
+        Merger::new(SuffixMerger)
+    }
+}
+
+impl fmt::Debug for Merger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Merger").field(&self.name()).finish()
+    }
+}
+
+impl PartialEq for Merger {
+    fn eq(&self, other: &Self) -> bool {
+        self.name() == other.name()
+    }
+}
+
+impl Eq for Merger {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{construct_lines_traced, construct_lines_with};
+
+    /// One chunk per line starting with an id
+    struct IdMerger;
+
+    impl LineMerger for IdMerger {
+        fn merge_traced(
+            &self,
+            lines: &[Line],
+            _: &ParseOptions,
+            _: &mut dyn FnMut(MergeStep),
+        ) -> Vec<String> {
+            let mut chunks: Vec<String> = Vec::new();
+            for line in lines {
+                match chunks.last_mut() {
+                    Some(chunk) if !line.text.starts_with(|c: char| c.is_ascii_digit()) => {
+                        chunk.push_str(&line.text)
+                    }
+                    _ => chunks.push(line.text.clone()),
+                }
+                chunks.last_mut().unwrap().push_str(&line.marker);
+            }
+            chunks
+        }
+    }
+
+    #[test]
+    fn test_merge_trace() {
+        let mut steps = Vec::new();
+        let chunks = construct_lines_traced(
+            "中国共产党机关\n负责人\nL\n6-01-01-01制米\n工",
+            &ParseOptions::default(),
+            &mut |step| steps.push(step.to_string()),
+        );
+        assert_eq!(chunks, ["中国共产党机关负责人L", "6-01-01-01制米", "工"]);
+        assert_eq!(
+            steps,
+            [
+                "buffered \"中国共产党机关\"",
+                "buffered \"负责人\"",
+                "flushed \"中国共产党机关负责人\": ends with 责人",
+                "marker L added to the chunk before",
+                "buffered \"6-01-01-01制米\"",
+                "flushed \"6-01-01-01制米\": 细类 id",
+                "buffered \"工\"",
+                "flushed \"工\": end of cell",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_line_merger() {
+        let text = "2-02-02-01大地测量\n工程技术人员L/S\n2-02-02-02工程测量工程技\n术";
+        let options = ParseOptions {
+            merger: Merger::new(IdMerger),
+            ..ParseOptions::default()
+        };
+        assert_ne!(options, ParseOptions::default());
+        assert_eq!(
+            construct_lines_with(text, &options),
+            [
+                "2-02-02-01大地测量工程技术人员L/S",
+                "2-02-02-02工程测量工程技术"
+            ]
+        );
+        // a line with a 细类 id ends a chunk of its own
+        assert_eq!(
+            construct_lines_with(text, &ParseOptions::default()),
+            [
+                "2-02-02-01大地测量",
+                "工程技术人员L/S",
+                "2-02-02-02工程测量工程技",
+                "术"
+            ]
+        );
+    }
+}
@@ -1,4 +1,11 @@
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use indicatif::{ProgressBar, ProgressStyle};
 use kimi::*;
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use tracing_subscriber::EnvFilter;
 
 // 这是一份从互联网上下载的《中华人民共和国职业分类大典》，不难看出，
 // 这是对某个 PDF 文件使用 OCR 技术转换而成的 Excel 文件。
@@ -7,20 +14,1284 @@ use kimi::*;
 // 并以 JSON 格式（或你喜欢的其他格式）输出。
 //
 
-// run: cargo run -- ./202306151255033.xlsx
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args: Vec<String> = std::env::args().collect();
-    if args.len() < 2 {
-        eprintln!("Usage: {} <excel_file.xlsx>", args[0]);
+// run: cargo run -- print ./202306151255033.xlsx
+//      cargo run -- export ./202306151255033.xlsx --format json -o out.json
+#[derive(Parser)]
+#[command(
+    version,
+    about = "Parse the 职业分类大典 workbook into a category tree",
+    after_help = "A workbook argument of - reads the xlsx bytes from stdin; an http(s) URL\ndownloads it first (needs the fetch cargo feature). The workbooks of a .zip\narchive are parsed in name order into one tree. Legacy .xls workbooks are\nread with the xls cargo feature and .ods spreadsheets with the ods feature.\nThe tables of .html and .htm pages are read as sheets with the html feature,\n.docx files with the docx feature, the text layer of .pdf files with the pdf\nfeature and Tesseract hOCR (.hocr) or ALTO (.xml) output with the ocr\nfeature. Set RUST_LOG=kimi=debug to log the cells skipped and the lines\nmerged or left unparsed."
+)]
+struct Cli {
+    /// Config file; defaults to ./kimi.toml when present
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+    /// Library reading the workbook: umya, or calamine (needs the calamine
+    /// cargo feature) which is faster on large files [default: umya]
+    #[arg(long, global = true)]
+    backend: Option<Backend>,
+    /// Worksheets to parse: a 0-based index, a sheet name or all
+    /// [default: 0, or parse.sheet of the config]
+    #[arg(long, global = true)]
+    sheet: Option<SheetSelection>,
+    /// Only read these rows, e.g. 3:500 to skip a title page
+    #[arg(long, global = true)]
+    rows: Option<Span>,
+    /// Only read these columns, e.g. A:F
+    #[arg(long, global = true)]
+    cols: Option<Span>,
+    /// Parse header rows, page numbers and banner text instead of skipping
+    /// them
+    #[arg(long, global = true)]
+    keep_headers: bool,
+    /// strict to fail on text that is not a category instead of skipping it
+    /// [default: lenient, or parse.mode of the config]
+    #[arg(long, global = true)]
+    mode: Option<ParseMode>,
+    /// Also end a line at this name ending, e.g. 操作工; may be repeated
+    #[arg(long = "suffix", global = true, value_name = "SUFFIX")]
+    suffixes: Vec<String>,
+    /// Also end lines at the name endings listed in this file, one per line
+    /// with # starting a comment; may be repeated
+    #[arg(long = "suffix-file", global = true, value_name = "FILE")]
+    suffix_files: Vec<PathBuf>,
+    /// Print how the lines of each cell were joined into categories
+    #[arg(long, global = true)]
+    trace_merge: bool,
+    /// Replace 大类 and 中类 names a character or two off the published
+    /// ones with them (needs the reference cargo feature)
+    /// [default: parse.correct_names of the config]
+    #[arg(long, global = true)]
+    correct_names: bool,
+    /// Reuse the tree parsed from an identical workbook with the same
+    /// options, kept in ~/.cache/kimi (needs the cache cargo feature)
+    /// [default: cache.enabled of the config]
+    #[arg(long, global = true)]
+    cache: bool,
+    /// Don't show progress or parse warnings on stderr [default:
+    /// output.quiet of the config]
+    #[arg(short, long, global = true)]
+    quiet: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the category tree
+    Print {
+        /// Workbook to parse
+        input: PathBuf,
+    },
+    /// Write the category tree in another format, JSON by default
+    #[command(visible_alias = "parse")]
+    Export(ExportArgs),
+    /// Show a category with its ancestry and siblings
+    Lookup {
+        /// Workbook to parse
+        input: PathBuf,
+        /// Category id, e.g. 2-02-38-07
+        #[arg(required_unless_present = "code")]
+        id: Option<String>,
+        /// Look up by GBM code instead, e.g. GBM20238
+        #[arg(long, conflicts_with = "id")]
+        code: Option<String>,
+    },
+    /// Find categories by name, exiting with 1 if there is no match
+    Search {
+        /// Workbook to parse
+        input: PathBuf,
+        /// Text to look for in category names
+        pattern: String,
+        /// Treat the pattern as a regular expression
+        #[arg(short, long)]
+        regex: bool,
+        /// Print matches as a JSON array
+        #[arg(long)]
+        json: bool,
+    },
+    /// Count categories per level to judge whether the parse is complete
+    Stats {
+        /// Workbook to parse
+        input: PathBuf,
+        /// Print the statistics as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Check the structure of the parsed tree, exiting with 1 on problems
+    Validate {
+        /// Workbook to parse
+        input: PathBuf,
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+        /// Also compare the number of categories at each level with the
+        /// totals published for this edition, 2015 or 2022; with the
+        /// reference feature, also compare the 大类 and 中类 of 2022 with
+        /// their published codes and names
+        #[arg(long)]
+        edition: Option<Edition>,
+    },
+    /// Compare two workbooks, exiting with 1 if they differ
+    Diff {
+        /// Workbook of the old edition or OCR run
+        old: PathBuf,
+        /// Workbook of the new edition or OCR run
+        new: PathBuf,
+        /// Print the differences as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Convert a workbook to the format given by the output file extension
+    ///
+    /// json, yaml, toml, csv, ndjson, txt, md, html, dot, opml, mm, sql
+    /// (PostgreSQL ltree) and xlsx are always available; sqlite, parquet,
+    /// ods, pb, bin, msgpack and cbor need their cargo features. A trailing
+    /// .gz or .zst compresses the output.
+    Convert {
+        /// Workbook to parse
+        input: PathBuf,
+        /// File to write, e.g. out.json or out.ndjson.gz
+        output: PathBuf,
+        /// Overwrite the output file if it exists
+        #[arg(long)]
+        force: bool,
+    },
+    /// Serve the parsed tree over HTTP
+    #[cfg(feature = "serve")]
+    Serve {
+        /// Workbook to parse
+        input: PathBuf,
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:3000")]
+        addr: std::net::SocketAddr,
+    },
+    /// Browse the parsed tree in a terminal UI
+    #[cfg(feature = "browse")]
+    Browse {
+        /// Workbook to parse
+        input: PathBuf,
+    },
+    /// Export a workbook again whenever it changes, e.g. while fixing OCR
+    /// errors in Excel; parse errors are printed and watching goes on
+    Watch {
+        /// Workbook to watch
+        input: PathBuf,
+        /// Output format [default: json, or output.format of the config]
+        #[arg(short, long, value_enum)]
+        format: Option<OutputFormat>,
+        /// File to rewrite on every change instead of printing to stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Seconds between checks for a change
+        #[arg(long, default_value_t = 1.0)]
+        interval: f64,
+        /// Overwrite the output file if it exists before watching starts
+        #[arg(long)]
+        force: bool,
+    },
+    /// Explore the parsed tree interactively
+    #[cfg(feature = "shell")]
+    Shell {
+        /// Workbook to parse
+        input: PathBuf,
+    },
+    /// Print the JSON Schema of the v2 JSON layout
+    Schema,
+}
+
+#[derive(Args)]
+struct ExportArgs {
+    /// Workbook to parse
+    #[arg(required_unless_present = "glob")]
+    input: Option<PathBuf>,
+    /// Parse every workbook matching this pattern, e.g. 'data/*.xlsx', and
+    /// merge them into one tree, keeping an id repeated across workbooks
+    /// once; a per-file summary, the repeated ids and any conflicting
+    /// categories go to stderr
+    #[arg(long, conflicts_with = "input")]
+    glob: Option<String>,
+    /// Output format [default: json, or output.format of the config]
+    #[arg(short, long, value_enum)]
+    format: Option<OutputFormat>,
+    /// Write to a file instead of stdout
+    #[arg(short, long, group = "destination")]
+    output: Option<PathBuf>,
+    /// With --glob, write one file per workbook instead of merging, to this
+    /// path with {stem} replaced by the workbook name, e.g. 'out/{stem}.json'
+    #[arg(long, requires = "glob", group = "destination")]
+    each: Option<String>,
+    /// Overwrite the output file if it exists
+    #[arg(long, requires = "destination")]
+    force: bool,
+    /// Compress the output: none, gzip or zstd (needs the cargo feature)
+    /// [default: none, or output.compress of the config]
+    #[arg(long)]
+    compress: Option<Compression>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    /// Indented tree as shown by `print`
+    Tree,
+    /// Pretty-printed JSON
+    Json,
+    /// JSON on a single line
+    JsonCompact,
+    /// YAML in the v2 layout
+    Yaml,
+    /// Flat table with one row per 细类
+    Csv,
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let config = load_config(cli.config.as_deref()).and_then(|mut config| {
+        config.parse.backend = cli.backend.unwrap_or(config.parse.backend);
+        if let Some(sheet) = cli.sheet {
+            config.parse.sheet = sheet;
+        }
+        config.parse.rows = cli.rows.or(config.parse.rows);
+        config.parse.cols = cli.cols.or(config.parse.cols);
+        if cli.keep_headers {
+            config.parse.skip_headers = false;
+        }
+        config.parse.mode = cli.mode.unwrap_or(config.parse.mode);
+        config.parse.correct_names |= cli.correct_names;
+        config.parse.trace_merges |= cli.trace_merge;
+        for path in &cli.suffix_files {
+            config.parse.add_suffixes_from(path)?;
+        }
+        config.parse.add_suffixes(cli.suffixes);
+        config.cache.enabled |= cli.cache;
+        config.output.quiet |= cli.quiet;
+        Ok(config)
+    });
+    let quiet = config
+        .as_ref()
+        .map_or(cli.quiet, |config| config.output.quiet);
+    tracing_subscriber::fmt()
+        .with_env_filter(log_filter(
+            std::env::var("RUST_LOG")
+                .ok()
+                .filter(|directives| !directives.is_empty())
+                .as_deref(),
+            quiet,
+        ))
+        .with_writer(io::stderr)
+        .with_ansi(io::stderr().is_terminal())
+        .init();
+    if let Err(e) = config.and_then(|config| run(cli.command, &config)) {
+        eprintln!("error: {}", e);
+        if let KimiError::Rejected(rejected) = &e {
+            for chunk in rejected {
+                eprintln!("  {}", chunk);
+            }
+        }
         std::process::exit(1);
     }
-    let input_file = &args[1];
+}
+
+fn load_config(path: Option<&Path>) -> Result<Config, KimiError> {
+    match path {
+        Some(path) => Config::load(path),
+        None if Path::new(Config::FILE_NAME).exists() => Config::load(Config::FILE_NAME),
+        None => Ok(Config::default()),
+    }
+}
+
+fn run(command: Command, config: &Config) -> Result<(), KimiError> {
+    let parse = |input: &Path| parse(input, config);
+    match command {
+        Command::Print { input } => {
+            let tree = parse(&input)?;
+            tree.pretty_print_to(io::stdout().lock())?;
+        }
+        Command::Export(args) => export(args, config)?,
+        Command::Watch {
+            input,
+            format,
+            output,
+            interval,
+            force,
+        } => {
+            let format = output_format(format, config)?;
+            if let Some(output) = &output {
+                check_overwrite(output, force)?;
+            }
+            watch(&input, format, output.as_deref(), interval, config)?;
+        }
+        Command::Lookup { input, id, code } => {
+            let tree = parse(&input)?;
+            lookup(&tree, id, code)?;
+        }
+        Command::Search {
+            input,
+            pattern,
+            regex,
+            json,
+        } => {
+            let tree = parse(&input)?;
+            if !search(&tree, &pattern, regex, json)? {
+                std::process::exit(1);
+            }
+        }
+        Command::Stats { input, json } => {
+            let stats = parse(&input)?.stats();
+            if json {
+                println!("{}", serde_json::to_string_pretty(&stats)?);
+            } else {
+                print_stats(&stats);
+            }
+        }
+        Command::Validate {
+            input,
+            json,
+            edition,
+        } => {
+            let mut warnings = Vec::new();
+            let (tree, report) = parse_reported(&input, config, &mut warnings)?;
+            let problems = tree.validate_with(&config.parse.id_format)?;
+            let report = report.unwrap_or_default();
+            let verification = edition.map(|edition| tree.verify(edition));
+            let mismatches = cross_check(&tree, edition);
+            let verified =
+                verification.as_ref().is_none_or(Verification::is_ok) && mismatches.is_empty();
+            if json {
+                let report = serde_json::json!({
+                    "valid": problems.is_empty() && verified,
+                    "problems": problems,
+                    "edition": verification,
+                    "reference": mismatches,
+                    "rejected": report.rejected,
+                    "corrections": report.corrections,
+                    "merges": report.merges,
+                    "warnings": warnings,
+                });
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                for problem in &problems {
+                    println!("{}", problem);
+                }
+                if let Some(verification) = &verification {
+                    for discrepancy in &verification.discrepancies {
+                        println!("{} edition {}", verification.edition, discrepancy);
+                    }
+                }
+                for mismatch in &mismatches {
+                    println!("2022 edition {}", mismatch);
+                }
+                for chunk in &report.rejected {
+                    eprintln!("skipped {}", chunk);
+                }
+                for correction in &report.corrections {
+                    eprintln!("corrected {}", correction);
+                }
+                for warning in &warnings {
+                    eprintln!("warning: {}", warning);
+                }
+            }
+            if !problems.is_empty() {
+                eprintln!("{} problems found", problems.len());
+            }
+            if !problems.is_empty() || !verified {
+                std::process::exit(1);
+            }
+        }
+        Command::Diff { old, new, json } => {
+            let diff = parse(&old)?.diff(&parse(&new)?);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&diff)?);
+            } else {
+                print_diff(&diff);
+            }
+            if !diff.is_empty() {
+                std::process::exit(1);
+            }
+        }
+        Command::Convert {
+            input,
+            output,
+            force,
+        } => {
+            check_overwrite(&output, force)?;
+            convert(&parse(&input)?, &output)?;
+        }
+        #[cfg(feature = "serve")]
+        Command::Serve { input, addr } => {
+            let tree = parse(&input)?;
+            eprintln!("listening on http://{}", addr);
+            serve(tree, addr)?;
+        }
+        #[cfg(feature = "browse")]
+        Command::Browse { input } => browse(&parse(&input)?)?,
+        #[cfg(feature = "shell")]
+        Command::Shell { input } => run_shell(&parse(&input)?)?,
+        Command::Schema => {
+            println!("{}", serde_json::to_string_pretty(&json_schema())?);
+        }
+    }
+    Ok(())
+}
+
+/// `RUST_LOG` if set, e.g. kimi=debug to see why a row was dropped, else
+/// the warnings of a parse unless `quiet`
+fn log_filter(rust_log: Option<&str>, quiet: bool) -> EnvFilter {
+    match rust_log {
+        Some(directives) => EnvFilter::new(directives),
+        None if quiet => EnvFilter::new("kimi=error"),
+        None => EnvFilter::new("kimi=warn"),
+    }
+}
+
+fn parse(input: &Path, config: &Config) -> Result<CategoryTree, KimiError> {
+    let mut log = |warning: Warning| tracing::warn!("{}", warning);
+    parse_reported(input, config, &mut log).map(|(tree, _)| tree)
+}
 
+/// Like `parse`, handing warnings to `diagnostics`, with the report of the
+/// parse unless the tree came from the cache
+fn parse_reported(
+    input: &Path,
+    config: &Config,
+    diagnostics: &mut dyn Diagnostics,
+) -> Result<(CategoryTree, Option<ParseReport>), KimiError> {
+    let options = &config.parse;
     let mut tree = CategoryTree::new();
-    tree.build_from(input_file)?;
-    tree.pretty_print();
-    // json format
-    // tree.pretty_print_json();
+    let name = input.to_string_lossy();
+    if name.starts_with("http://") || name.starts_with("https://") {
+        #[cfg(feature = "fetch")]
+        {
+            let quiet = config.output.quiet;
+            let report = tree.build_from_url_with(&name, options, |done, total| match total {
+                _ if quiet => {}
+                Some(total) => eprint!("\rdownloading {} / {} KiB", done / 1024, total / 1024),
+                None => eprint!("\rdownloading {} KiB", done / 1024),
+            })?;
+            if !quiet {
+                eprintln!();
+            }
+            print_merges(&report);
+            return Ok((tree, Some(report)));
+        }
+        #[cfg(not(feature = "fetch"))]
+        return Err(invalid_input(format!(
+            "reading {} needs the fetch cargo feature",
+            name
+        )));
+    }
+    let report = if input == Path::new("-") {
+        // the zip reader needs to seek, so buffer the whole workbook
+        let mut bytes = Vec::new();
+        io::stdin().lock().read_to_end(&mut bytes)?;
+        let mut source = options.backend.from_reader(io::Cursor::new(bytes))?;
+        tree.build_from_source_with_diagnostics(&mut *source, options, diagnostics)?
+    } else if config.cache.enabled {
+        #[cfg(feature = "cache")]
+        {
+            let dir = config
+                .cache
+                .dir
+                .clone()
+                .or_else(ParseCache::default_dir)
+                .ok_or_else(|| {
+                    invalid_input("no cache directory: set cache.dir or HOME".to_string())
+                })?;
+            return Ok((ParseCache::new(dir).build(&name, options)?, None));
+        }
+        #[cfg(not(feature = "cache"))]
+        return Err(invalid_input(
+            "--cache needs the cache cargo feature".to_string(),
+        ));
+    } else if config.output.quiet {
+        tree.build_from_with_diagnostics(&name, options, diagnostics)?
+    } else {
+        let bar = ProgressBar::new(0)
+            .with_style(
+                ProgressStyle::with_template("{msg} [{bar:40}] {pos}/{len} rows")
+                    .expect("valid template")
+                    .progress_chars("=> "),
+            )
+            .with_message("parsing");
+        let on_progress = bar.clone();
+        let options = ParseOptions {
+            progress: OnProgress::new(move |progress| {
+                if progress.sheets > 1 {
+                    on_progress.set_message(format!(
+                        "sheet {}/{}",
+                        progress.sheet + 1,
+                        progress.sheets
+                    ));
+                }
+                on_progress.set_length(progress.rows.into());
+                on_progress.set_position(progress.row.into());
+            }),
+            ..options.clone()
+        };
+        let report = tree.build_from_with_diagnostics(&name, &options, diagnostics)?;
+        bar.finish_and_clear();
+        report
+    };
+    print_merges(&report);
+    Ok((tree, Some(report)))
+}
+
+/// The merge steps of `--trace-merge`, one line each
+fn print_merges(report: &ParseReport) {
+    for trace in &report.merges {
+        for step in &trace.steps {
+            eprintln!("{}: {}", trace.cell, step);
+        }
+    }
+}
+
+fn export(args: ExportArgs, config: &Config) -> Result<(), KimiError> {
+    if let Some(path) = &args.output {
+        check_overwrite(path, args.force)?;
+    }
+    let format = output_format(args.format, config)?;
+    let compression = match (args.compress, &config.output.compress) {
+        (Some(compression), _) => compression,
+        (None, Some(name)) => name
+            .parse()
+            .map_err(|e| invalid_input(format!("output.compress in config: {}", e)))?,
+        (None, None) => Compression::None,
+    };
+
+    let Some(pattern) = &args.glob else {
+        let tree = parse(args.input.as_deref().unwrap_or(Path::new("-")), config)?;
+        return write_tree(&tree, format, compression, args.output.as_deref());
+    };
+    let inputs = expand_glob(pattern)?;
+    let mut merger = VolumeMerger::new();
+    let mut summary = Vec::new();
+    for input in inputs {
+        let tree = parse(&input, config)?;
+        let name = input.display().to_string();
+        summary.push((name.clone(), tree.stats()));
+        match &args.each {
+            Some(template) => {
+                let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+                let output = PathBuf::from(template.replace("{stem}", &stem));
+                check_overwrite(&output, args.force)?;
+                write_tree(&tree, format, compression, Some(&output))?;
+            }
+            None => merger.add(name, &tree),
+        }
+    }
+    print_batch_summary(&summary);
+    if args.each.is_none() {
+        let (merged, report) = merger.finish();
+        print_merge_report(&report);
+        write_tree(&merged, format, compression, args.output.as_deref())?;
+    }
+    Ok(())
+}
+
+/// `format`, else output.format of the config, else JSON
+fn output_format(format: Option<OutputFormat>, config: &Config) -> Result<OutputFormat, KimiError> {
+    Ok(match (format, &config.output.format) {
+        (Some(format), _) => format,
+        (None, Some(name)) => OutputFormat::from_str(name, true)
+            .map_err(|e| invalid_input(format!("output.format in config: {}", e)))?,
+        (None, None) => OutputFormat::Json,
+    })
+}
+
+/// Parse and write `input` each time it changes; a change is acted on once
+/// the file has stayed the same for one `interval`, so a workbook still
+/// being saved is not read
+fn watch(
+    input: &Path,
+    format: OutputFormat,
+    output: Option<&Path>,
+    interval: f64,
+    config: &Config,
+) -> Result<(), KimiError> {
+    let interval = std::time::Duration::try_from_secs_f64(interval)
+        .map_err(|e| invalid_input(format!("--interval: {}", e)))?;
+    let mut watcher = Watcher::default();
+    loop {
+        if watcher.changed(input) {
+            match parse(input, config) {
+                Ok(tree) => {
+                    write_tree(&tree, format, Compression::None, output)?;
+                    eprintln!(
+                        "{}: {} categories, {} problems",
+                        input.display(),
+                        tree.iter().count(),
+                        tree.validate_with(&config.parse.id_format)?.len()
+                    );
+                }
+                Err(e) => eprintln!("error: {}", e),
+            }
+        }
+        std::thread::sleep(interval);
+    }
+}
+
+/// Modification time and length of a watched file
+type Stamp = (Option<std::time::SystemTime>, u64);
+
+/// The change detection of `watch`
+#[derive(Default)]
+struct Watcher {
+    /// Stamp of the file as last exported
+    done: Option<Stamp>,
+    /// Stamp of the file at the previous check
+    seen: Option<Stamp>,
+}
+
+impl Watcher {
+    /// Whether `input` changed since it was last exported and has kept the
+    /// same stamp since the previous check, so a workbook still being saved
+    /// is not read
+    fn changed(&mut self, input: &Path) -> bool {
+        let current = fs::metadata(input)
+            .ok()
+            .map(|metadata| (metadata.modified().ok(), metadata.len()));
+        let changed = current.is_some() && current != self.done && current == self.seen;
+        if changed {
+            self.done = current;
+        }
+        self.seen = current;
+        changed
+    }
+}
+
+/// Paths matching `pattern` in sorted order, failing if there are none
+fn expand_glob(pattern: &str) -> Result<Vec<PathBuf>, KimiError> {
+    let paths = glob::glob(pattern)
+        .map_err(|e| invalid_input(format!("invalid glob {:?}: {}", pattern, e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(io::Error::from)?;
+    if paths.is_empty() {
+        return Err(invalid_input(format!("no workbook matches {:?}", pattern)));
+    }
+    Ok(paths)
+}
+
+fn write_tree(
+    tree: &CategoryTree,
+    format: OutputFormat,
+    compression: Compression,
+    output: Option<&Path>,
+) -> Result<(), KimiError> {
+    let render = |w: &mut dyn Write| -> Result<(), KimiError> {
+        match format {
+            OutputFormat::Tree => tree.pretty_print_to(w)?,
+            OutputFormat::Json | OutputFormat::JsonCompact => {
+                let pretty = matches!(format, OutputFormat::Json);
+                tree.write_json(&mut *w, JsonFormat::V1, pretty)?;
+                writeln!(w)?;
+            }
+            OutputFormat::Yaml => tree.to_yaml_writer(w)?,
+            OutputFormat::Csv => tree.write_csv(w)?,
+        }
+        Ok(())
+    };
+    match output {
+        Some(path) => write_atomic(path, |file| write_compressed(file, compression, render)),
+        None => write_compressed(io::stdout().lock(), compression, render),
+    }
+}
 
+fn lookup(tree: &CategoryTree, id: Option<String>, code: Option<String>) -> Result<(), KimiError> {
+    let cat = match code {
+        Some(code) => tree
+            .find_by_code(&code)
+            .map(|(_, cat)| cat)
+            .ok_or(KimiError::CodeNotFound(code))?,
+        None => {
+            let id = id.unwrap_or_default();
+            tree.get(&id).ok_or(KimiError::IdNotFound(id))?
+        }
+    };
+    for ancestor in tree.ancestors(&cat.id) {
+        println!("{}", ancestor);
+    }
+    println!("{}", cat);
+    if let Some(source) = &cat.source {
+        println!("read from {}", source);
+    }
+    let siblings = tree.siblings(&cat.id);
+    if !siblings.is_empty() {
+        println!("\nsiblings:");
+        for sibling in siblings {
+            println!("  {}", sibling);
+        }
+    }
     Ok(())
 }
+
+#[derive(Serialize)]
+struct SearchHit<'a> {
+    #[serde(flatten)]
+    category: &'a Category,
+    /// Names from 大类 down to the direct parent
+    path: Vec<&'a str>,
+}
+
+/// Print the matches of `pattern`, returning whether there were any
+fn search(tree: &CategoryTree, pattern: &str, regex: bool, json: bool) -> Result<bool, KimiError> {
+    let matches = if regex {
+        tree.search_regex(pattern)?
+    } else {
+        tree.search(pattern)
+    };
+    let hits = matches
+        .iter()
+        .map(|cat| SearchHit {
+            category: cat,
+            path: tree
+                .ancestors(&cat.id)
+                .iter()
+                .filter_map(|c| c.desc.as_deref())
+                .collect(),
+        })
+        .collect::<Vec<_>>();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&hits)?);
+    } else {
+        for hit in &hits {
+            println!("{}", hit.category);
+            if !hit.path.is_empty() {
+                println!("    {}", hit.path.join(" > "));
+            }
+        }
+    }
+    Ok(!hits.is_empty())
+}
+
+/// The 大类 and 中类 differing from the embedded reference, when checking
+/// against the 2022 edition
+#[cfg(feature = "reference")]
+fn cross_check(tree: &CategoryTree, edition: Option<Edition>) -> Vec<Mismatch> {
+    match edition {
+        Some(Edition::Edition2022) => tree.cross_check(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(not(feature = "reference"))]
+fn cross_check(_: &CategoryTree, _: Option<Edition>) -> Vec<String> {
+    Vec::new()
+}
+
+fn print_stats(stats: &TreeStats) {
+    println!("level  count  missing code");
+    for level in Level::ALL {
+        println!(
+            "{}  {:>5}  {:>12}",
+            level,
+            stats.levels.get(level),
+            stats.missing_code.get(level)
+        );
+    }
+    if stats.unknown_level > 0 {
+        println!("deeper than 细类: {}", stats.unknown_level);
+    }
+    println!("leaves: {}", stats.leaves);
+    println!("max depth: {}", stats.max_depth);
+    println!("duplicate ids: {}", stats.duplicate_ids.len());
+    for id in &stats.duplicate_ids {
+        println!("  {}", id);
+    }
+}
+
+/// Ids shared by volumes and the categories they disagree on, to stderr
+fn print_merge_report(report: &MergeReport) {
+    for overlap in &report.overlaps {
+        eprintln!(
+            "{} repeats {} ids of {}",
+            overlap.later,
+            overlap.ids.len(),
+            overlap.earlier
+        );
+    }
+    for conflict in &report.conflicts {
+        eprintln!(
+            "conflict for {}: kept {} from {}, dropped {} from {}",
+            conflict.id, conflict.kept, conflict.kept_from, conflict.dropped, conflict.dropped_from
+        );
+    }
+}
+
+/// Per-file counts of a --glob run, on stderr to keep stdout for the tree
+fn print_batch_summary(summary: &[(String, TreeStats)]) {
+    eprint!("{}", batch_summary(summary));
+}
+
+/// The table of `print_batch_summary`, one line per file under a header
+fn batch_summary(summary: &[(String, TreeStats)]) -> String {
+    let width = summary
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0);
+    let mut table = format!(
+        "{:<width$}  {}  {}  {}  {}  duplicates\n",
+        "file",
+        Level::Major,
+        Level::Middle,
+        Level::Minor,
+        Level::Detail
+    );
+    for (name, stats) in summary {
+        table += &format!(
+            "{:<width$}  {:>4}  {:>4}  {:>4}  {:>4}  {:>10}\n",
+            name,
+            stats.levels.major,
+            stats.levels.middle,
+            stats.levels.minor,
+            stats.levels.detail,
+            stats.duplicate_ids.len()
+        );
+    }
+    table
+}
+
+fn print_diff(diff: &TreeDiff) {
+    for cat in &diff.removed {
+        println!("- {}", cat);
+    }
+    for cat in &diff.added {
+        println!("+ {}", cat);
+    }
+    for change in &diff.changed {
+        println!("~ {}", change.old);
+        println!("  {}", change.new);
+    }
+    println!(
+        "{} removed, {} added, {} changed",
+        diff.removed.len(),
+        diff.added.len(),
+        diff.changed.len()
+    );
+}
+
+fn check_overwrite(path: &Path, force: bool) -> Result<(), KimiError> {
+    if path.exists() && !force {
+        return Err(KimiError::Io(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!(
+                "{} already exists, use --force to overwrite",
+                path.display()
+            ),
+        )));
+    }
+    Ok(())
+}
+
+/// What `convert` writes, inferred from the extension of the output file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConvertFormat {
+    Json,
+    Yaml,
+    Toml,
+    Csv,
+    Ndjson,
+    Text,
+    Markdown,
+    Html,
+    Dot,
+    Opml,
+    FreeMind,
+    LtreeSql,
+    Xlsx,
+    #[cfg(feature = "sqlite")]
+    Sqlite,
+    #[cfg(feature = "parquet")]
+    Parquet,
+    #[cfg(feature = "ods")]
+    Ods,
+    #[cfg(feature = "protobuf")]
+    Protobuf,
+    #[cfg(feature = "bincode")]
+    Bincode,
+    #[cfg(feature = "msgpack")]
+    MsgPack,
+    #[cfg(feature = "cbor")]
+    Cbor,
+}
+
+/// The format of `output` and its compression, from extensions such as
+/// out.json or out.ndjson.gz
+fn convert_format(output: &Path) -> Result<(ConvertFormat, Compression), KimiError> {
+    let extension = |path: &Path| {
+        path.extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default()
+    };
+    let mut ext = extension(output);
+    let mut compression = Compression::None;
+    if ext == "gz" || ext == "zst" {
+        compression = ext.parse().map_err(invalid_input)?;
+        ext = extension(Path::new(output.file_stem().unwrap_or_default()));
+    }
+
+    let format = match ext.as_str() {
+        "json" => ConvertFormat::Json,
+        "yaml" | "yml" => ConvertFormat::Yaml,
+        "toml" => ConvertFormat::Toml,
+        "csv" => ConvertFormat::Csv,
+        "ndjson" | "jsonl" => ConvertFormat::Ndjson,
+        "txt" => ConvertFormat::Text,
+        "md" => ConvertFormat::Markdown,
+        "html" | "htm" => ConvertFormat::Html,
+        "dot" | "gv" => ConvertFormat::Dot,
+        "opml" => ConvertFormat::Opml,
+        "mm" => ConvertFormat::FreeMind,
+        "sql" => ConvertFormat::LtreeSql,
+        "xlsx" => ConvertFormat::Xlsx,
+        #[cfg(feature = "sqlite")]
+        "sqlite" | "db" => ConvertFormat::Sqlite,
+        #[cfg(feature = "parquet")]
+        "parquet" => ConvertFormat::Parquet,
+        #[cfg(feature = "ods")]
+        "ods" => ConvertFormat::Ods,
+        #[cfg(feature = "protobuf")]
+        "pb" => ConvertFormat::Protobuf,
+        #[cfg(feature = "bincode")]
+        "bin" => ConvertFormat::Bincode,
+        #[cfg(feature = "msgpack")]
+        "msgpack" => ConvertFormat::MsgPack,
+        #[cfg(feature = "cbor")]
+        "cbor" => ConvertFormat::Cbor,
+        _ => {
+            let feature = match ext.as_str() {
+                "sqlite" | "db" => Some("sqlite"),
+                "parquet" | "ods" | "msgpack" | "cbor" => Some(ext.as_str()),
+                "pb" => Some("protobuf"),
+                "bin" => Some("bincode"),
+                _ => None,
+            };
+            return Err(invalid_input(match feature {
+                Some(feature) => format!(
+                    "writing {} needs the {} cargo feature",
+                    output.display(),
+                    feature
+                ),
+                None => format!("cannot infer the output format of {}", output.display()),
+            }));
+        }
+    };
+    #[cfg(feature = "sqlite")]
+    if format == ConvertFormat::Sqlite && compression != Compression::None {
+        return Err(invalid_input(
+            "SQLite output cannot be compressed".to_string(),
+        ));
+    }
+    Ok((format, compression))
+}
+
+fn convert(tree: &CategoryTree, output: &Path) -> Result<(), KimiError> {
+    let (format, compression) = convert_format(output)?;
+    let bytes = match format {
+        #[cfg(feature = "sqlite")]
+        ConvertFormat::Sqlite => {
+            // the tables must not exist yet
+            if output.exists() {
+                fs::remove_file(output)?;
+            }
+            return tree.export_sqlite(output);
+        }
+        ConvertFormat::Json => format!("{}\n", tree.to_json_string()).into_bytes(),
+        ConvertFormat::Yaml => tree.to_yaml_string()?.into_bytes(),
+        ConvertFormat::Toml => tree.to_toml_string()?.into_bytes(),
+        ConvertFormat::Csv => tree.to_csv()?.into_bytes(),
+        ConvertFormat::Ndjson => {
+            let mut buf = Vec::new();
+            tree.to_ndjson(&mut buf)?;
+            buf
+        }
+        ConvertFormat::Text => tree.to_tree_string().into_bytes(),
+        ConvertFormat::Markdown => tree.to_markdown(MarkdownStyle::List).into_bytes(),
+        ConvertFormat::Html => tree.to_html().into_bytes(),
+        ConvertFormat::Dot => tree.to_dot(None).into_bytes(),
+        ConvertFormat::Opml => tree.to_opml("职业分类").into_bytes(),
+        ConvertFormat::FreeMind => tree.to_freemind("职业分类").into_bytes(),
+        ConvertFormat::LtreeSql => tree.to_ltree_sql().into_bytes(),
+        ConvertFormat::Xlsx => {
+            let mut buf = Vec::new();
+            tree.write_xlsx(&mut buf)?;
+            buf
+        }
+        #[cfg(feature = "parquet")]
+        ConvertFormat::Parquet => {
+            let mut buf = Vec::new();
+            tree.write_parquet(&mut buf)?;
+            buf
+        }
+        #[cfg(feature = "ods")]
+        ConvertFormat::Ods => tree.to_ods_bytes()?,
+        #[cfg(feature = "protobuf")]
+        ConvertFormat::Protobuf => tree.to_protobuf_bytes(),
+        #[cfg(feature = "bincode")]
+        ConvertFormat::Bincode => tree.to_bytes()?,
+        #[cfg(feature = "msgpack")]
+        ConvertFormat::MsgPack => tree.to_msgpack()?,
+        #[cfg(feature = "cbor")]
+        ConvertFormat::Cbor => tree.to_cbor()?,
+    };
+    write_atomic(output, |file| {
+        write_compressed(file, compression, |w| Ok(w.write_all(&bytes)?))
+    })
+}
+
+fn invalid_input(message: String) -> KimiError {
+    KimiError::Io(io::Error::new(io::ErrorKind::InvalidInput, message))
+}
+
+/// Write `path` through a temporary file in the same directory that is
+/// renamed into place once complete, so readers never see a partial file
+fn write_atomic<F>(path: &Path, write: F) -> Result<(), KimiError>
+where
+    F: FnOnce(BufWriter<&File>) -> Result<(), KimiError>,
+{
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = path.with_file_name(format!(".{}.{}.tmp", name, std::process::id()));
+    let result = File::create(&tmp)
+        .map_err(KimiError::from)
+        .and_then(|file| {
+            write(BufWriter::new(&file))?;
+            file.sync_all()?;
+            Ok(())
+        })
+        .and_then(|()| Ok(fs::rename(&tmp, path)?));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty directory of its own for `test`
+    fn scratch_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("kimi-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// A workbook with one category per cell down column C
+    fn write_workbook(path: &Path, cells: &[&str]) {
+        let mut book = umya_spreadsheet::new_file();
+        let sheet = book.get_sheet_mut(&0).unwrap();
+        for (row, text) in (1..).zip(cells) {
+            sheet.get_cell_mut((3, row)).set_value(*text);
+        }
+        umya_spreadsheet::writer::xlsx::write(&book, path).unwrap();
+    }
+
+    #[test]
+    fn test_convert_format() {
+        let format = |path: &str| convert_format(Path::new(path));
+        assert_eq!(
+            format("out.json").unwrap(),
+            (ConvertFormat::Json, Compression::None)
+        );
+        assert_eq!(
+            format("OUT.CSV").unwrap(),
+            (ConvertFormat::Csv, Compression::None)
+        );
+        assert_eq!(
+            format("dir.v2/out.jsonl").unwrap(),
+            (ConvertFormat::Ndjson, Compression::None)
+        );
+        let message = |path: &str| format(path).unwrap_err().to_string();
+        assert!(message("out.docx").contains("cannot infer the output format of out.docx"));
+        assert!(message("out").contains("cannot infer"));
+
+        #[cfg(feature = "gzip")]
+        assert_eq!(
+            format("out.json.gz").unwrap(),
+            (ConvertFormat::Json, Compression::Gzip)
+        );
+        #[cfg(not(feature = "gzip"))]
+        assert!(message("out.json.gz").contains("gzip and zstd need their cargo features"));
+        #[cfg(feature = "sqlite")]
+        {
+            assert_eq!(
+                format("out.sqlite").unwrap(),
+                (ConvertFormat::Sqlite, Compression::None)
+            );
+            #[cfg(feature = "gzip")]
+            assert!(message("out.db.gz").contains("cannot be compressed"));
+        }
+        #[cfg(not(feature = "sqlite"))]
+        assert!(message("out.sqlite").contains("needs the sqlite cargo feature"));
+        #[cfg(not(feature = "protobuf"))]
+        assert!(message("out.pb").contains("needs the protobuf cargo feature"));
+    }
+
+    #[test]
+    fn test_write_atomic() {
+        let dir = scratch_dir("write-atomic");
+        let path = dir.join("out.json");
+        fs::write(&path, "old").unwrap();
+        let Err(KimiError::Io(e)) = check_overwrite(&path, false) else {
+            panic!("existing file accepted without --force");
+        };
+        assert_eq!(e.kind(), io::ErrorKind::AlreadyExists);
+
+        check_overwrite(&path, true).unwrap();
+        write_atomic(&path, |mut file| Ok(file.write_all(b"new")?)).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+
+        // a failing writer leaves the file as it was and no temporary file
+        let err = write_atomic(&path, |mut file| {
+            file.write_all(b"partial")?;
+            Err(invalid_input("render failed".to_string()))
+        });
+        assert!(err.is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        let names = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["out.json"]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_watch() {
+        let dir = scratch_dir("watch");
+        let (input, output) = (dir.join("dict.xlsx"), dir.join("out.json"));
+        let mut watcher = Watcher::default();
+        assert!(!watcher.changed(&input));
+
+        write_workbook(&input, &["2-04(GBM20400)交通运输工程技术人员"]);
+        // exported once the file has kept its stamp for a check
+        assert!(!watcher.changed(&input));
+        assert!(watcher.changed(&input));
+        assert!(!watcher.changed(&input));
+
+        write_workbook(
+            &input,
+            &[
+                "2-04(GBM20400)交通运输工程技术人员",
+                "2-04-02(GBM20402)船舶指挥和引航人员",
+            ],
+        );
+        assert!(!watcher.changed(&input));
+        assert!(watcher.changed(&input));
+        let tree = parse(&input, &Config::default()).unwrap();
+        write_tree(&tree, OutputFormat::Json, Compression::None, Some(&output)).unwrap();
+        let written = CategoryTree::from_json_reader(File::open(&output).unwrap()).unwrap();
+        assert!(written.get("2-04-02").is_some());
+
+        // the existing output is kept without --force
+        assert!(check_overwrite(&output, false).is_err());
+        assert!(check_overwrite(&output, true).is_ok());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_export_glob() {
+        let dir = scratch_dir("export-glob");
+        write_workbook(
+            &dir.join("a.xlsx"),
+            &[
+                "2-04(GBM20400)交通运输工程技术人员",
+                "2-04-02(GBM20402)船舶指挥和引航人员",
+            ],
+        );
+        write_workbook(
+            &dir.join("b.xlsx"),
+            &[
+                "2-04(GBM20400)交通运输工程技术人员",
+                "2-05(GBM20500)农业技术人员",
+            ],
+        );
+        let pattern = dir.join("*.xlsx").display().to_string();
+        let args = |output: Option<PathBuf>, each: Option<String>| ExportArgs {
+            input: None,
+            glob: Some(pattern.clone()),
+            format: Some(OutputFormat::Json),
+            output,
+            each,
+            force: false,
+            compress: None,
+        };
+        let read = |path: &Path| CategoryTree::from_json_reader(File::open(path).unwrap()).unwrap();
+
+        // merged, keeping the id repeated across the workbooks once
+        let merged = dir.join("merged.json");
+        export(args(Some(merged.clone()), None), &Config::default()).unwrap();
+        let tree = read(&merged);
+        assert!(tree.get("2-04-02").is_some() && tree.get("2-05").is_some());
+        assert_eq!(tree.iter().filter(|(_, cat)| cat.id == "2-04").count(), 1);
+
+        // one file per workbook
+        let each = dir.join("{stem}.json").display().to_string();
+        export(args(None, Some(each.clone())), &Config::default()).unwrap();
+        let (a, b) = (read(&dir.join("a.json")), read(&dir.join("b.json")));
+        assert!(a.get("2-04-02").is_some() && a.get("2-05").is_none());
+        assert!(b.get("2-05").is_some() && b.get("2-04-02").is_none());
+        // an existing output is kept without --force
+        assert!(export(args(None, Some(each)), &Config::default()).is_err());
+
+        let summary = expand_glob(&pattern)
+            .unwrap()
+            .iter()
+            .map(|input| {
+                let tree = parse(input, &Config::default()).unwrap();
+                (input.display().to_string(), tree.stats())
+            })
+            .collect::<Vec<_>>();
+        let table = batch_summary(&summary);
+        let rows = table
+            .lines()
+            .map(|line| line.split_whitespace().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].first(), Some(&"file"));
+        assert_eq!(rows[0].last(), Some(&"duplicates"));
+        assert_eq!(rows[1], [summary[0].0.as_str(), "0", "1", "1", "0", "0"]);
+        assert_eq!(rows[2], [summary[1].0.as_str(), "0", "2", "0", "0", "0"]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_log_filter() {
+        assert_eq!(log_filter(None, false).to_string(), "kimi=warn");
+        assert_eq!(log_filter(None, true).to_string(), "kimi=error");
+        assert_eq!(
+            log_filter(Some("kimi=debug"), true).to_string(),
+            "kimi=debug"
+        );
+    }
+
+    #[test]
+    fn test_parse_alias() {
+        let input = |args: &[&str]| match Cli::try_parse_from(args).unwrap().command {
+            Command::Export(args) => (args.input, args.glob),
+            _ => panic!("not parsed as export"),
+        };
+        assert_eq!(
+            input(&["kimi", "parse", "-"]),
+            (Some(PathBuf::from("-")), None)
+        );
+        assert_eq!(
+            input(&[
+                "kimi",
+                "-q",
+                "parse",
+                "https://example.com/202306151255033.xlsx"
+            ]),
+            (
+                Some(PathBuf::from("https://example.com/202306151255033.xlsx")),
+                None
+            )
+        );
+        assert_eq!(
+            input(&["kimi", "parse", "--glob", "data/*.xlsx", "-f", "yaml"]),
+            (None, Some("data/*.xlsx".to_string()))
+        );
+        assert!(Cli::try_parse_from(["kimi", "parse"]).is_err());
+    }
+}
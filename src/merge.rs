@@ -0,0 +1,236 @@
+use crate::{Category, CategoryTree, KimiError};
+use std::collections::{BTreeMap, HashMap};
+
+/// How to resolve categories with the same id when merging trees
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergeStrategy {
+    /// Keep the category already in the tree
+    #[default]
+    KeepFirst,
+    /// Replace it with the category from the merged tree
+    KeepLast,
+    /// Fail if the two categories differ
+    Error,
+}
+
+impl CategoryTree {
+    /// Merge `other` into this tree
+    ///
+    /// With `MergeStrategy::Error` the tree is left untouched when a
+    /// conflict is found.
+    pub fn merge(&mut self, other: CategoryTree, strategy: MergeStrategy) -> Result<(), KimiError> {
+        if strategy == MergeStrategy::Error {
+            for (_, cat) in &other {
+                if let Some(existing) = self.get(&cat.id)
+                    && existing != cat
+                {
+                    return Err(KimiError::MergeConflict(cat.id.clone()));
+                }
+            }
+        }
+        self.merge_node(other, strategy);
+        Ok(())
+    }
+
+    fn merge_node(&mut self, other: CategoryTree, strategy: MergeStrategy) {
+        for cat in other.items {
+            match self.items.iter_mut().find(|c| c.id == cat.id) {
+                Some(existing) if strategy == MergeStrategy::KeepLast => *existing = cat,
+                Some(_) => {}
+                None => self.items.push(cat),
+            }
+        }
+        for (key, child) in other.children {
+            self.children
+                .entry(key)
+                .or_default()
+                .merge_node(child, strategy);
+        }
+    }
+}
+
+/// Ids a volume shares with an earlier one, e.g. a page repeated on both
+/// sides of a volume break
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VolumeOverlap {
+    pub earlier: String,
+    pub later: String,
+    pub ids: Vec<String>,
+}
+
+/// Two volumes giving different categories for the same id
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VolumeConflict {
+    pub id: String,
+    /// Volume of the category left in the tree
+    pub kept_from: String,
+    pub kept: Category,
+    pub dropped_from: String,
+    pub dropped: Category,
+}
+
+/// What `VolumeMerger` found while merging
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MergeReport {
+    pub overlaps: Vec<VolumeOverlap>,
+    pub conflicts: Vec<VolumeConflict>,
+}
+
+/// Merges the trees of several volumes of one edition, parsed one by one
+///
+/// An id already read from an earlier volume is not added again. When the
+/// two categories differ the one with the higher OCR confidence is kept, a
+/// known confidence beating an unknown one and the earlier volume winning
+/// ties, and the pair is reported as a conflict.
+#[derive(Debug, Default)]
+pub struct VolumeMerger {
+    tree: CategoryTree,
+    volumes: Vec<String>,
+    /// Volume each id was taken from
+    origin: HashMap<String, usize>,
+    report: MergeReport,
+}
+
+impl VolumeMerger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Merge the tree parsed from `volume`, e.g. its file name
+    pub fn add(&mut self, volume: impl Into<String>, tree: &CategoryTree) {
+        let index = self.volumes.len();
+        self.volumes.push(volume.into());
+        let mut overlaps: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+        for (_, cat) in tree {
+            let earlier = match self.origin.get(&cat.id) {
+                // repeated within the volume, left for `validate` to report
+                Some(&earlier) if earlier != index => earlier,
+                _ => {
+                    self.origin.insert(cat.id.clone(), index);
+                    self.tree.insert(cat.id.clone(), cat.clone());
+                    continue;
+                }
+            };
+            overlaps.entry(earlier).or_default().push(cat.id.clone());
+            let Some(existing) = self
+                .tree
+                .get_node_mut(&cat.id)
+                .and_then(|node| node.items.iter_mut().find(|c| c.id == cat.id))
+            else {
+                continue;
+            };
+            if existing == cat {
+                continue;
+            }
+            let (kept, dropped) = if cat.confidence > existing.confidence {
+                self.origin.insert(cat.id.clone(), index);
+                (
+                    (index, cat.clone()),
+                    (earlier, std::mem::replace(existing, cat.clone())),
+                )
+            } else {
+                ((earlier, existing.clone()), (index, cat.clone()))
+            };
+            self.report.conflicts.push(VolumeConflict {
+                id: cat.id.clone(),
+                kept_from: self.volumes[kept.0].clone(),
+                kept: kept.1,
+                dropped_from: self.volumes[dropped.0].clone(),
+                dropped: dropped.1,
+            });
+        }
+        for (earlier, ids) in overlaps {
+            self.report.overlaps.push(VolumeOverlap {
+                earlier: self.volumes[earlier].clone(),
+                later: self.volumes[index].clone(),
+                ids,
+            });
+        }
+    }
+
+    /// The merged tree and what was found merging it
+    pub fn finish(self) -> (CategoryTree, MergeReport) {
+        (self.tree, self.report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree(text: &str) -> CategoryTree {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column(text).unwrap();
+        tree
+    }
+
+    #[test]
+    fn test_merge_strategies() {
+        let a = tree("1-01(GBM10100)机关负责人\n1-01-00-01中国共产党机关负责人");
+        let b = tree("1-01(GBM10100)国家机关负责人\n1-02(GBM10200)国家机关负责人");
+
+        let mut first = a.clone();
+        first.merge(b.clone(), MergeStrategy::KeepFirst).unwrap();
+        assert_eq!(
+            first.get("1-01").unwrap().desc.as_deref(),
+            Some("机关负责人")
+        );
+        assert!(first.get("1-02").is_some());
+        assert!(first.get("1-01-00-01").is_some());
+        assert_eq!(first.iter().count(), 3);
+
+        let mut last = a.clone();
+        last.merge(b.clone(), MergeStrategy::KeepLast).unwrap();
+        assert_eq!(
+            last.get("1-01").unwrap().desc.as_deref(),
+            Some("国家机关负责人")
+        );
+        assert_eq!(last.iter().count(), 3);
+
+        let mut strict = a.clone();
+        let err = strict.merge(b, MergeStrategy::Error).unwrap_err();
+        assert!(matches!(err, KimiError::MergeConflict(id) if id == "1-01"));
+        assert!(strict.get("1-02").is_none());
+
+        // identical entries are not a conflict
+        let mut strict = a.clone();
+        strict.merge(a, MergeStrategy::Error).unwrap();
+        assert_eq!(strict.iter().count(), 2);
+    }
+
+    #[test]
+    fn test_volume_merger() {
+        let first = tree("1-01(GBM10100)机关负责人\n1-01-00-01中国共产党机关负责人");
+        let second = tree("1-01(GBM10100)机关负责人\n1-01-00-01中国共产觉机关负责人");
+        let mut merger = VolumeMerger::new();
+        merger.add("卷1.xlsx", &first);
+        merger.add("卷2.xlsx", &second);
+        let (tree, report) = merger.finish();
+        assert_eq!(tree.iter().count(), 2);
+        assert_eq!(
+            report.overlaps,
+            [VolumeOverlap {
+                earlier: "卷1.xlsx".to_string(),
+                later: "卷2.xlsx".to_string(),
+                ids: vec!["1-01".to_string(), "1-01-00-01".to_string()],
+            }]
+        );
+        // without OCR confidence the earlier volume wins
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].kept_from, "卷1.xlsx");
+
+        let mut scored = second.clone();
+        if let Some(node) = scored.get_node_mut("1-01-00-01") {
+            node.items[0].confidence = Some(40);
+        }
+        let mut merger = VolumeMerger::new();
+        merger.add("卷1.xlsx", &first);
+        merger.add("卷2.xlsx", &scored);
+        let (tree, report) = merger.finish();
+        assert_eq!(
+            tree.get("1-01-00-01").unwrap().desc.as_deref(),
+            Some("中国共产觉机关负责人")
+        );
+        assert_eq!(report.conflicts[0].dropped_from, "卷1.xlsx");
+    }
+}
@@ -0,0 +1,312 @@
+//! OCR output
+//!
+//! hOCR and ALTO XML, as written by Tesseract's `hocr` and `alto` configs,
+//! give each recognized word with its bounding box and confidence. Each page
+//! becomes one row of a single sheet, with a cell per column of words found
+//! by `page::page_columns`, and the confidence of each word is kept with its
+//! characters so the categories read from them can be scored.
+
+use crate::page::{Glyph, page_columns};
+use crate::{CategoryTree, KimiError, ParseOptions, ParseReport, Sheet, SpreadsheetSource};
+use quick_xml::Reader;
+use quick_xml::events::{BytesStart, Event};
+use std::fs;
+use std::path::Path;
+
+/// Pages of an hOCR or ALTO file, one row each, as a sheet named "Document"
+pub struct OcrSource {
+    sheet: Sheet,
+}
+
+impl OcrSource {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, KimiError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Read ALTO if the document has an `<alto>` root, hOCR otherwise
+    pub fn parse(xml: &str) -> Result<Self, KimiError> {
+        let pages = if xml.contains("<alto") {
+            alto_pages(xml)?
+        } else {
+            hocr_pages(xml)?
+        };
+        let mut sheet = Sheet::new("Document");
+        for (row, page) in (1..).zip(&pages) {
+            for (col, (text, confidence)) in (1..).zip(page_columns(page)) {
+                sheet.set_text(col, row, text);
+                sheet.set_confidence(col, row, confidence);
+            }
+        }
+        Ok(OcrSource { sheet })
+    }
+}
+
+impl SpreadsheetSource for OcrSource {
+    fn sheet_names(&self) -> Vec<String> {
+        vec![self.sheet.name().to_string()]
+    }
+
+    fn read_sheet(&mut self, index: usize) -> Result<Sheet, KimiError> {
+        match index {
+            0 => Ok(self.sheet.clone()),
+            _ => Err(KimiError::SheetNotFound(index)),
+        }
+    }
+}
+
+impl CategoryTree {
+    /// Parse the words of an hOCR or ALTO file, like `build_from_with`,
+    /// setting the confidence of each category; see `low_confidence`
+    pub fn build_from_ocr(
+        &mut self,
+        input_file: &str,
+        options: &ParseOptions,
+    ) -> Result<ParseReport, KimiError> {
+        self.build_from_source(&mut OcrSource::open(input_file)?, options)
+    }
+}
+
+fn document_error(e: impl std::fmt::Display) -> KimiError {
+    KimiError::Document(e.to_string())
+}
+
+/// Words of each `ocr_page`, from the `ocrx_word` elements
+fn hocr_pages(html: &str) -> Result<Vec<Vec<Glyph>>, KimiError> {
+    let mut reader = Reader::from_str(html);
+    reader.config_mut().check_end_names = false;
+    let mut pages = Vec::new();
+    let mut depth = 0;
+    // the word being read, with the depth of its element
+    let mut word: Option<(Glyph, usize)> = None;
+    loop {
+        match reader.read_event().map_err(document_error)? {
+            Event::Start(e) => {
+                depth += 1;
+                let class = attribute(&e, b"class")?.unwrap_or_default();
+                let mut classes = class.split_whitespace();
+                if classes.clone().any(|class| class == "ocr_page") {
+                    pages.push(Vec::new());
+                } else if word.is_none() && classes.any(|class| class == "ocrx_word") {
+                    let title = attribute(&e, b"title")?.unwrap_or_default();
+                    word = hocr_word(&title).map(|glyph| (glyph, depth));
+                }
+            }
+            Event::Text(e) => {
+                if let Some((glyph, _)) = &mut word {
+                    glyph.text.push_str(&e.unescape().map_err(document_error)?);
+                }
+            }
+            Event::End(_) => {
+                if word.as_ref().is_some_and(|(_, at)| *at == depth)
+                    && let Some((glyph, _)) = word.take()
+                {
+                    push_word(&mut pages, glyph);
+                }
+                depth = depth.saturating_sub(1);
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(pages)
+}
+
+/// A word without its text from the `title` of an `ocrx_word`, e.g.
+/// `bbox 120 300 410 342; x_wconf 93`
+fn hocr_word(title: &str) -> Option<Glyph> {
+    let mut bbox = None;
+    let mut confidence = None;
+    for property in title.split(';') {
+        let mut values = property.split_whitespace();
+        match values.next() {
+            Some("bbox") => {
+                if let [x0, y0, x1, y1] = values
+                    .filter_map(|v| v.parse::<f64>().ok())
+                    .collect::<Vec<_>>()[..]
+                {
+                    bbox = Some((x0, y0, x1, y1));
+                }
+            }
+            Some("x_wconf") => {
+                confidence = values
+                    .next()
+                    .and_then(|v| v.parse::<f64>().ok())
+                    .map(|c| c.clamp(0., 100.).round() as u8);
+            }
+            _ => {}
+        }
+    }
+    let (x0, y0, x1, y1) = bbox?;
+    Some(word_glyph(x0, y0, x1, y1, confidence))
+}
+
+/// Words of each `Page`, from the `String` elements
+fn alto_pages(xml: &str) -> Result<Vec<Vec<Glyph>>, KimiError> {
+    let mut reader = Reader::from_str(xml);
+    let mut pages = Vec::new();
+    loop {
+        match reader.read_event().map_err(document_error)? {
+            Event::Start(e) | Event::Empty(e) => match e.local_name().as_ref() {
+                b"Page" => pages.push(Vec::new()),
+                b"String" => {
+                    let number = |name| -> Result<Option<f64>, KimiError> {
+                        Ok(attribute(&e, name)?.and_then(|v| v.trim().parse().ok()))
+                    };
+                    let x = number(b"HPOS")?.unwrap_or(0.);
+                    let y = number(b"VPOS")?.unwrap_or(0.);
+                    let confidence =
+                        number(b"WC")?.map(|c| (c * 100.).clamp(0., 100.).round() as u8);
+                    let mut glyph = word_glyph(
+                        x,
+                        y,
+                        x + number(b"WIDTH")?.unwrap_or(0.),
+                        y + number(b"HEIGHT")?.unwrap_or(0.),
+                        confidence,
+                    );
+                    glyph.text = attribute(&e, b"CONTENT")?.unwrap_or_default();
+                    push_word(&mut pages, glyph);
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(pages)
+}
+
+/// A word whose bounding box runs from (x0, y0) to (x1, y1), sitting on
+/// its bottom edge
+fn word_glyph(x0: f64, y0: f64, x1: f64, y1: f64, confidence: Option<u8>) -> Glyph {
+    Glyph {
+        x: x0,
+        end: x1,
+        y: y1,
+        size: (y1 - y0).max(1.),
+        text: String::new(),
+        confidence,
+    }
+}
+
+fn push_word(pages: &mut Vec<Vec<Glyph>>, mut glyph: Glyph) {
+    glyph.text = glyph.text.trim().to_string();
+    if glyph.text.is_empty() {
+        return;
+    }
+    if pages.is_empty() {
+        pages.push(Vec::new());
+    }
+    if let Some(page) = pages.last_mut() {
+        page.push(glyph);
+    }
+}
+
+fn attribute(e: &BytesStart, name: &[u8]) -> Result<Option<String>, KimiError> {
+    for attr in e.attributes() {
+        let attr = attr.map_err(document_error)?;
+        if attr.key.local_name().as_ref() == name {
+            return Ok(Some(
+                attr.unescape_value().map_err(document_error)?.into_owned(),
+            ));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColumnGroup, LayoutProfile};
+
+    /// An `ocrx_word` span at x0..x1 on the line ending at y
+    fn word(x0: u32, x1: u32, y: u32, confidence: u8, text: &str) -> String {
+        format!(
+            r#"<span class="ocrx_word" title="bbox {x0} {} {x1} {y}; x_wconf {confidence}">{text}</span>"#,
+            y - 30
+        )
+    }
+
+    #[test]
+    fn test_hocr_confidence() {
+        let words = [
+            word(100, 300, 130, 95, "2-04-02-01"),
+            word(400, 560, 130, 90, "船舶驾驶员"),
+            word(100, 300, 180, 96, "2-04-02-02"),
+            word(400, 560, 180, 41, "<strong>船舶</strong>引航员"),
+            word(570, 590, 180, 88, "L"),
+        ];
+        let hocr = format!(
+            r#"<html xmlns="http://www.w3.org/1999/xhtml"><body>
+            <div class="ocr_page" title="bbox 0 0 2480 3508"><p class="ocr_par">{}</p></div>
+            </body></html>"#,
+            words.join(" ")
+        );
+        let mut source = OcrSource::parse(&hocr).unwrap();
+        let sheet = source.read_sheet(0).unwrap();
+        assert_eq!(sheet.text(1, 1), "2-04-02-01\n2-04-02-02");
+        assert_eq!(sheet.text(2, 1), "船舶驾驶员\n船舶引航员 L");
+
+        let options = ParseOptions {
+            layout: Some(LayoutProfile {
+                columns: Vec::new(),
+                paired_columns: vec![ColumnGroup::pair(1, 2)],
+                ignore: Vec::new(),
+            }),
+            ..ParseOptions::default()
+        };
+        let mut tree = CategoryTree::new();
+        tree.build_from_source(&mut source, &options).unwrap();
+        assert_eq!(tree.get("2-04-02-01").unwrap().confidence, Some(90));
+        let pilot = tree.get("2-04-02-02").unwrap();
+        assert_eq!(pilot.confidence, Some(41));
+        assert!(pilot.is_green);
+        assert_eq!(tree.low_confidence(60), [pilot]);
+    }
+
+    #[test]
+    fn test_alto_pages() {
+        let alto = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <alto xmlns="http://www.loc.gov/standards/alto/ns-v3#"><Layout>
+            <Page ID="page_0"><PrintSpace><TextBlock><TextLine>
+              <String CONTENT="2-04-02-01" HPOS="100" VPOS="100" WIDTH="200" HEIGHT="30" WC="0.95"/>
+              <SP/>
+              <String CONTENT="船舶驾驶员" HPOS="400" VPOS="100" WIDTH="160" HEIGHT="30" WC="0.62"/>
+            </TextLine></TextBlock></PrintSpace></Page>
+            <Page ID="page_1"><PrintSpace><TextBlock><TextLine>
+              <String CONTENT="2-04-02-02" HPOS="100" VPOS="100" WIDTH="200" HEIGHT="30" WC="0.9"/>
+            </TextLine></TextBlock></PrintSpace></Page>
+            </Layout></alto>"#;
+        let sheet = OcrSource::parse(alto).unwrap().read_sheet(0).unwrap();
+        assert_eq!(sheet.highest_row(), 2);
+        assert_eq!(sheet.text(2, 1), "船舶驾驶员");
+        assert_eq!(sheet.text(1, 2), "2-04-02-02");
+        let mut from = 0;
+        assert_eq!(sheet.confidence(2, 1).lowest("驾驶", &mut from), Some(62));
+        assert_eq!(from, 4);
+    }
+
+    #[test]
+    fn test_missing_width() {
+        // HPOS and WIDTH are optional in ALTO
+        let alto = r#"<alto xmlns="http://www.loc.gov/standards/alto/ns-v3#"><Layout>
+            <Page ID="page_0"><PrintSpace><TextBlock><TextLine>
+              <String CONTENT="2-04-02-01" VPOS="100" HEIGHT="30"/>
+              <String CONTENT="船舶驾驶员" VPOS="100" HEIGHT="30"/>
+            </TextLine></TextBlock></PrintSpace></Page>
+            </Layout></alto>"#;
+        let sheet = OcrSource::parse(alto).unwrap().read_sheet(0).unwrap();
+        assert_eq!(sheet.text(1, 1), "2-04-02-01船舶驾驶员");
+
+        // a bbox whose right edge is left of its left edge
+        let hocr = format!(
+            r#"<html xmlns="http://www.w3.org/1999/xhtml"><body>
+            <div class="ocr_page" title="bbox 0 0 2480 3508"><p class="ocr_par">{} {}</p></div>
+            </body></html>"#,
+            word(300, 100, 130, 95, "2-04-02-01"),
+            word(400, 560, 130, 90, "船舶驾驶员"),
+        );
+        let sheet = OcrSource::parse(&hocr).unwrap().read_sheet(0).unwrap();
+        assert_eq!(sheet.text(1, 1), "2-04-02-01");
+        assert_eq!(sheet.text(2, 1), "船舶驾驶员");
+    }
+}
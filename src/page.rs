@@ -0,0 +1,151 @@
+//! Columns of a page of placed text
+//!
+//! Shared by the readers of PDF text layers and of OCR output, which both
+//! give the position of each piece of text on a page rather than cells.
+//! The text is split into columns at the vertical gutters free of text, and
+//! each column into lines by baseline. A banner or page number crossing a
+//! gutter does not close it, as only x positions covered by more than a
+//! twentieth of the lines of the page count as text.
+
+use crate::Confidence;
+
+/// A character or word placed on the page, from the top left corner; `y`
+/// is its baseline and `size` its height
+#[derive(Debug, Clone)]
+pub(crate) struct Glyph {
+    pub x: f64,
+    pub end: f64,
+    pub y: f64,
+    pub size: f64,
+    pub text: String,
+    /// OCR confidence, 0 to 100
+    pub confidence: Option<u8>,
+}
+
+/// Text of each column of a page, left to right, with the confidence of
+/// its characters
+pub(crate) fn page_columns(glyphs: &[Glyph]) -> Vec<(String, Confidence)> {
+    if glyphs.is_empty() {
+        return Vec::new();
+    }
+    let lines = baselines(glyphs);
+    let size = median_size(glyphs);
+
+    // lines covering each point of the page width; a gutter is a run of
+    // points covered by at most a twentieth of the lines
+    let width = glyphs
+        .iter()
+        .map(|g| g.x.max(g.end))
+        .fold(0., f64::max)
+        .ceil() as usize
+        + 1;
+    let mut coverage = vec![Vec::new(); width];
+    for (line, glyphs) in lines.iter().enumerate() {
+        for glyph in glyphs {
+            // a mirrored glyph ends left of where it starts
+            let (left, right) = (glyph.x.min(glyph.end), glyph.x.max(glyph.end));
+            for point in &mut coverage[left.max(0.) as usize..right.max(0.).ceil() as usize] {
+                if point.last() != Some(&line) {
+                    point.push(line);
+                }
+            }
+        }
+    }
+    let sparse = lines.len() / 20;
+    let mut columns: Vec<(f64, f64)> = Vec::new();
+    let mut start = None;
+    for (point, lines) in coverage.iter().enumerate() {
+        let point = point as f64;
+        match (lines.len() > sparse, start) {
+            (true, None) => start = Some(point),
+            (false, Some(first)) => {
+                columns.push((first, point));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(first) = start {
+        columns.push((first, width as f64));
+    }
+    // glyphs without a width, e.g. ALTO strings lacking WIDTH, cover nothing
+    if columns.is_empty() {
+        columns.push((0., width as f64));
+    }
+    // gaps narrower than a character are spaces within a column
+    columns.dedup_by(|next, column| {
+        let joined = next.0 - column.1 < size;
+        if joined {
+            column.1 = next.1;
+        }
+        joined
+    });
+
+    let column_of = |glyph: &Glyph| {
+        let center = (glyph.x + glyph.end) / 2.;
+        columns
+            .iter()
+            .position(|&(_, last)| center < last)
+            .unwrap_or(columns.len() - 1)
+    };
+    let mut texts = vec![(Vec::new(), Confidence::default()); columns.len()];
+    for glyphs in &lines {
+        let mut parts = vec![Vec::new(); columns.len()];
+        for glyph in glyphs {
+            parts[column_of(glyph)].push(*glyph);
+        }
+        for ((text, confidence), part) in texts.iter_mut().zip(parts) {
+            if !part.is_empty() {
+                text.push(line_text(&part));
+                for glyph in part {
+                    if let Some(c) = glyph.confidence {
+                        confidence.push(&glyph.text, c);
+                    }
+                }
+            }
+        }
+    }
+    texts
+        .into_iter()
+        .map(|(lines, confidence)| (lines.join("\n"), confidence))
+        .collect()
+}
+
+/// Glyphs grouped by baseline, top to bottom, each line left to right
+fn baselines(glyphs: &[Glyph]) -> Vec<Vec<&Glyph>> {
+    let mut sorted = glyphs.iter().collect::<Vec<_>>();
+    sorted.sort_by(|a, b| a.y.total_cmp(&b.y));
+    let mut lines: Vec<Vec<&Glyph>> = Vec::new();
+    for glyph in sorted {
+        match lines.last_mut() {
+            Some(line) if (glyph.y - line[0].y).abs() < line[0].size.min(glyph.size) / 2. => {
+                line.push(glyph)
+            }
+            _ => lines.push(vec![glyph]),
+        }
+    }
+    for line in &mut lines {
+        line.sort_by(|a, b| a.x.total_cmp(&b.x));
+    }
+    lines
+}
+
+/// Characters of one line, with a space where they are set apart
+fn line_text(glyphs: &[&Glyph]) -> String {
+    let mut text = String::new();
+    let mut end = None;
+    for glyph in glyphs {
+        if end.is_some_and(|end| glyph.x - end > glyph.size * 0.2) {
+            text.push(' ');
+        }
+        text.push_str(&glyph.text);
+        end = Some(glyph.end);
+    }
+    text
+}
+
+fn median_size(glyphs: &[Glyph]) -> f64 {
+    let mut sizes = glyphs.iter().map(|g| g.size).collect::<Vec<_>>();
+    sizes.sort_by(f64::total_cmp);
+    sizes[sizes.len() / 2]
+}
@@ -0,0 +1,252 @@
+//! PDF text layers
+//!
+//! A PDF with a text layer can be read directly instead of going through
+//! OCR into Excel. Each page becomes one row of a single sheet, with a cell
+//! per column of text found by `page::page_columns`.
+
+use crate::page::{Glyph, page_columns};
+use crate::{CategoryTree, KimiError, ParseOptions, ParseReport, Sheet, SpreadsheetSource};
+use pdf_extract::{Document, MediaBox, OutputDev, OutputError, Transform};
+use std::path::Path;
+
+/// Pages of a PDF, one row each, as a sheet named "Document"
+pub struct PdfSource {
+    sheet: Sheet,
+}
+
+impl PdfSource {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, KimiError> {
+        Self::from_document(&Document::load(path).map_err(document_error)?)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, KimiError> {
+        Self::from_document(&Document::load_mem(bytes).map_err(document_error)?)
+    }
+
+    fn from_document(document: &Document) -> Result<Self, KimiError> {
+        let mut glyphs = GlyphCollector::default();
+        pdf_extract::output_doc(document, &mut glyphs).map_err(document_error)?;
+        let mut sheet = Sheet::new("Document");
+        for (row, page) in (1..).zip(&glyphs.pages) {
+            for (col, (text, _)) in (1..).zip(page_columns(page)) {
+                sheet.set_text(col, row, text);
+            }
+        }
+        Ok(PdfSource { sheet })
+    }
+}
+
+impl SpreadsheetSource for PdfSource {
+    fn sheet_names(&self) -> Vec<String> {
+        vec![self.sheet.name().to_string()]
+    }
+
+    fn read_sheet(&mut self, index: usize) -> Result<Sheet, KimiError> {
+        match index {
+            0 => Ok(self.sheet.clone()),
+            _ => Err(KimiError::SheetNotFound(index)),
+        }
+    }
+}
+
+impl CategoryTree {
+    /// Parse the text layer of a PDF, skipping the OCR step
+    pub fn build_from_pdf(&mut self, input_file: &str) -> Result<ParseReport, KimiError> {
+        self.build_from_pdf_with(input_file, &ParseOptions::default())
+    }
+
+    /// Like `build_from_pdf`, with the sheet layout and heuristics given by
+    /// `options`
+    pub fn build_from_pdf_with(
+        &mut self,
+        input_file: &str,
+        options: &ParseOptions,
+    ) -> Result<ParseReport, KimiError> {
+        self.build_from_source(&mut PdfSource::open(input_file)?, options)
+    }
+}
+
+fn document_error(e: impl std::fmt::Display) -> KimiError {
+    KimiError::Document(e.to_string())
+}
+
+#[derive(Default)]
+struct GlyphCollector {
+    /// Flips PDF coordinates, which grow upwards, to grow downwards
+    flip: Option<Transform>,
+    pages: Vec<Vec<Glyph>>,
+}
+
+impl OutputDev for GlyphCollector {
+    fn begin_page(
+        &mut self,
+        _page_num: u32,
+        media_box: &MediaBox,
+        _art_box: Option<(f64, f64, f64, f64)>,
+    ) -> Result<(), OutputError> {
+        self.flip = Some(Transform::row_major(
+            1.,
+            0.,
+            0.,
+            -1.,
+            0.,
+            media_box.ury - media_box.lly,
+        ));
+        self.pages.push(Vec::new());
+        Ok(())
+    }
+
+    fn end_page(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn output_character(
+        &mut self,
+        trm: &Transform,
+        width: f64,
+        _spacing: f64,
+        font_size: f64,
+        text: &str,
+    ) -> Result<(), OutputError> {
+        let position = self.flip.map_or(*trm, |flip| trm.post_transform(&flip));
+        // side of the square with the area of the transformed em box
+        let size = (font_size * (trm.m11 + trm.m21) * font_size * (trm.m12 + trm.m22))
+            .abs()
+            .sqrt();
+        if let Some(page) = self.pages.last_mut()
+            && !text.trim().is_empty()
+        {
+            // mirrored text advances to the left
+            let end = position.m31 + width * size;
+            page.push(Glyph {
+                x: position.m31.min(end),
+                end: position.m31.max(end),
+                y: position.m32,
+                size,
+                text: text.to_string(),
+                confidence: None,
+            });
+        }
+        Ok(())
+    }
+
+    fn begin_word(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn end_word(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn end_line(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pdf_extract::content::{Content, Operation};
+    use pdf_extract::{Dictionary, Object, Stream};
+
+    /// A one page PDF placing each (x, y, text) in 10pt Courier
+    fn pdf(texts: &[(i64, i64, &str)]) -> Vec<u8> {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let mut font = Dictionary::new();
+        font.set("Type", "Font");
+        font.set("Subtype", "Type1");
+        font.set("BaseFont", "Courier");
+        let font_id = doc.add_object(font);
+        let mut fonts = Dictionary::new();
+        fonts.set("F1", font_id);
+        let mut resources = Dictionary::new();
+        resources.set("Font", fonts);
+
+        let mut operations = Vec::new();
+        for &(x, y, text) in texts {
+            operations.extend([
+                Operation::new("BT", vec![]),
+                Operation::new("Tf", vec!["F1".into(), 10.into()]),
+                Operation::new("Td", vec![x.into(), y.into()]),
+                Operation::new("Tj", vec![Object::string_literal(text)]),
+                Operation::new("ET", vec![]),
+            ]);
+        }
+        let content = Content { operations }.encode().unwrap();
+        let content_id = doc.add_object(Stream::new(Dictionary::new(), content));
+        let mut page = Dictionary::new();
+        page.set("Type", "Page");
+        page.set("Parent", pages_id);
+        page.set("Contents", content_id);
+        page.set("Resources", resources);
+        let page_id = doc.add_object(page);
+        let mut pages = Dictionary::new();
+        pages.set("Type", "Pages");
+        pages.set("Kids", vec![page_id.into()]);
+        pages.set("Count", 1);
+        pages.set("MediaBox", vec![0.into(), 0.into(), 595.into(), 842.into()]);
+        doc.objects.insert(pages_id, Object::Dictionary(pages));
+        let mut catalog = Dictionary::new();
+        catalog.set("Type", "Catalog");
+        catalog.set("Pages", pages_id);
+        let catalog_id = doc.add_object(catalog);
+        doc.trailer.set("Root", catalog_id);
+        let mut bytes = Vec::new();
+        doc.save_to(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_pdf_columns() {
+        let ids = (1..=20)
+            .map(|i| format!("2-04-02-{:02}", i))
+            .collect::<Vec<_>>();
+        // a banner crossing the gutter between the two columns
+        let mut texts = vec![(120, 800, "Occupation Classification")];
+        for (i, id) in (0..).zip(&ids) {
+            texts.push((50, 760 - 12 * i, id.as_str()));
+            texts.push((200, 760 - 12 * i, "Ship pilot L"));
+        }
+        texts.push((200, 760 - 12 * 20, "engineer"));
+
+        let sheet = PdfSource::from_bytes(&pdf(&texts))
+            .unwrap()
+            .read_sheet(0)
+            .unwrap();
+        assert_eq!(sheet.highest_column(), 2);
+        assert_eq!(sheet.text(1, 1).lines().collect::<Vec<_>>(), ids);
+        let second = sheet.text(2, 1).lines().collect::<Vec<_>>();
+        assert_eq!(second[0], "Occupation Classification");
+        assert_eq!(second[1], "Ship pilot L");
+        assert_eq!(second[21], "engineer");
+    }
+
+    #[test]
+    fn test_mirrored_glyphs() {
+        let mut glyphs = GlyphCollector::default();
+        let media_box = MediaBox {
+            llx: 0.,
+            lly: 0.,
+            urx: 595.,
+            ury: 842.,
+        };
+        glyphs.begin_page(1, &media_box, None).unwrap();
+        for (i, c) in "2-04".chars().enumerate() {
+            let x = 100. - 6. * i as f64;
+            let mirrored = Transform::row_major(-1., 0., 0., 1., x, 700.);
+            glyphs
+                .output_character(&mirrored, 0.6, 0., 10., &c.to_string())
+                .unwrap();
+        }
+        // no advance at all
+        let placed = Transform::row_major(1., 0., 0., 1., 300., 700.);
+        glyphs.output_character(&placed, 0., 0., 10., "L").unwrap();
+
+        let page = &glyphs.pages[0];
+        assert!(page.iter().all(|glyph| glyph.x <= glyph.end));
+        let columns = page_columns(page);
+        assert_eq!(columns.len(), 1);
+        assert!(columns[0].0.contains('L'));
+    }
+}
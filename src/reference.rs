@@ -0,0 +1,394 @@
+//! Reference taxonomy
+//!
+//! The 大类 and 中类 of the 2022 edition with their codes and names, as
+//! printed in the 大典, so a parse can be checked against them: a name the
+//! OCR garbled, a code with digits lost or a 中类 missing altogether. The
+//! 小类 and 职业 are too many to embed; `CategoryTree::verify` counts them.
+//!
+//! With `ParseOptions::correct_names` a name within a couple of characters
+//! of the published one, such as 负贵人 for 负责人, is replaced by it as the
+//! cell is parsed, and the replacement is listed in the `ParseReport`.
+
+use crate::{Category, CategoryTree, Correction, Level, compare_ids};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::LazyLock;
+
+/// A category as published
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ReferenceCategory {
+    pub id: &'static str,
+    pub code: &'static str,
+    pub desc: &'static str,
+}
+
+/// The 8 大类 and 79 中类 of the 2022 edition, in id order
+pub fn reference_categories() -> impl Iterator<Item = ReferenceCategory> {
+    REFERENCE_2022
+        .iter()
+        .map(|&(id, code, desc)| ReferenceCategory { id, code, desc })
+}
+
+const REFERENCE_2022: [(&str, &str, &str); 87] = [
+    (
+        "1",
+        "GBM10000",
+        "党的机关、国家机关、群众团体和社会组织、企事业单位负责人",
+    ),
+    ("1-01", "GBM10100", "中国共产党机关和基层组织负责人"),
+    ("1-02", "GBM10200", "国家机关负责人"),
+    ("1-03", "GBM10300", "民主党派和工商联负责人"),
+    (
+        "1-04",
+        "GBM10400",
+        "人民团体和群众团体、社会组织及其他成员组织负责人",
+    ),
+    ("1-05", "GBM10500", "基层群众性自治组织负责人"),
+    ("1-06", "GBM10600", "企事业单位负责人"),
+    ("2", "GBM20000", "专业技术人员"),
+    ("2-01", "GBM20100", "科学研究人员"),
+    ("2-02", "GBM20200", "工程技术人员"),
+    ("2-03", "GBM20300", "农业技术人员"),
+    ("2-04", "GBM20400", "飞机和船舶技术人员"),
+    ("2-05", "GBM20500", "卫生专业技术人员"),
+    ("2-06", "GBM20600", "经济和金融专业人员"),
+    ("2-07", "GBM20700", "监察、法律、社会和宗教专业人员"),
+    ("2-08", "GBM20800", "教学人员"),
+    ("2-09", "GBM20900", "文学艺术、体育专业人员"),
+    ("2-10", "GBM21000", "新闻出版、文化专业人员"),
+    ("2-99", "GBM29900", "其他专业技术人员"),
+    ("3", "GBM30000", "办事人员和有关人员"),
+    ("3-01", "GBM30100", "行政办事及辅助人员"),
+    ("3-02", "GBM30200", "安全和消防及辅助人员"),
+    ("3-03", "GBM30300", "仲裁、调解及相关法律事务辅助人员"),
+    ("3-99", "GBM39900", "其他办事人员和有关人员"),
+    ("4", "GBM40000", "社会生产服务和生活服务人员"),
+    ("4-01", "GBM40100", "批发与零售服务人员"),
+    ("4-02", "GBM40200", "交通运输、仓储物流和邮政业服务人员"),
+    ("4-03", "GBM40300", "住宿和餐饮服务人员"),
+    ("4-04", "GBM40400", "信息传输、软件和信息技术服务人员"),
+    ("4-05", "GBM40500", "金融服务人员"),
+    ("4-06", "GBM40600", "房地产服务人员"),
+    ("4-07", "GBM40700", "租赁和商务服务人员"),
+    ("4-08", "GBM40800", "技术辅助服务人员"),
+    ("4-09", "GBM40900", "水利、环境和公共设施管理服务人员"),
+    ("4-10", "GBM41000", "居民服务人员"),
+    ("4-11", "GBM41100", "电力、燃气及水供应服务人员"),
+    ("4-12", "GBM41200", "修理及制作服务人员"),
+    ("4-13", "GBM41300", "文化和教育服务人员"),
+    ("4-14", "GBM41400", "健康、体育和休闲服务人员"),
+    ("4-99", "GBM49900", "其他社会生产服务和生活服务人员"),
+    ("5", "GBM50000", "农、林、牧、渔业生产及辅助人员"),
+    ("5-01", "GBM50100", "农业生产人员"),
+    ("5-02", "GBM50200", "林业生产人员"),
+    ("5-03", "GBM50300", "畜牧业生产人员"),
+    ("5-04", "GBM50400", "渔业生产人员"),
+    ("5-05", "GBM50500", "农、林、牧、渔业生产辅助人员"),
+    ("5-99", "GBM59900", "其他农、林、牧、渔业生产及辅助人员"),
+    ("6", "GBM60000", "生产制造及有关人员"),
+    ("6-01", "GBM60100", "农副产品加工人员"),
+    ("6-02", "GBM60200", "食品、饮料生产加工人员"),
+    ("6-03", "GBM60300", "烟草及其制品加工人员"),
+    ("6-04", "GBM60400", "纺织、针织、印染人员"),
+    (
+        "6-05",
+        "GBM60500",
+        "纺织品、服装和皮革、毛皮制品加工制作人员",
+    ),
+    ("6-06", "GBM60600", "木材加工、家具与木制品制作人员"),
+    ("6-07", "GBM60700", "纸及纸制品生产加工人员"),
+    ("6-08", "GBM60800", "印刷和记录媒介复制人员"),
+    ("6-09", "GBM60900", "文教、工美、体育和娱乐用品制造人员"),
+    ("6-10", "GBM61000", "石油加工和炼焦、煤化工生产人员"),
+    ("6-11", "GBM61100", "化学原料和化学制品制造人员"),
+    ("6-12", "GBM61200", "医药制造人员"),
+    ("6-13", "GBM61300", "化学纤维制造人员"),
+    ("6-14", "GBM61400", "橡胶和塑料制品制造人员"),
+    ("6-15", "GBM61500", "非金属矿物制品制造人员"),
+    ("6-16", "GBM61600", "采矿人员"),
+    ("6-17", "GBM61700", "金属冶炼和压延加工人员"),
+    ("6-18", "GBM61800", "机械制造基础加工人员"),
+    ("6-19", "GBM61900", "金属制品制造人员"),
+    ("6-20", "GBM62000", "通用设备制造人员"),
+    ("6-21", "GBM62100", "专用设备制造人员"),
+    ("6-22", "GBM62200", "汽车制造人员"),
+    ("6-23", "GBM62300", "铁路、船舶、航空设备制造人员"),
+    ("6-24", "GBM62400", "电气机械和器材制造人员"),
+    ("6-25", "GBM62500", "计算机、通信和其他电子设备制造人员"),
+    ("6-26", "GBM62600", "仪器仪表制造人员"),
+    ("6-27", "GBM62700", "再生资源综合利用人员"),
+    ("6-28", "GBM62800", "电力、热力、气体、水生产和输配人员"),
+    ("6-29", "GBM62900", "建筑施工人员"),
+    (
+        "6-30",
+        "GBM63000",
+        "运输设备和通用工程机械操作人员及有关人员",
+    ),
+    ("6-31", "GBM63100", "生产辅助人员"),
+    ("6-99", "GBM69900", "其他生产制造及有关人员"),
+    ("7", "GBM70000", "军队人员"),
+    ("7-01", "GBM70100", "军官"),
+    ("7-02", "GBM70200", "军士(警士)"),
+    ("7-03", "GBM70300", "义务兵"),
+    ("7-04", "GBM70400", "文职人员"),
+    ("8", "GBM80000", "不便分类的其他从业人员"),
+    ("8-00", "GBM80000", "不便分类的其他从业人员"),
+];
+
+/// A 大类 or 中类 that differs from the reference
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Mismatch {
+    /// Not in the tree
+    Missing { id: String, desc: String },
+    /// Read with another code, or none
+    CodeDiffers {
+        id: String,
+        expected: String,
+        found: Option<String>,
+    },
+    /// Read with another name, or none
+    NameDiffers {
+        id: String,
+        expected: String,
+        found: Option<String>,
+    },
+    /// A 大类 or 中类 id the edition does not have
+    Unexpected { id: String },
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mismatch::Missing { id, desc } => write!(f, "{}: missing, expected {}", id, desc),
+            Mismatch::CodeDiffers {
+                id,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{}: code {}, expected {}",
+                id,
+                found.as_deref().unwrap_or("none"),
+                expected
+            ),
+            Mismatch::NameDiffers {
+                id,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{}: name {:?}, expected {:?}",
+                id,
+                found.as_deref().unwrap_or_default(),
+                expected
+            ),
+            Mismatch::Unexpected { id } => write!(f, "{}: not in the reference", id),
+        }
+    }
+}
+
+/// "第一大类" and the like, heading the name of a 大类
+static MAJOR_PREFIX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^第\S{1,2}大类").unwrap());
+
+/// `desc` as compared with the reference: full-width letters, digits and
+/// punctuation as ASCII, without whitespace or the "第一大类" of a 大类
+fn comparable(desc: &str, level: Level) -> String {
+    let folded = desc
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| match c {
+            '\u{ff01}'..='\u{ff5e}' => char::from_u32(c as u32 - 0xfee0).unwrap_or(c),
+            _ => c,
+        })
+        .collect::<String>();
+    match level {
+        Level::Major => MAJOR_PREFIX.replace(&folded, "").into_owned(),
+        _ => folded,
+    }
+}
+
+/// Published name of the 大类 or 中类 `id`
+fn reference_desc(id: &str) -> Option<&'static str> {
+    REFERENCE_2022
+        .iter()
+        .find(|(reference, _, _)| *reference == id)
+        .map(|(_, _, desc)| *desc)
+}
+
+/// Number of characters to insert, delete or replace to turn `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let replace = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = replace.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Replace the name of `cat` by the published one when it differs by at
+/// most one character in four, and two in all; names further off are
+/// likely another category read under a wrong id and are kept
+pub(crate) fn correct_desc(cat: &mut Category) -> Option<Correction> {
+    let level = cat.level()?;
+    let expected = reference_desc(&cat.id)?;
+    let desc = cat.desc.as_deref()?;
+    let (found, wanted) = (comparable(desc, level), comparable(expected, level));
+    let distance = edit_distance(&found, &wanted);
+    if distance == 0 || distance > 2 || distance * 4 > wanted.chars().count() {
+        return None;
+    }
+    let from = cat.desc.replace(expected.to_string())?;
+    Some(Correction {
+        id: cat.id.clone(),
+        from,
+        to: expected.to_string(),
+        source: cat.source.clone(),
+    })
+}
+
+impl CategoryTree {
+    /// Compare the 大类 and 中类 of the tree with the embedded reference of
+    /// the 2022 edition, in reference order, then the ids it does not have;
+    /// the first category read for an id is the one compared
+    pub fn cross_check(&self) -> Vec<Mismatch> {
+        let mut read = HashMap::new();
+        for (_, cat) in self {
+            if matches!(cat.level(), Some(Level::Major | Level::Middle)) {
+                read.entry(cat.id.as_str()).or_insert(cat);
+            }
+        }
+        let mut mismatches = Vec::new();
+        for reference in reference_categories() {
+            let Some(cat) = read.remove(reference.id) else {
+                mismatches.push(Mismatch::Missing {
+                    id: reference.id.to_string(),
+                    desc: reference.desc.to_string(),
+                });
+                continue;
+            };
+            if cat.code.as_deref() != Some(reference.code) {
+                mismatches.push(Mismatch::CodeDiffers {
+                    id: reference.id.to_string(),
+                    expected: reference.code.to_string(),
+                    found: cat.code.clone(),
+                });
+            }
+            let level = cat.level().unwrap_or(Level::Middle);
+            if cat.desc.as_deref().map(|desc| comparable(desc, level))
+                != Some(comparable(reference.desc, level))
+            {
+                mismatches.push(Mismatch::NameDiffers {
+                    id: reference.id.to_string(),
+                    expected: reference.desc.to_string(),
+                    found: cat.desc.clone(),
+                });
+            }
+        }
+        let mut unexpected = read.into_keys().collect::<Vec<_>>();
+        unexpected.sort_by(|a, b| compare_ids(a, b));
+        mismatches.extend(
+            unexpected
+                .into_iter()
+                .map(|id| Mismatch::Unexpected { id: id.to_string() }),
+        );
+        mismatches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_correct_names() {
+        let options = crate::ParseOptions {
+            layout: Some(crate::LayoutProfile::default()),
+            correct_names: true,
+            ..crate::ParseOptions::default()
+        };
+        let row = crate::RowCells {
+            sheet: "Sheet1".to_string(),
+            row: 1,
+            cells: vec![crate::CellText::Column {
+                col: 1,
+                text: "1-02(GBM10200)国家机关负责人\n\
+                       6-16(GBM61600)有色金属冶炼人员\n\
+                       1-01(GBM10100)中国共产党机关和基层组织负贵人"
+                    .to_string(),
+                confidence: Default::default(),
+            }],
+        };
+        let mut report = crate::ParseReport::default();
+        let categories =
+            crate::row_categories(row, &options, &mut report, &mut Vec::new()).unwrap();
+        let corrections = report
+            .corrections
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            corrections,
+            [
+                "1-01: \"中国共产党机关和基层组织负贵人\" corrected to \"中国共产党机关和基层组织负责人\" at Sheet1!A1"
+            ]
+        );
+        let descs = categories
+            .iter()
+            .map(|cat| cat.desc.as_deref().unwrap())
+            .collect::<Vec<_>>();
+        // the garbled name comes last, as a line without a closing suffix
+        // takes in the next one; 6-16 is too far off to be the same name
+        assert_eq!(
+            descs,
+            [
+                "国家机关负责人",
+                "有色金属冶炼人员",
+                "中国共产党机关和基层组织负责人",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cross_check() {
+        let mut tree = CategoryTree::new();
+        for reference in reference_categories().filter(|r| r.id != "2-04") {
+            let desc = match reference.id {
+                "2" => "第二大类 专业技术人员",
+                "7-02" => "军士（警士）",
+                "6-16" => "釆矿人员",
+                _ => reference.desc,
+            };
+            let code = match reference.id {
+                "1" => "GBM10",
+                _ => reference.code,
+            };
+            tree.parse_one_column(&format!("{}({}){}", reference.id, code, desc))
+                .unwrap();
+        }
+        tree.parse_one_column("9-01(GBM90100)其他人员").unwrap();
+
+        let mismatches = tree
+            .cross_check()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            mismatches,
+            [
+                "1: code GBM10, expected GBM10000",
+                "2-04: missing, expected 飞机和船舶技术人员",
+                "6-16: name \"釆矿人员\", expected \"采矿人员\"",
+                "9-01: not in the reference",
+            ]
+        );
+    }
+}
@@ -0,0 +1,199 @@
+//! What a parse read and what it left out
+//!
+//! OCR garbage and banner text that the header patterns miss end up as
+//! chunks without a category id or with a malformed one, and a name lost to
+//! the scan leaves a category with an empty description.
+//! `ParseMode::Lenient` skips the former and keeps the latter;
+//! `ParseMode::Strict` fails with all of them, so a pipeline never drops an
+//! occupation silently. Either way the `ParseReport` returned by
+//! `build_from` lists them with the cells they came from.
+
+use crate::{CellRef, KimiError, MergeTrace, ParseMode, ParseOptions};
+use serde::Serialize;
+use std::fmt;
+
+/// Cells read by a parse, the chunks that made categories and those that
+/// did not
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ParseReport {
+    /// Every non-empty cell read, in order
+    pub cells: Vec<CellReport>,
+    /// Number of chunks that parsed as a category
+    pub matched: usize,
+    /// Chunks the category regex rejected or with a malformed id, and
+    /// categories without a name
+    pub rejected: Vec<RejectedChunk>,
+    /// Names replaced by the published ones, see `ParseOptions::correct_names`
+    pub corrections: Vec<Correction>,
+    /// How the lines of each cell were joined, with
+    /// `ParseOptions::trace_merges`
+    pub merges: Vec<MergeTrace>,
+}
+
+impl ParseReport {
+    /// Text of the chunks the category regex rejected, in order
+    pub fn unmatched(&self) -> impl Iterator<Item = &str> {
+        self.rejected
+            .iter()
+            .filter(|chunk| chunk.reason == RejectReason::Unmatched)
+            .map(|chunk| chunk.text.as_str())
+    }
+}
+
+/// A cell read by a parse; `col` is the first of a column pair
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CellReport {
+    pub sheet: String,
+    pub row: u32,
+    pub col: u32,
+    /// Number of categories parsed from the cell
+    pub categories: usize,
+}
+
+/// Why a chunk was rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RejectReason {
+    /// The chunk does not start with a category id
+    Unmatched,
+    /// The id at the start of the chunk has an empty segment, e.g. "2-04-"
+    MalformedId,
+    /// The category read from the chunk has no name
+    EmptyDesc,
+}
+
+/// A chunk of a cell that does not make a complete category
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RejectedChunk {
+    pub sheet: String,
+    pub row: u32,
+    pub col: u32,
+    /// The chunk, or the category id for `RejectReason::EmptyDesc`
+    pub text: String,
+    pub reason: RejectReason,
+}
+
+impl fmt::Display for RejectedChunk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} row {}, col {}: ", self.sheet, self.row, self.col)?;
+        match self.reason {
+            RejectReason::Unmatched => write!(f, "no category id in {:?}", self.text),
+            RejectReason::MalformedId => write!(f, "malformed category id in {:?}", self.text),
+            RejectReason::EmptyDesc => write!(f, "category {} has no name", self.text),
+        }
+    }
+}
+
+/// A category name replaced by the published one
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Correction {
+    pub id: String,
+    /// The name as read
+    pub from: String,
+    pub to: String,
+    pub source: Option<CellRef>,
+}
+
+impl fmt::Display for Correction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {:?} corrected to {:?}", self.id, self.from, self.to)?;
+        match &self.source {
+            Some(source) => write!(f, " at {}", source),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Fail with the rejected chunks of `report` in strict mode
+pub(crate) fn check_mode(options: &ParseOptions, report: &ParseReport) -> Result<(), KimiError> {
+    match options.mode {
+        ParseMode::Strict if !report.rejected.is_empty() => {
+            Err(KimiError::Rejected(report.rejected.clone()))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CategoryTree, CellText, RowCells};
+
+    #[test]
+    fn test_parse_mode() {
+        let rows = || {
+            [RowCells {
+                sheet: "Sheet1".to_string(),
+                row: 4,
+                cells: vec![CellText::Column {
+                    col: 2,
+                    text: "2-04(GBM20400)交通运输工程技术人员\n船舶 引航员\n2-04-02(GBM20402)"
+                        .to_string(),
+                    confidence: Default::default(),
+                }],
+            }]
+        };
+        let mut tree = CategoryTree::new();
+        tree.append_rows(rows()).unwrap();
+        assert_eq!(tree.iter().count(), 2);
+
+        let options = ParseOptions {
+            mode: ParseMode::Strict,
+            ..ParseOptions::default()
+        };
+        let Err(KimiError::Rejected(rejected)) =
+            CategoryTree::new().append_rows_with(rows(), &options)
+        else {
+            panic!("strict mode accepted the row");
+        };
+        let rejected = rejected.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(
+            rejected,
+            [
+                "Sheet1 row 4, col 2: no category id in \"船舶引航员\"",
+                "Sheet1 row 4, col 2: category 2-04-02 has no name",
+            ]
+        );
+        assert_eq!("strict".parse(), Ok(ParseMode::Strict));
+    }
+
+    #[test]
+    fn test_parse_report() {
+        let mut book = umya_spreadsheet::new_file();
+        let sheet = book.get_sheet_mut(&0).unwrap();
+        sheet
+            .get_cell_mut((1, 1))
+            .set_value("2-04(GBM20400)交通运输工程技术人员\n船舶 引航员");
+        sheet
+            .get_cell_mut((1, 2))
+            .set_value("2-04-02(GBM20402)船舶指挥和引航人员");
+        sheet
+            .get_cell_mut((1, 3))
+            .set_value("2-04-(GBM20400)交通运输工程技术人员");
+        let mut bytes = Vec::new();
+        umya_spreadsheet::writer::xlsx::write_writer(&book, &mut bytes).unwrap();
+
+        let options = ParseOptions {
+            layout: Some(crate::LayoutProfile::default()),
+            ..ParseOptions::default()
+        };
+        let mut tree = CategoryTree::new();
+        let report = tree
+            .build_from_reader(std::io::Cursor::new(bytes), &options)
+            .unwrap();
+        let cells = report
+            .cells
+            .iter()
+            .map(|cell| (cell.row, cell.col, cell.categories))
+            .collect::<Vec<_>>();
+        assert_eq!(cells, [(1, 1, 1), (2, 1, 1), (3, 1, 0)]);
+        assert_eq!(report.matched, 2);
+        assert_eq!(report.unmatched().collect::<Vec<_>>(), ["船舶引航员"]);
+        assert_eq!(
+            report.rejected[1].to_string(),
+            "Sheet1 row 3, col 1: malformed category id in \"2-04-(GBM20400)交通运输工程技术人员\""
+        );
+        let source = tree.get("2-04-02").unwrap().source.as_ref().unwrap();
+        assert_eq!(source.to_string(), "Sheet1!A2");
+    }
+}
@@ -0,0 +1,157 @@
+//! Inline rich-text cells
+//!
+//! umya-spreadsheet keeps only the last formatting run of an inline string
+//! (`<c t="inlineStr"><is><r>…</r><r>…</r></is></c>`), so a description
+//! split into bold and plain runs loses its head. These cells are read
+//! straight from the sheet XML instead, concatenating every run and
+//! dropping formatting and phonetic (`rPh`) runs. Rich text in the shared
+//! string table is handled by umya itself.
+
+use crate::KimiError;
+use crate::layout::parse_cell;
+use quick_xml::Reader;
+use quick_xml::events::Event;
+use std::collections::HashMap;
+use std::io::{Read, Seek};
+use umya_spreadsheet::XlsxError;
+use zip::ZipArchive;
+
+/// Text of the inline-string cells of one sheet, keyed by (column, row)
+pub(crate) type InlineCells = HashMap<(u32, u32), String>;
+
+/// Inline-string cells of every sheet, in workbook order
+pub(crate) fn read_inline_strings<R: Read + Seek>(
+    reader: R,
+) -> Result<Vec<InlineCells>, KimiError> {
+    let mut archive = ZipArchive::new(reader).map_err(XlsxError::from)?;
+    let workbook = read_entry(&mut archive, "xl/workbook.xml")?;
+    let rels = read_entry(&mut archive, "xl/_rels/workbook.xml.rels")?;
+    let targets = attributes(&rels, b"Relationship", b"Id", b"Target")?;
+
+    let mut sheets = Vec::new();
+    for (_, rel_id) in attributes(&workbook, b"sheet", b"name", b"r:id")? {
+        let Some((_, target)) = targets.iter().find(|(id, _)| *id == rel_id) else {
+            sheets.push(InlineCells::new());
+            continue;
+        };
+        let path = match target.strip_prefix('/') {
+            Some(absolute) => absolute.to_string(),
+            None => format!("xl/{}", target),
+        };
+        let xml = read_entry(&mut archive, &path)?;
+        sheets.push(if xml.contains("inlineStr") {
+            inline_cells(&xml)?
+        } else {
+            InlineCells::new()
+        });
+    }
+    Ok(sheets)
+}
+
+fn read_entry<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    name: &str,
+) -> Result<String, KimiError> {
+    let mut text = String::new();
+    archive
+        .by_name(name)
+        .map_err(XlsxError::from)?
+        .read_to_string(&mut text)?;
+    Ok(text)
+}
+
+/// The (first, second) attribute pairs of every `element` in `xml`
+fn attributes(
+    xml: &str,
+    element: &[u8],
+    first: &[u8],
+    second: &[u8],
+) -> Result<Vec<(String, String)>, KimiError> {
+    let mut reader = Reader::from_str(xml);
+    let mut pairs = Vec::new();
+    loop {
+        match reader.read_event().map_err(XlsxError::from)? {
+            Event::Start(e) | Event::Empty(e) if e.name().as_ref() == element => {
+                let value = |key| -> Result<String, KimiError> {
+                    Ok(
+                        match e
+                            .try_get_attribute(key)
+                            .map_err(quick_xml::Error::from)
+                            .map_err(XlsxError::from)?
+                        {
+                            Some(attr) => {
+                                attr.unescape_value().map_err(XlsxError::from)?.into_owned()
+                            }
+                            None => String::new(),
+                        },
+                    )
+                };
+                pairs.push((value(first)?, value(second)?));
+            }
+            Event::Eof => return Ok(pairs),
+            _ => {}
+        }
+    }
+}
+
+fn inline_cells(xml: &str) -> Result<InlineCells, KimiError> {
+    let mut reader = Reader::from_str(xml);
+    let mut cells = InlineCells::new();
+    // reference of the current inline-string cell and its text so far
+    let mut cell: Option<((u32, u32), String)> = None;
+    let mut in_text = false;
+    let mut in_phonetic = false;
+    loop {
+        match reader.read_event().map_err(XlsxError::from)? {
+            Event::Start(e) => match e.name().as_ref() {
+                b"c" => {
+                    let attr = |key: &[u8]| {
+                        e.try_get_attribute(key)
+                            .ok()
+                            .flatten()
+                            .and_then(|attr| attr.unescape_value().ok().map(|v| v.into_owned()))
+                    };
+                    cell = (attr(b"t").as_deref() == Some("inlineStr"))
+                        .then(|| attr(b"r").as_deref().and_then(parse_cell))
+                        .flatten()
+                        .map(|at| (at, String::new()));
+                }
+                b"t" => in_text = true,
+                b"rPh" => in_phonetic = true,
+                _ => {}
+            },
+            Event::Text(e) if in_text && !in_phonetic => {
+                if let Some((_, text)) = &mut cell {
+                    text.push_str(&e.unescape().map_err(XlsxError::from)?);
+                }
+            }
+            Event::End(e) => match e.name().as_ref() {
+                b"c" => {
+                    if let Some((at, text)) = cell.take() {
+                        cells.insert(at, text);
+                    }
+                }
+                b"t" => in_text = false,
+                b"rPh" => in_phonetic = false,
+                _ => {}
+            },
+            Event::Eof => return Ok(cells),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inline_cells() {
+        let cells = inline_cells(
+            r#"<sheetData><row r="1"><c r="C1" t="inlineStr"><is><r><rPr><b/></rPr><t>2-01(GBM20100)</t></r><r><t xml:space="preserve">科学 </t></r><r><t>研究人员</t></r><rPh sb="0" eb="1"><t>カガク</t></rPh></is></c><c r="D1" t="s"><v>0</v></c></row></sheetData>"#,
+        )
+        .unwrap();
+        assert_eq!(cells.len(), 1);
+        assert_eq!(cells[&(3, 1)], "2-01(GBM20100)科学 研究人员");
+    }
+}
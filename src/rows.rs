@@ -0,0 +1,622 @@
+//! Row-by-row access to the category text of a workbook
+//!
+//! `parse_rows` hands each row to a callback instead of building a tree, for
+//! pipelines that consume the cells themselves. Only the current sheet is
+//! held in memory; no `CategoryTree` is built.
+
+use crate::layout::MergedCells;
+use crate::source::open_source;
+use crate::{
+    Confidence, JoinRule, KimiError, LayoutProfile, ParseOptions, Sheet, SheetSelection, Span,
+    SpreadsheetSource, strip_headers,
+};
+use regex::{Regex, RegexSet};
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::sync::Arc;
+use unicode_normalization::UnicodeNormalization;
+
+/// Category text read from one row of a worksheet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowCells {
+    /// Name of the worksheet
+    pub sheet: String,
+    /// 1-based row number
+    pub row: u32,
+    /// Non-empty layout columns of the row, left to right
+    pub cells: Vec<CellText>,
+}
+
+/// Text of one layout column, with headers dropped and merged cells resolved,
+/// and the OCR confidence of its characters, empty if not read from OCR
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CellText {
+    /// A column holding one category per line
+    Column {
+        col: u32,
+        text: String,
+        confidence: Confidence,
+    },
+    /// A column group whose texts are joined by `join`, one text per
+    /// column; a group merged over several rows is read whole at its top row
+    Group {
+        col: u32,
+        join: JoinRule,
+        texts: Vec<String>,
+        confidence: Vec<Confidence>,
+    },
+}
+
+/// How far a parse has got through the selected sheets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// 0-based position of the sheet being read among the selected ones,
+    /// counted per workbook in a zip
+    pub sheet: usize,
+    pub sheets: usize,
+    /// Rows of the sheet processed so far, out of `rows`
+    pub row: u32,
+    pub rows: u32,
+}
+
+/// Called with the `Progress` of a parse after each row read, e.g. to
+/// drive a progress bar; options compare equal whatever their callbacks
+#[derive(Clone, Default)]
+pub struct OnProgress(Option<Arc<dyn Fn(Progress) + Send + Sync>>);
+
+impl OnProgress {
+    pub fn new(f: impl Fn(Progress) + Send + Sync + 'static) -> Self {
+        OnProgress(Some(Arc::new(f)))
+    }
+
+    pub(crate) fn report(&self, progress: Progress) {
+        if let Some(f) = &self.0 {
+            f(progress);
+        }
+    }
+}
+
+impl fmt::Debug for OnProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("OnProgress")
+            .field(&self.0.is_some())
+            .finish()
+    }
+}
+
+impl PartialEq for OnProgress {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for OnProgress {}
+
+impl CellText {
+    /// The text and confidence in Unicode NFKC
+    pub(crate) fn nfkc(self) -> CellText {
+        match self {
+            CellText::Column {
+                col,
+                text,
+                confidence,
+            } => CellText::Column {
+                col,
+                text: text.nfkc().collect(),
+                confidence: confidence.nfkc(),
+            },
+            CellText::Group {
+                col,
+                join,
+                texts,
+                confidence,
+            } => CellText::Group {
+                col,
+                join,
+                texts: texts.iter().map(|text| text.nfkc().collect()).collect(),
+                confidence: confidence.iter().map(Confidence::nfkc).collect(),
+            },
+        }
+    }
+
+    /// 1-based column the text was read from, the first of a group
+    pub fn col(&self) -> u32 {
+        match self {
+            CellText::Column { col, .. } | CellText::Group { col, .. } => *col,
+        }
+    }
+}
+
+/// Stream the rows of the first sheet of an xlsx file to `f`
+///
+/// ```no_run
+/// let mut rows = 0;
+/// kimi::parse_rows("dict.xlsx", |row| {
+///     rows += 1;
+///     println!("{}: {} cells", row.row, row.cells.len());
+///     Ok(())
+/// })?;
+/// # Ok::<(), kimi::KimiError>(())
+/// ```
+pub fn parse_rows<F>(input_file: &str, f: F) -> Result<(), KimiError>
+where
+    F: FnMut(RowCells) -> Result<(), KimiError>,
+{
+    parse_rows_with(input_file, &ParseOptions::default(), f)
+}
+
+/// Like `parse_rows`, reading the sheets, rows and columns given by
+/// `options`
+pub fn parse_rows_with<F>(input_file: &str, options: &ParseOptions, f: F) -> Result<(), KimiError>
+where
+    F: FnMut(RowCells) -> Result<(), KimiError>,
+{
+    parse_rows_from(
+        &mut *open_source(Path::new(input_file), options.backend)?,
+        options,
+        f,
+    )
+}
+
+/// Like `parse_rows_with`, reading the sheets of `source`; the first error
+/// returned by `f` stops the parse
+pub fn parse_rows_from<F>(
+    source: &mut dyn SpreadsheetSource,
+    options: &ParseOptions,
+    mut f: F,
+) -> Result<(), KimiError>
+where
+    F: FnMut(RowCells) -> Result<(), KimiError>,
+{
+    let names = source.sheet_names();
+    let indices = match &options.sheet {
+        SheetSelection::Index(index) if *index < names.len() => vec![*index],
+        SheetSelection::Index(index) => return Err(KimiError::SheetNotFound(*index)),
+        SheetSelection::Name(name) => vec![
+            names
+                .iter()
+                .position(|sheet| sheet == name)
+                .ok_or_else(|| KimiError::SheetNameNotFound(name.clone()))?,
+        ],
+        SheetSelection::All => (0..names.len()).collect(),
+    };
+    let sheets = indices.len();
+    for (position, index) in indices.into_iter().enumerate() {
+        let sheet = source.read_sheet(index)?;
+        sheet_rows(&sheet, options, &mut f, &mut |row, rows| {
+            options.progress.report(Progress {
+                sheet: position,
+                sheets,
+                row,
+                rows,
+            })
+        })?;
+    }
+    Ok(())
+}
+
+fn sheet_rows<F>(
+    sheet: &Sheet,
+    options: &ParseOptions,
+    f: &mut F,
+    progress: &mut dyn FnMut(u32, u32),
+) -> Result<(), KimiError>
+where
+    F: FnMut(RowCells) -> Result<(), KimiError>,
+{
+    let detected;
+    let layout = match &options.layout {
+        Some(layout) => layout,
+        None => {
+            detected = LayoutProfile::detect_with(sheet, &options.id_format)?.unwrap_or_default();
+            &detected
+        }
+    };
+    let headers = if options.skip_headers {
+        RegexSet::new(&options.header_patterns)?
+    } else {
+        RegexSet::empty()
+    };
+    // compiled again for each cell, so fail on a bad pattern here
+    options.id_format.chunk_regex()?;
+    let leading_id = options.id_format.leading_regex()?;
+    let merged = sheet.merged();
+    let text_at = |col: u32, row: u32| strip_headers(sheet.text(col, row), &headers);
+
+    let rows = Span::clamp(options.rows.as_ref(), sheet.highest_row());
+    let total = rows.clone().count() as u32;
+    for (done, row) in (1..).zip(rows) {
+        let mut cells = Vec::new();
+        for col in Span::clamp(options.cols.as_ref(), sheet.highest_column()) {
+            if layout.is_ignored(col, row) {
+                if !sheet.text(col, row).trim().is_empty() {
+                    tracing::debug!(
+                        sheet = sheet.name(),
+                        row,
+                        col,
+                        "cell skipped: ignored by the layout"
+                    );
+                }
+                continue;
+            }
+            if layout.columns.contains(&col) {
+                let at = match merged.find(col, row) {
+                    // A region anchored left of the layout columns belongs
+                    // to the first of them it covers
+                    Some(range)
+                        if range.first.1 == row
+                            && !(range.first.0..col).any(|c| layout.reads(c)) =>
+                    {
+                        range.first.0
+                    }
+                    _ => col,
+                };
+                let text = text_at(at, row);
+                if !text.trim().is_empty() {
+                    cells.push(CellText::Column {
+                        col,
+                        text,
+                        confidence: sheet.confidence(at, row).clone(),
+                    });
+                }
+            } else if let Some(group) = layout.group_at(col) {
+                let (texts, confidence) = match sheet_group(merged, group.columns(), row) {
+                    GroupCells::Skip => {
+                        tracing::debug!(
+                            sheet = sheet.name(),
+                            row,
+                            col,
+                            "cell skipped: read with the merged region above it"
+                        );
+                        continue;
+                    }
+                    GroupCells::Spanning { col, row } => {
+                        let text = text_at(col, row);
+                        let mut texts = vec![String::new(); group.columns().len()];
+                        let mut confidence = vec![Confidence::default(); group.columns().len()];
+                        match group.join() {
+                            JoinRule::SideBySide => {
+                                let (first, second) = split_id_column(&text, &leading_id);
+                                [confidence[0], confidence[1]] =
+                                    sheet.confidence(col, row).split(&first);
+                                [texts[0], texts[1]] = [first, second];
+                            }
+                            JoinRule::Stacked => {
+                                confidence[0] = sheet.confidence(col, row).clone();
+                                texts[0] = text;
+                            }
+                        }
+                        (texts, confidence)
+                    }
+                    GroupCells::Rows(rows) => group
+                        .columns()
+                        .iter()
+                        .map(|&col| {
+                            let mut confidence = Confidence::default();
+                            let mut lines = Vec::new();
+                            for row in rows.clone() {
+                                let text = text_at(col, row);
+                                if !text.trim().is_empty() {
+                                    confidence.append(sheet.confidence(col, row));
+                                    lines.push(text);
+                                }
+                            }
+                            (lines.join("\n"), confidence)
+                        })
+                        .unzip(),
+                };
+                if texts.iter().any(|text| !text.trim().is_empty()) {
+                    cells.push(CellText::Group {
+                        col,
+                        join: group.join(),
+                        texts,
+                        confidence,
+                    });
+                }
+            }
+        }
+        if !cells.is_empty() {
+            f(RowCells {
+                sheet: sheet.name().to_string(),
+                row,
+                cells,
+            })?;
+        }
+        progress(done, total);
+    }
+    Ok(())
+}
+
+/// Split text laid out as an id column and a name column, as found in a
+/// cell merged across a column group, back into the two columns
+fn split_id_column(text: &str, leading_id: &Regex) -> (String, String) {
+    let (mut first, mut second) = (Vec::new(), Vec::new());
+    for line in text.lines() {
+        match leading_id.captures(line) {
+            Some(cap) => {
+                first.push(cap[1].to_string());
+                second.push(cap[2].to_string());
+            }
+            None => {
+                first.push(String::new());
+                second.push(line.trim().to_string());
+            }
+        }
+    }
+    (first.join("\n"), second.join("\n"))
+}
+
+/// What to read for a group of columns at one row
+enum GroupCells {
+    /// The row is covered by a merged region read at an earlier row
+    Skip,
+    /// One merged cell spans the first two columns and holds whole lines
+    Spanning { col: u32, row: u32 },
+    /// Read every column over these rows, which merged cells span
+    Rows(RangeInclusive<u32>),
+}
+
+fn sheet_group(merged: &MergedCells, columns: &[u32], row: u32) -> GroupCells {
+    let (first, second) = (columns[0], columns[1]);
+    if let Some(range) = merged
+        .find(first, row)
+        .filter(|range| range.contains(second, row))
+    {
+        return if range.first == (first, row) {
+            GroupCells::Spanning { col: first, row }
+        } else {
+            GroupCells::Skip
+        };
+    }
+    let (top, bottom) = columns
+        .iter()
+        .map(|&col| merged.rows(col, row))
+        .fold((row, row), |(top, bottom), (col_top, col_bottom)| {
+            (top.min(col_top), bottom.max(col_bottom))
+        });
+    if row != top {
+        return GroupCells::Skip;
+    }
+    GroupCells::Rows(top..=bottom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CategoryTree;
+    use std::sync::Mutex;
+
+    /// Sheets held in memory
+    struct Sheets(Vec<Sheet>);
+
+    impl SpreadsheetSource for Sheets {
+        fn sheet_names(&self) -> Vec<String> {
+            self.0
+                .iter()
+                .map(|sheet| sheet.name().to_string())
+                .collect()
+        }
+
+        fn read_sheet(&mut self, index: usize) -> Result<Sheet, KimiError> {
+            Ok(self.0[index].clone())
+        }
+    }
+
+    #[test]
+    fn test_window() {
+        let mut sheet = Sheet::new("Sheet1");
+        sheet.set_text(1, 3, "9-99(GBM99900)窗口左边的类别");
+        sheet.set_text(
+            3,
+            1,
+            "1(GBM10000)党的机关、国家机关、群众团体和社会组织、企事业单位负责人",
+        );
+        sheet.set_text(3, 2, "1-01(GBM10100)中国共产党机关负责人");
+        sheet.set_text(3, 7, "1-02(GBM10200)国家机关负责人");
+        sheet.set_text(5, 1, "1-01-00-03");
+        sheet.set_text(6, 1, "窗口上边的负责人");
+        sheet.add_merged("E1:E2".parse().unwrap());
+        sheet.set_text(5, 3, "1-01-00-01");
+        sheet.set_text(6, 3, "党委负责人");
+        sheet.set_text(5, 5, "1-01-00-02");
+        sheet.set_text(6, 5, "纪委");
+        sheet.set_text(6, 6, "负责人");
+        sheet.add_merged("E5:E6".parse().unwrap());
+        let options = ParseOptions {
+            rows: Some("2:5".parse().unwrap()),
+            cols: Some("C:F".parse().unwrap()),
+            ..ParseOptions::default()
+        };
+
+        let mut tree = CategoryTree::new();
+        tree.build_from_source(&mut Sheets(vec![sheet]), &options)
+            .unwrap();
+        // the layout is detected over the whole sheet, but column A, rows 1
+        // and 7 and the region merged down into the window are left out
+        let ids = tree
+            .iter()
+            .map(|(_, cat)| cat.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["1-01", "1-01-00-01", "1-01-00-02"]);
+        // a region merged out of the window is read whole
+        let cat = tree.get("1-01-00-02").unwrap();
+        assert_eq!(cat.desc.as_deref(), Some("纪委负责人"));
+        assert_eq!(cat.source.as_ref().unwrap().to_string(), "Sheet1!E5");
+    }
+
+    /// The messages and fields of the events `f` emits, one line each
+    fn events(f: impl FnOnce()) -> Vec<String> {
+        use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+
+        struct Events(Arc<Mutex<Vec<String>>>);
+
+        impl<S: tracing::Subscriber> Layer<S> for Events {
+            fn on_event(&self, event: &tracing::Event<'_>, _: Context<'_, S>) {
+                let mut line = String::new();
+                event.record(
+                    &mut |field: &tracing::field::Field, value: &dyn fmt::Debug| {
+                        if !line.is_empty() {
+                            line.push(' ');
+                        }
+                        match field.name() {
+                            "message" => line.push_str(&format!("{:?}", value)),
+                            name => line.push_str(&format!("{}={:?}", name, value)),
+                        }
+                    },
+                );
+                self.0.lock().unwrap().push(line);
+            }
+        }
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let subscriber = tracing_subscriber::registry().with(Events(events.clone()));
+        tracing::subscriber::with_default(subscriber, f);
+        events.lock().unwrap().clone()
+    }
+
+    #[test]
+    fn test_debug_events() {
+        let mut sheet = Sheet::new("Sheet1");
+        sheet.set_text(1, 1, "内部资料");
+        sheet.set_text(3, 2, "党的机关负责人");
+        sheet.set_text(5, 2, "1-01-00-01");
+        sheet.set_text(6, 2, "党委负责人");
+        sheet.set_text(6, 3, "纪委负责人");
+        sheet.add_merged("E2:E3".parse().unwrap());
+        let options = ParseOptions {
+            layout: Some(LayoutProfile {
+                ignore: vec!["A1:A1".parse().unwrap()],
+                ..LayoutProfile::default()
+            }),
+            ..ParseOptions::default()
+        };
+
+        let events = events(|| {
+            CategoryTree::new()
+                .build_from_source(&mut Sheets(vec![sheet]), &options)
+                .unwrap();
+        });
+        for event in [
+            "cell skipped: ignored by the layout sheet=\"Sheet1\" row=1 col=1",
+            "cell skipped: read with the merged region above it sheet=\"Sheet1\" row=3 col=5",
+            "no category id at the start of chunk chunk=\"党的机关负责人\"",
+        ] {
+            assert!(
+                events.iter().any(|e| e == event),
+                "{event} not in {events:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_progress() {
+        let mut first = Sheet::new("大类1");
+        first.set_text(
+            3,
+            1,
+            "1(GBM10000)党的机关、国家机关、群众团体和社会组织、企事业单位负责人",
+        );
+        first.set_text(3, 2, "1-01(GBM10100)中国共产党机关负责人");
+        let mut second = Sheet::new("大类2");
+        second.set_text(3, 1, "2(GBM20000)专业技术人员");
+        second.set_text(3, 3, "2-01(GBM20100)科学研究人员");
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let options = ParseOptions {
+            sheet: SheetSelection::All,
+            layout: Some(LayoutProfile::default()),
+            progress: OnProgress::new({
+                let seen = seen.clone();
+                move |progress| seen.lock().unwrap().push(progress)
+            }),
+            ..ParseOptions::default()
+        };
+
+        let mut tree = CategoryTree::new();
+        tree.build_from_source(&mut Sheets(vec![first, second]), &options)
+            .unwrap();
+        assert!(tree.get("1-01").is_some() && tree.get("2-01").is_some());
+        let at = |sheet, row, rows| Progress {
+            sheet,
+            sheets: 2,
+            row,
+            rows,
+        };
+        assert_eq!(
+            *seen.lock().unwrap(),
+            [
+                at(0, 1, 2),
+                at(0, 2, 2),
+                at(1, 1, 3),
+                at(1, 2, 3),
+                at(1, 3, 3)
+            ]
+        );
+        // the callback does not take part in comparing options
+        assert_eq!(
+            options,
+            ParseOptions {
+                progress: OnProgress::default(),
+                ..options.clone()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_rows() {
+        let mut sheet = Sheet::new("Sheet1");
+        sheet.set_text(1, 1, "中华人民共和国职业分类大典");
+        sheet.set_text(3, 2, "2-04-02(GBM20402)船舶指挥和引航人员");
+        sheet.set_text(5, 2, "2-04-02-01\n2-04-02-02");
+        sheet.set_text(6, 2, "船舶驾驶员");
+        sheet.set_text(6, 3, "船舶引航员 L");
+        sheet.add_merged("E2:E3".parse().unwrap());
+        let options = ParseOptions {
+            layout: Some(LayoutProfile::default()),
+            ..ParseOptions::default()
+        };
+
+        let mut rows = Vec::new();
+        let mut done = Vec::new();
+        sheet_rows(
+            &sheet,
+            &options,
+            &mut |row| {
+                rows.push(row);
+                Ok(())
+            },
+            &mut |row, rows| done.push((row, rows)),
+        )
+        .unwrap();
+        assert_eq!(done, [(1, 3), (2, 3), (3, 3)]);
+        assert_eq!(rows.len(), 1);
+        assert_eq!((rows[0].sheet.as_str(), rows[0].row), ("Sheet1", 2));
+        assert_eq!(
+            rows[0].cells,
+            [
+                CellText::Column {
+                    col: 3,
+                    text: "2-04-02(GBM20402)船舶指挥和引航人员".to_string(),
+                    confidence: Confidence::default(),
+                },
+                CellText::Group {
+                    col: 5,
+                    join: JoinRule::SideBySide,
+                    texts: vec![
+                        "2-04-02-01\n2-04-02-02".to_string(),
+                        "船舶驾驶员\n船舶引航员 L".to_string(),
+                    ],
+                    confidence: vec![Confidence::default(); 2],
+                },
+            ]
+        );
+
+        let err = sheet_rows(
+            &sheet,
+            &options,
+            &mut |_| Err(KimiError::IdNotFound("x".into())),
+            &mut |_, _| {},
+        );
+        assert!(matches!(err, Err(KimiError::IdNotFound(_))));
+    }
+}
@@ -0,0 +1,54 @@
+use crate::{Category, CategoryTree, KimiError};
+use regex::Regex;
+
+impl CategoryTree {
+    /// Categories whose description contains `needle`, in tree order
+    pub fn search(&self, needle: &str) -> Vec<&Category> {
+        self.search_by(|desc| desc.contains(needle))
+    }
+
+    /// Categories whose description matches the regular expression
+    /// `pattern`, in tree order
+    pub fn search_regex(&self, pattern: &str) -> Result<Vec<&Category>, KimiError> {
+        let re = Regex::new(pattern)?;
+        Ok(self.search_by(|desc| re.is_match(desc)))
+    }
+
+    /// Categories read from OCR output with a word confidence below
+    /// `threshold`, 0 to 100, in tree order, for manual review
+    pub fn low_confidence(&self, threshold: u8) -> Vec<&Category> {
+        self.iter()
+            .map(|(_, cat)| cat)
+            .filter(|cat| cat.confidence.is_some_and(|c| c < threshold))
+            .collect()
+    }
+
+    fn search_by<F: Fn(&str) -> bool>(&self, f: F) -> Vec<&Category> {
+        self.iter()
+            .map(|(_, cat)| cat)
+            .filter(|cat| cat.desc.as_deref().is_some_and(&f))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search() {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column(
+            "2-05(GBM20500)卫生专业技术人员\n2-05-08(GBM20508)护理人员\n2-05-08-01内科护士\n2-05-08-02儿科护士\n2-05-08-05社区护士",
+        )
+        .unwrap();
+        let ids = |cats: Vec<&Category>| cats.iter().map(|c| c.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(tree.search("护士")).len(), 3);
+        assert_eq!(ids(tree.search("护理")), vec!["2-05-08"]);
+        assert_eq!(
+            ids(tree.search_regex("^(内|儿)科").unwrap()),
+            vec!["2-05-08-01", "2-05-08-02"]
+        );
+        assert!(matches!(tree.search_regex("("), Err(KimiError::Regex(_))));
+    }
+}
@@ -0,0 +1,151 @@
+//! HTTP API over a parsed tree (cargo feature `serve`)
+//!
+//! - `GET /categories/{id}`: the category with its `ancestors`
+//! - `GET /search?q=护士` (add `&regex=true` for a regular expression):
+//!   matching categories
+//! - `GET /tree?prefix=2-02`: the tree, or the part under `prefix`, in the
+//!   v2 JSON layout
+//!
+//! Errors are JSON objects with an `error` message.
+
+use crate::{Category, CategoryTree, KimiError};
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+type Shared = State<Arc<CategoryTree>>;
+
+#[derive(Serialize)]
+struct CategoryDetail<'a> {
+    #[serde(flatten)]
+    category: &'a Category,
+    ancestors: Vec<&'a Category>,
+}
+
+#[derive(Deserialize)]
+struct SearchParams {
+    q: String,
+    #[serde(default)]
+    regex: bool,
+}
+
+#[derive(Deserialize)]
+struct TreeParams {
+    prefix: Option<String>,
+}
+
+/// Routes of the API, see the module docs
+pub fn router(tree: CategoryTree) -> Router {
+    Router::new()
+        .route("/categories/{id}", get(category))
+        .route("/search", get(search))
+        .route("/tree", get(subtree))
+        .with_state(Arc::new(tree))
+}
+
+/// Serve the API on `addr` until the process is stopped
+pub fn serve(tree: CategoryTree, addr: SocketAddr) -> Result<(), KimiError> {
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async {
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        axum::serve(listener, router(tree)).await?;
+        Ok(())
+    })
+}
+
+async fn category(State(tree): Shared, Path(id): Path<String>) -> Response {
+    match tree.get(&id) {
+        Some(category) => Json(CategoryDetail {
+            category,
+            ancestors: tree.ancestors(&id),
+        })
+        .into_response(),
+        None => error(StatusCode::NOT_FOUND, KimiError::IdNotFound(id)),
+    }
+}
+
+async fn search(State(tree): Shared, Query(params): Query<SearchParams>) -> Response {
+    let matches = if params.regex {
+        tree.search_regex(&params.q)
+    } else {
+        Ok(tree.search(&params.q))
+    };
+    match matches {
+        Ok(matches) => Json(matches).into_response(),
+        Err(e) => error(StatusCode::BAD_REQUEST, e),
+    }
+}
+
+async fn subtree(State(tree): Shared, Query(params): Query<TreeParams>) -> Response {
+    match params.prefix {
+        None => Json(tree.to_v2()).into_response(),
+        Some(prefix) => match tree.subtree(&prefix) {
+            Some(subtree) => Json(subtree.to_v2()).into_response(),
+            None => error(StatusCode::NOT_FOUND, KimiError::IdNotFound(prefix)),
+        },
+    }
+}
+
+fn error(status: StatusCode, e: KimiError) -> Response {
+    (status, Json(json!({ "error": e.to_string() }))).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::to_bytes;
+    use serde_json::Value;
+
+    fn state() -> Shared {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column(
+            "2-05(GBM20500)卫生专业技术人员\n2-05-08(GBM20508)护理人员\n2-05-08-01内科护士\n2-05-08-02儿科护士",
+        )
+        .unwrap();
+        State(Arc::new(tree))
+    }
+
+    async fn body(response: Response) -> (StatusCode, Value) {
+        let status = response.status();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_category() {
+        let (status, value) = body(category(state(), Path("2-05-08-01".to_string())).await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(value["desc"], "内科护士");
+        assert_eq!(value["ancestors"][1]["id"], "2-05-08");
+
+        let (status, value) = body(category(state(), Path("9".to_string())).await).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(value["error"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_search_and_tree() {
+        let params = SearchParams {
+            q: "^儿科".to_string(),
+            regex: true,
+        };
+        let (_, value) = body(search(state(), Query(params)).await).await;
+        assert_eq!(value.as_array().unwrap().len(), 1);
+
+        let params = TreeParams {
+            prefix: Some("2-05-08".to_string()),
+        };
+        let (status, value) = body(subtree(state(), Query(params)).await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            value["children"][0]["children"][0]["children"][0]["id"],
+            "2-05-08"
+        );
+    }
+}
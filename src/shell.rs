@@ -0,0 +1,243 @@
+//! Interactive shell over a parsed tree (cargo feature `shell`)
+
+use crate::{CategoryTree, KimiError};
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::history::DefaultHistory;
+use rustyline::{Context, Editor, Helper, Highlighter, Hinter, Validator};
+use std::io::{self, Write};
+
+const COMMANDS: [&str; 8] = ["ls", "cd", "pwd", "find", "show", "tree", "help", "exit"];
+
+const HELP: &str = "\
+ls [id]          list the nodes under the current node or id
+cd <id|..|/>     change the current node; ids may be relative, e.g. cd 02
+pwd              print the current node
+find <text>      search category names
+show <id|GBM…>   show a category with its ancestry
+tree [id]        print the subtree under the current node or id
+help             show this help
+exit             leave the shell (also Ctrl-D)";
+
+/// State of a shell session: the tree and the current node
+pub struct Shell<'a> {
+    tree: &'a CategoryTree,
+    cwd: String,
+}
+
+impl<'a> Shell<'a> {
+    pub fn new(tree: &'a CategoryTree) -> Self {
+        Shell {
+            tree,
+            cwd: String::new(),
+        }
+    }
+
+    /// Id of the current node, empty at the root
+    pub fn cwd(&self) -> &str {
+        &self.cwd
+    }
+
+    /// Run one command line, writing its output to `out`; returns false
+    /// once the user asked to leave
+    pub fn execute<W: Write>(&mut self, line: &str, out: &mut W) -> io::Result<bool> {
+        let mut words = line.split_whitespace();
+        let Some(command) = words.next() else {
+            return Ok(true);
+        };
+        let arg = words.collect::<Vec<_>>().join(" ");
+        match command {
+            "ls" => match self.resolve(&arg) {
+                Some(id) => self.ls(&id, out)?,
+                None => writeln!(out, "no such node: {}", arg)?,
+            },
+            "cd" => match self.resolve(&arg) {
+                Some(id) => self.cwd = id,
+                None => writeln!(out, "no such node: {}", arg)?,
+            },
+            "pwd" => writeln!(out, "/{}", self.cwd)?,
+            "find" if !arg.is_empty() => {
+                for cat in self.tree.search(&arg) {
+                    writeln!(out, "{}", cat)?;
+                }
+            }
+            "show" if !arg.is_empty() => self.show(&arg, out)?,
+            "tree" => match self.resolve(&arg) {
+                Some(id) if id.is_empty() => self.tree.pretty_print_to(&mut *out)?,
+                Some(id) => self.tree.subtree(&id).unwrap().pretty_print_to(&mut *out)?,
+                None => writeln!(out, "no such node: {}", arg)?,
+            },
+            "help" => writeln!(out, "{}", HELP)?,
+            "exit" | "quit" => return Ok(false),
+            _ => writeln!(out, "unknown command {:?}, try help", line.trim())?,
+        }
+        Ok(true)
+    }
+
+    /// Absolute id for `arg` relative to the current node
+    fn resolve(&self, arg: &str) -> Option<String> {
+        let id = match arg {
+            "" => self.cwd.clone(),
+            "/" => String::new(),
+            ".." => self
+                .cwd
+                .rsplit_once('-')
+                .map_or(String::new(), |(parent, _)| parent.to_string()),
+            _ if !self.cwd.is_empty()
+                && self
+                    .tree
+                    .get_node(&format!("{}-{}", self.cwd, arg))
+                    .is_some() =>
+            {
+                format!("{}-{}", self.cwd, arg)
+            }
+            _ => arg.trim_start_matches('/').to_string(),
+        };
+        (id.is_empty() || self.tree.get_node(&id).is_some()).then_some(id)
+    }
+
+    fn ls<W: Write>(&self, id: &str, out: &mut W) -> io::Result<()> {
+        let node = if id.is_empty() {
+            self.tree
+        } else {
+            self.tree.get_node(id).unwrap()
+        };
+        for (key, child) in node.children() {
+            match child.items().first() {
+                Some(cat) => writeln!(out, "{}", cat)?,
+                None if id.is_empty() => writeln!(out, "{}", key)?,
+                None => writeln!(out, "{}-{}", id, key)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn show<W: Write>(&self, arg: &str, out: &mut W) -> io::Result<()> {
+        let cat = if arg.to_uppercase().starts_with("GBM") {
+            self.tree.find_by_code(arg).map(|(_, cat)| cat)
+        } else {
+            self.resolve(arg).and_then(|id| self.tree.get(&id))
+        };
+        let Some(cat) = cat else {
+            return writeln!(out, "not found: {}", arg);
+        };
+        for ancestor in self.tree.ancestors(&cat.id) {
+            writeln!(out, "{}", ancestor)?;
+        }
+        writeln!(out, "{}", cat)
+    }
+}
+
+#[derive(Helper, Hinter, Highlighter, Validator)]
+struct IdCompleter {
+    ids: Vec<String>,
+}
+
+impl Completer for IdCompleter {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let start = line[..pos].rfind(' ').map_or(0, |i| i + 1);
+        let word = &line[start..pos];
+        let candidates = if start == 0 {
+            COMMANDS
+                .iter()
+                .filter(|c| c.starts_with(word))
+                .map(|c| c.to_string())
+                .collect()
+        } else {
+            self.ids
+                .iter()
+                .filter(|id| id.starts_with(word))
+                .cloned()
+                .collect()
+        };
+        Ok((start, candidates))
+    }
+}
+
+/// Run the interactive shell on the terminal until `exit` or Ctrl-D
+pub fn run_shell(tree: &CategoryTree) -> Result<(), KimiError> {
+    let mut ids = Vec::new();
+    collect_ids(tree, "", &mut ids);
+    let mut editor = Editor::<IdCompleter, DefaultHistory>::new().map_err(readline_error)?;
+    editor.set_helper(Some(IdCompleter { ids }));
+
+    let mut shell = Shell::new(tree);
+    let mut stdout = io::stdout();
+    loop {
+        let prompt = format!("kimi:/{}> ", shell.cwd());
+        match editor.readline(&prompt) {
+            Ok(line) => {
+                let _ = editor.add_history_entry(line.as_str());
+                if !shell.execute(&line, &mut stdout)? {
+                    break;
+                }
+            }
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(readline_error(e)),
+        }
+    }
+    Ok(())
+}
+
+fn collect_ids(tree: &CategoryTree, prefix: &str, ids: &mut Vec<String>) {
+    for (key, child) in tree.children() {
+        let id = if prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}-{}", prefix, key)
+        };
+        collect_ids(child, &id, ids);
+        ids.push(id);
+    }
+}
+
+fn readline_error(e: ReadlineError) -> KimiError {
+    match e {
+        ReadlineError::Io(e) => KimiError::Io(e),
+        e => KimiError::Io(io::Error::other(e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(shell: &mut Shell, line: &str) -> String {
+        let mut out = Vec::new();
+        shell.execute(line, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_shell() {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column(
+            "2-05(GBM20500)卫生专业技术人员\n2-05-08(GBM20508)护理人员\n2-05-08-01内科护士\n2-05-08-02儿科护士",
+        )
+        .unwrap();
+        let mut shell = Shell::new(&tree);
+        assert_eq!(run(&mut shell, "ls"), "2\n");
+        run(&mut shell, "cd 2-05");
+        assert_eq!(shell.cwd(), "2-05");
+        run(&mut shell, "cd 08");
+        assert_eq!(shell.cwd(), "2-05-08");
+        assert_eq!(run(&mut shell, "ls").lines().count(), 2);
+        run(&mut shell, "cd ..");
+        assert_eq!(run(&mut shell, "pwd"), "/2-05\n");
+        assert_eq!(run(&mut shell, "cd 9"), "no such node: 9\n");
+        assert_eq!(
+            run(&mut shell, "show GBM20508"),
+            "中类 2-05 卫生专业技术人员 [GBM20500]\n小类 2-05-08 护理人员 [GBM20508]\n"
+        );
+        assert_eq!(run(&mut shell, "find 儿科"), "细类 2-05-08-02 儿科护士\n");
+        assert!(!shell.execute("exit", &mut Vec::new()).unwrap());
+    }
+}
@@ -0,0 +1,625 @@
+//! Spreadsheet backends
+//!
+//! The parser only needs the text and merged regions of a sheet, so the
+//! workbook is read through `SpreadsheetSource` into a plain `Sheet`.
+//! umya-spreadsheet is always available; the `calamine` feature adds a
+//! read-only backend that skips styles and is much faster on large scans,
+//! and the `xls` feature reads the legacy binary format through calamine.
+//! OpenDocument spreadsheets are read with the `ods` feature.
+
+use crate::layout::MergedCells;
+use crate::rich_text::{InlineCells, read_inline_strings};
+use crate::{CellRange, KimiError};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{Read, Seek};
+use std::path::Path;
+use std::str::FromStr;
+use umya_spreadsheet::Spreadsheet;
+use umya_spreadsheet::reader::xlsx;
+use unicode_normalization::UnicodeNormalization;
+
+/// OCR confidence of the characters of a text, whitespace left out
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Confidence(Vec<(char, u8)>);
+
+impl Confidence {
+    /// Each character of `text` with the confidence, 0 to 100, of the OCR
+    /// word it belongs to
+    pub fn new(text: &str, confidence: u8) -> Self {
+        let mut chars = Confidence::default();
+        chars.push(text, confidence);
+        chars
+    }
+
+    pub fn push(&mut self, text: &str, confidence: u8) {
+        self.0.extend(
+            text.chars()
+                .filter(|c| !c.is_whitespace())
+                .map(|c| (c, confidence.min(100))),
+        );
+    }
+
+    pub fn append(&mut self, other: &Confidence) {
+        self.0.extend_from_slice(&other.0);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The characters in Unicode NFKC, each with the confidence of the one
+    /// it came from
+    pub(crate) fn nfkc(&self) -> Confidence {
+        Confidence(
+            self.0
+                .iter()
+                .flat_map(|&(c, confidence)| {
+                    std::iter::once(c)
+                        .nfkc()
+                        .filter(|c| !c.is_whitespace())
+                        .map(move |c| (c, confidence))
+                })
+                .collect(),
+        )
+    }
+
+    /// Split into the characters of `first`, found in order, and the rest
+    pub(crate) fn split(&self, first: &str) -> [Confidence; 2] {
+        let mut wanted = first.chars().filter(|c| !c.is_whitespace()).peekable();
+        let (mut matched, mut rest) = (Confidence::default(), Confidence::default());
+        for &(c, confidence) in &self.0 {
+            if wanted.next_if_eq(&c).is_some() {
+                matched.0.push((c, confidence));
+            } else {
+                rest.0.push((c, confidence));
+            }
+        }
+        [matched, rest]
+    }
+
+    /// Lowest confidence of the characters of `text`, looked up in order
+    /// from the `*from`th character, which is moved past them; `None` if
+    /// they are not all found
+    pub(crate) fn lowest(&self, text: &str, from: &mut usize) -> Option<u8> {
+        let mut at = *from;
+        let mut lowest = None;
+        for c in text.chars().filter(|c| !c.is_whitespace()) {
+            let found = at + self.0.get(at..)?.iter().position(|&(d, _)| d == c)?;
+            let confidence = self.0[found].1;
+            lowest = Some(lowest.map_or(confidence, |l: u8| l.min(confidence)));
+            at = found + 1;
+        }
+        *from = at;
+        lowest
+    }
+}
+
+/// Text and merged regions of one worksheet
+#[derive(Debug, Clone, Default)]
+pub struct Sheet {
+    name: String,
+    cells: HashMap<(u32, u32), String>,
+    confidence: HashMap<(u32, u32), Confidence>,
+    max_col: u32,
+    max_row: u32,
+    merged: MergedCells,
+}
+
+impl Sheet {
+    pub fn new(name: impl Into<String>) -> Self {
+        Sheet {
+            name: name.into(),
+            ..Sheet::default()
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Set the text of the 1-based cell, growing the sheet to include it
+    pub fn set_text(&mut self, col: u32, row: u32, text: impl Into<String>) {
+        self.max_col = self.max_col.max(col);
+        self.max_row = self.max_row.max(row);
+        let text = text.into();
+        if text.is_empty() {
+            self.cells.remove(&(col, row));
+        } else {
+            self.cells.insert((col, row), text);
+        }
+    }
+
+    /// Text of the 1-based cell, empty if it is not set
+    pub fn text(&self, col: u32, row: u32) -> &str {
+        self.cells.get(&(col, row)).map_or("", String::as_str)
+    }
+
+    /// Set the OCR confidence of the characters of the 1-based cell
+    pub fn set_confidence(&mut self, col: u32, row: u32, confidence: Confidence) {
+        self.confidence.insert((col, row), confidence);
+    }
+
+    /// OCR confidence of the characters of the 1-based cell, empty if the
+    /// sheet does not come from OCR output
+    pub fn confidence(&self, col: u32, row: u32) -> &Confidence {
+        static NONE: Confidence = Confidence(Vec::new());
+        self.confidence.get(&(col, row)).unwrap_or(&NONE)
+    }
+
+    /// Mark `range` as merged; its text is the one of its top left cell
+    pub fn add_merged(&mut self, range: CellRange) {
+        self.merged.push(range);
+    }
+
+    pub fn highest_column(&self) -> u32 {
+        self.max_col
+    }
+
+    pub fn highest_row(&self) -> u32 {
+        self.max_row
+    }
+
+    pub(crate) fn merged(&self) -> &MergedCells {
+        &self.merged
+    }
+}
+
+/// A workbook the parser can read sheets from
+pub trait SpreadsheetSource {
+    /// Names of the worksheets, in workbook order
+    fn sheet_names(&self) -> Vec<String>;
+
+    /// Read the worksheet at the 0-based `index`
+    fn read_sheet(&mut self, index: usize) -> Result<Sheet, KimiError>;
+}
+
+/// Workbook loaded with umya-spreadsheet
+pub struct UmyaSource {
+    book: Spreadsheet,
+    inline: Vec<InlineCells>,
+}
+
+impl UmyaSource {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, KimiError> {
+        let path = path.as_ref();
+        let inline = read_inline_strings(File::open(path)?)?;
+        let book = xlsx::read(path)?;
+        Ok(UmyaSource { book, inline })
+    }
+
+    pub fn from_reader<R: Read + Seek>(mut reader: R) -> Result<Self, KimiError> {
+        let inline = read_inline_strings(&mut reader)?;
+        reader.rewind()?;
+        let book = xlsx::read_reader(reader, true)?;
+        Ok(UmyaSource { book, inline })
+    }
+}
+
+impl SpreadsheetSource for UmyaSource {
+    fn sheet_names(&self) -> Vec<String> {
+        self.book
+            .get_sheet_collection()
+            .iter()
+            .map(|sheet| sheet.get_name().to_string())
+            .collect()
+    }
+
+    fn read_sheet(&mut self, index: usize) -> Result<Sheet, KimiError> {
+        let worksheet = self
+            .book
+            .get_sheet(&index)
+            .ok_or(KimiError::SheetNotFound(index))?;
+        let mut sheet = Sheet::new(worksheet.get_name());
+        for cell in worksheet.get_cell_collection() {
+            let coordinate = cell.get_coordinate();
+            let (col, row) = (*coordinate.get_col_num(), *coordinate.get_row_num());
+            sheet.set_text(col, row, cell.get_value());
+        }
+        // umya keeps only the last run of an inline rich string
+        if let Some(inline) = self.inline.get(index) {
+            for (&(col, row), text) in inline {
+                sheet.set_text(col, row, text.as_str());
+            }
+        }
+        sheet.max_col = sheet.max_col.max(worksheet.get_highest_column());
+        sheet.max_row = sheet.max_row.max(worksheet.get_highest_row());
+        for range in worksheet.get_merge_cells() {
+            if let Ok(range) = range.get_range().parse() {
+                sheet.add_merged(range);
+            }
+        }
+        Ok(sheet)
+    }
+}
+
+/// Workbook read with calamine, which ignores styles
+#[cfg(feature = "calamine")]
+pub struct CalamineSource<RS: Read + Seek> {
+    workbook: calamine::Xlsx<RS>,
+}
+
+#[cfg(feature = "calamine")]
+impl CalamineSource<std::io::BufReader<File>> {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, KimiError> {
+        Ok(CalamineSource {
+            workbook: calamine::open_workbook(path)?,
+        })
+    }
+}
+
+#[cfg(feature = "calamine")]
+impl<RS: Read + Seek> CalamineSource<RS> {
+    pub fn from_reader(reader: RS) -> Result<Self, KimiError> {
+        use calamine::Reader;
+        Ok(CalamineSource {
+            workbook: calamine::Xlsx::new(reader)?,
+        })
+    }
+}
+
+#[cfg(feature = "calamine")]
+impl<RS: Read + Seek> SpreadsheetSource for CalamineSource<RS> {
+    fn sheet_names(&self) -> Vec<String> {
+        use calamine::Reader;
+        self.workbook.sheet_names()
+    }
+
+    fn read_sheet(&mut self, index: usize) -> Result<Sheet, KimiError> {
+        use calamine::Reader;
+        let name = self
+            .sheet_names()
+            .into_iter()
+            .nth(index)
+            .ok_or(KimiError::SheetNotFound(index))?;
+        let range = self.workbook.worksheet_range(&name)?;
+        let merged = self.workbook.merge_cells_by_sheet_name(&name)?;
+        Ok(calamine_sheet(&name, &range, &merged))
+    }
+}
+
+/// Legacy `.xls` workbook, read with calamine
+#[cfg(feature = "xls")]
+pub struct XlsSource<RS: Read + Seek> {
+    workbook: calamine::Xls<RS>,
+}
+
+#[cfg(feature = "xls")]
+impl XlsSource<std::io::BufReader<File>> {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, KimiError> {
+        Ok(XlsSource {
+            workbook: calamine::open_workbook(path)?,
+        })
+    }
+}
+
+#[cfg(feature = "xls")]
+impl<RS: Read + Seek> XlsSource<RS> {
+    pub fn from_reader(reader: RS) -> Result<Self, KimiError> {
+        use calamine::Reader;
+        Ok(XlsSource {
+            workbook: calamine::Xls::new(reader)?,
+        })
+    }
+}
+
+#[cfg(feature = "xls")]
+impl<RS: Read + Seek> SpreadsheetSource for XlsSource<RS> {
+    fn sheet_names(&self) -> Vec<String> {
+        use calamine::Reader;
+        self.workbook.sheet_names()
+    }
+
+    fn read_sheet(&mut self, index: usize) -> Result<Sheet, KimiError> {
+        use calamine::Reader;
+        let name = self
+            .sheet_names()
+            .into_iter()
+            .nth(index)
+            .ok_or(KimiError::SheetNotFound(index))?;
+        let range = self.workbook.worksheet_range(&name)?;
+        let merged = self.workbook.merge_cells_by_sheet_name(&name)?;
+        Ok(calamine_sheet(&name, &range, &merged))
+    }
+}
+
+#[cfg(feature = "calamine")]
+fn calamine_sheet(
+    name: &str,
+    range: &calamine::Range<calamine::Data>,
+    merged: &[calamine::Dimensions],
+) -> Sheet {
+    let mut sheet = Sheet::new(name);
+    // calamine positions are 0-based (row, column), relative to the start
+    // of the used range
+    if let Some((first_row, first_col)) = range.start() {
+        for (row, col, data) in range.used_cells() {
+            let (col, row) = (first_col + col as u32 + 1, first_row + row as u32 + 1);
+            sheet.set_text(col, row, data.to_string());
+        }
+    }
+    for dimensions in merged {
+        let (first, last) = (dimensions.start, dimensions.end);
+        sheet.add_merged(CellRange {
+            first: (first.1 + 1, first.0 + 1),
+            last: (last.1 + 1, last.0 + 1),
+        });
+    }
+    sheet
+}
+
+/// OpenDocument spreadsheet, e.g. a scan converted with LibreOffice
+#[cfg(feature = "ods")]
+pub struct OdsSource {
+    book: spreadsheet_ods::WorkBook,
+}
+
+#[cfg(feature = "ods")]
+impl OdsSource {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, KimiError> {
+        Self::from_reader(std::io::BufReader::new(File::open(path)?))
+    }
+
+    pub fn from_reader<R: Read + Seek>(reader: R) -> Result<Self, KimiError> {
+        Ok(OdsSource {
+            book: spreadsheet_ods::OdsOptions::default()
+                .content_only()
+                .read_ods(reader)?,
+        })
+    }
+}
+
+#[cfg(feature = "ods")]
+impl SpreadsheetSource for OdsSource {
+    fn sheet_names(&self) -> Vec<String> {
+        self.book
+            .iter_sheets()
+            .map(|sheet| sheet.name().clone())
+            .collect()
+    }
+
+    fn read_sheet(&mut self, index: usize) -> Result<Sheet, KimiError> {
+        use spreadsheet_ods::Value;
+        if index >= self.book.num_sheets() {
+            return Err(KimiError::SheetNotFound(index));
+        }
+        let ods = self.book.sheet(index);
+        let mut sheet = Sheet::new(ods.name().as_str());
+        // spreadsheet-ods positions are 0-based (row, column)
+        for ((row, col), cell) in ods.iter() {
+            let (col, row) = (col + 1, row + 1);
+            let text = match cell.value {
+                Value::Number(n) => n.to_string(),
+                // paragraphs of a cell are joined with line breaks
+                value => value.as_cow_str_or("").into_owned(),
+            };
+            sheet.set_text(col, row, text);
+            let (rows, cols) = (cell.span.row_span(), cell.span.col_span());
+            if rows > 1 || cols > 1 {
+                sheet.add_merged(CellRange {
+                    first: (col, row),
+                    last: (col + cols - 1, row + rows - 1),
+                });
+            }
+        }
+        Ok(sheet)
+    }
+}
+
+/// Open `path` with the reader its extension calls for, or with `backend`
+/// for xlsx workbooks
+pub(crate) fn open_source(
+    path: &Path,
+    backend: Backend,
+) -> Result<Box<dyn SpreadsheetSource>, KimiError> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        #[cfg(feature = "xls")]
+        Some("xls") => Ok(Box::new(XlsSource::open(path)?)),
+        #[cfg(feature = "ods")]
+        Some("ods") => Ok(Box::new(OdsSource::open(path)?)),
+        #[cfg(feature = "html")]
+        Some("html" | "htm") => Ok(Box::new(crate::HtmlSource::open(path)?)),
+        #[cfg(feature = "docx")]
+        Some("docx") => Ok(Box::new(crate::DocxSource::open(path)?)),
+        #[cfg(feature = "pdf")]
+        Some("pdf") => Ok(Box::new(crate::PdfSource::open(path)?)),
+        #[cfg(feature = "ocr")]
+        Some("hocr" | "xml") => Ok(Box::new(crate::OcrSource::open(path)?)),
+        _ => backend.open(path),
+    }
+}
+
+/// Read the file `name` of an archive with the reader its extension calls
+/// for; `None` if the extension is not one of a workbook or document
+pub(crate) fn read_source(
+    name: &str,
+    bytes: Vec<u8>,
+    backend: Backend,
+) -> Result<Option<Box<dyn SpreadsheetSource>>, KimiError> {
+    let extension = Path::new(name)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+    let reader = std::io::Cursor::new(bytes);
+    Ok(Some(match extension.as_deref() {
+        Some("xlsx" | "xlsm") => backend.from_reader(reader)?,
+        #[cfg(feature = "xls")]
+        Some("xls") => Box::new(XlsSource::from_reader(reader)?),
+        #[cfg(feature = "ods")]
+        Some("ods") => Box::new(OdsSource::from_reader(reader)?),
+        #[cfg(feature = "html")]
+        Some("html" | "htm") => Box::new(crate::HtmlSource::parse(&utf8(reader)?)),
+        #[cfg(feature = "docx")]
+        Some("docx") => Box::new(crate::DocxSource::from_reader(reader)?),
+        #[cfg(feature = "pdf")]
+        Some("pdf") => Box::new(crate::PdfSource::from_bytes(reader.get_ref())?),
+        #[cfg(feature = "ocr")]
+        Some("hocr" | "xml") => Box::new(crate::OcrSource::parse(&utf8(reader)?)?),
+        _ => return Ok(None),
+    }))
+}
+
+#[cfg(any(feature = "html", feature = "ocr"))]
+fn utf8(reader: std::io::Cursor<Vec<u8>>) -> Result<String, KimiError> {
+    String::from_utf8(reader.into_inner())
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e).into())
+}
+
+/// Library used to read xlsx workbooks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Backend {
+    #[default]
+    Umya,
+    #[cfg(feature = "calamine")]
+    Calamine,
+}
+
+impl Backend {
+    /// Open the workbook at `path`
+    pub fn open(self, path: impl AsRef<Path>) -> Result<Box<dyn SpreadsheetSource>, KimiError> {
+        Ok(match self {
+            Backend::Umya => Box::new(UmyaSource::open(path)?),
+            #[cfg(feature = "calamine")]
+            Backend::Calamine => Box::new(CalamineSource::open(path)?),
+        })
+    }
+
+    /// Read the workbook bytes from `reader`
+    pub fn from_reader<'a, R: Read + Seek + 'a>(
+        self,
+        reader: R,
+    ) -> Result<Box<dyn SpreadsheetSource + 'a>, KimiError> {
+        Ok(match self {
+            Backend::Umya => Box::new(UmyaSource::from_reader(reader)?),
+            #[cfg(feature = "calamine")]
+            Backend::Calamine => Box::new(CalamineSource::from_reader(reader)?),
+        })
+    }
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    /// `umya`, and `calamine` when its feature is enabled
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "umya" => Ok(Backend::Umya),
+            #[cfg(feature = "calamine")]
+            "calamine" => Ok(Backend::Calamine),
+            _ => Err(format!(
+                "unsupported backend {:?} (calamine needs its cargo feature)",
+                s
+            )),
+        }
+    }
+}
+
+impl TryFrom<String> for Backend {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Backend> for String {
+    fn from(backend: Backend) -> Self {
+        backend.to_string()
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Backend::Umya => "umya",
+            #[cfg(feature = "calamine")]
+            Backend::Calamine => "calamine",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn workbook() -> Vec<u8> {
+        let mut book = umya_spreadsheet::new_file();
+        let sheet = book.get_sheet_mut(&0).unwrap();
+        sheet
+            .get_cell_mut((1, 2))
+            .set_value("1-01(GBM10100)机关负责人");
+        sheet.get_cell_mut((3, 4)).set_value("12");
+        sheet.add_merge_cells("E2:F3");
+        let mut bytes = Vec::new();
+        umya_spreadsheet::writer::xlsx::write_writer(&book, &mut bytes).unwrap();
+        bytes
+    }
+
+    /// The cells of `workbook` as an OpenDocument spreadsheet
+    #[cfg(feature = "ods")]
+    fn ods_workbook() -> Vec<u8> {
+        let mut sheet = spreadsheet_ods::Sheet::new("Sheet1");
+        sheet.set_value(1, 0, "1-01(GBM10100)机关负责人");
+        sheet.set_value(3, 2, "12");
+        sheet.set_row_span(1, 4, 2);
+        sheet.set_col_span(1, 4, 2);
+        let mut book = spreadsheet_ods::WorkBook::new_empty();
+        book.push_sheet(sheet);
+        spreadsheet_ods::write_ods_buf(&mut book, Vec::new()).unwrap()
+    }
+
+    fn check(source: &mut dyn SpreadsheetSource) {
+        assert_eq!(source.sheet_names(), ["Sheet1"]);
+        let sheet = source.read_sheet(0).unwrap();
+        assert_eq!(sheet.name(), "Sheet1");
+        assert_eq!(sheet.text(1, 2), "1-01(GBM10100)机关负责人");
+        assert_eq!(sheet.text(3, 4), "12");
+        assert_eq!(sheet.text(2, 2), "");
+        assert!(sheet.highest_column() >= 3 && sheet.highest_row() >= 4);
+        assert_eq!(sheet.merged().rows(6, 3), (2, 3));
+        assert!(matches!(
+            source.read_sheet(1),
+            Err(KimiError::SheetNotFound(1))
+        ));
+    }
+
+    #[test]
+    fn test_sources() {
+        let bytes = workbook();
+        check(&mut *Backend::Umya.from_reader(Cursor::new(&bytes)).unwrap());
+        #[cfg(feature = "calamine")]
+        check(&mut *Backend::Calamine.from_reader(Cursor::new(&bytes)).unwrap());
+        #[cfg(feature = "ods")]
+        {
+            check(&mut OdsSource::from_reader(Cursor::new(ods_workbook())).unwrap());
+            // the cells of `workbook` saved the way LibreOffice does, with
+            // repeated and covered cells, and two paragraphs in G5
+            let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/workbook.ods");
+            let mut source = open_source(&path, Backend::Umya).unwrap();
+            check(&mut *source);
+            let sheet = source.read_sheet(0).unwrap();
+            assert_eq!(sheet.text(7, 5), "2-04-02-01\n2-04-02-02");
+            assert_eq!(sheet.text(6, 3), "");
+        }
+        #[cfg(feature = "xls")]
+        {
+            // the cells of `workbook`, saved as BIFF8
+            let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/workbook.xls");
+            check(&mut *open_source(&path, Backend::Umya).unwrap());
+            assert!(matches!(
+                XlsSource::from_reader(Cursor::new(&bytes)),
+                Err(KimiError::Xls(_))
+            ));
+        }
+        assert_eq!("umya".parse(), Ok(Backend::Umya));
+        assert!("excel".parse::<Backend>().is_err());
+    }
+}
@@ -0,0 +1,167 @@
+use crate::{CategoryTree, Level, Tree};
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// Category counts broken down by level
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct LevelCounts {
+    pub major: usize,
+    pub middle: usize,
+    pub minor: usize,
+    pub detail: usize,
+}
+
+impl LevelCounts {
+    pub fn get(&self, level: Level) -> usize {
+        match level {
+            Level::Major => self.major,
+            Level::Middle => self.middle,
+            Level::Minor => self.minor,
+            Level::Detail => self.detail,
+        }
+    }
+
+    fn bump(&mut self, level: Level) {
+        match level {
+            Level::Major => self.major += 1,
+            Level::Middle => self.middle += 1,
+            Level::Minor => self.minor += 1,
+            Level::Detail => self.detail += 1,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.major + self.middle + self.minor + self.detail
+    }
+}
+
+/// Summary of a parsed tree
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TreeStats {
+    /// Categories per level (大类/中类/小类/细类)
+    pub levels: LevelCounts,
+    /// Categories whose id has more than four segments
+    pub unknown_level: usize,
+    /// Categories in nodes without children
+    pub leaves: usize,
+    /// Categories without a GBM code, per level
+    pub missing_code: LevelCounts,
+    /// Largest number of id segments of any category
+    pub max_depth: usize,
+    /// Ids held by more than one category, in tree order
+    pub duplicate_ids: Vec<String>,
+}
+
+impl CategoryTree {
+    /// Counts per level, leaves, missing codes, duplicate ids and depth of
+    /// the tree
+    pub fn stats(&self) -> TreeStats {
+        let mut stats = TreeStats {
+            leaves: self.leaves().count(),
+            ..TreeStats::default()
+        };
+        let mut seen = HashSet::new();
+        for (path, cat) in self {
+            stats.max_depth = stats.max_depth.max(path.len());
+            if !seen.insert(&cat.id) && !stats.duplicate_ids.contains(&cat.id) {
+                stats.duplicate_ids.push(cat.id.clone());
+            }
+            match cat.level() {
+                Some(level) => {
+                    stats.levels.bump(level);
+                    if cat.code.is_none() {
+                        stats.missing_code.bump(level);
+                    }
+                }
+                None => stats.unknown_level += 1,
+            }
+        }
+        stats
+    }
+}
+
+impl<T> Tree<T> {
+    /// Depth of the deepest node below the root (0 for an empty tree)
+    pub fn depth(&self) -> usize {
+        self.children
+            .values()
+            .map(|child| child.depth() + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Number of nodes at the given level, e.g. 8 for `Level::Major`
+    pub fn width_at(&self, level: Level) -> usize {
+        self.width_at_depth(level.depth())
+    }
+
+    fn width_at_depth(&self, depth: usize) -> usize {
+        match depth {
+            0 => 1,
+            1 => self.children.len(),
+            _ => self
+                .children
+                .values()
+                .map(|child| child.width_at_depth(depth - 1))
+                .sum(),
+        }
+    }
+
+    /// Number of nodes below the root, with or without categories
+    pub fn node_count(&self) -> usize {
+        self.children
+            .values()
+            .map(|child| child.node_count() + 1)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats() {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column(
+            "1(GBM10)负责人\n1-01(GBM10100)机关负责人\n1-01-00(GBM10100)机关负责人\n1-01-00-01中国共产党机关负责人\n1-01-00-02中国共产党基层组织负责人\n1-02 国家机关负责人\n1-01-00-02中国共产党基层组织负责人",
+        )
+        .unwrap();
+        let stats = tree.stats();
+        assert_eq!(
+            stats.levels,
+            LevelCounts {
+                major: 1,
+                middle: 2,
+                minor: 1,
+                detail: 3
+            }
+        );
+        assert_eq!(stats.levels.total(), 7);
+        assert_eq!(stats.leaves, 4);
+        assert_eq!(stats.missing_code.get(Level::Middle), 1);
+        assert_eq!(stats.missing_code.get(Level::Detail), 3);
+        assert_eq!(stats.duplicate_ids, vec!["1-01-00-02"]);
+        assert_eq!(stats.max_depth, 4);
+        assert_eq!(stats.unknown_level, 0);
+    }
+
+    #[test]
+    fn test_shape_metrics() {
+        let mut tree = CategoryTree::new();
+        assert_eq!(tree.depth(), 0);
+        assert_eq!(tree.node_count(), 0);
+
+        tree.parse_one_column(
+            "1-01(GBM10100)机关负责人\n1-01-00-01中国共产党机关负责人\n1-02(GBM10200)国家机关负责人\n2-01(GBM20100)科学研究人员",
+        )
+        .unwrap();
+        assert_eq!(tree.depth(), 4);
+        assert_eq!(tree.width_at(Level::Major), 2);
+        assert_eq!(tree.width_at(Level::Middle), 3);
+        assert_eq!(tree.width_at(Level::Minor), 1);
+        assert_eq!(tree.width_at(Level::Detail), 1);
+        // 1, 1-01, 1-01-00, 1-01-00-01, 1-02, 2, 2-01
+        assert_eq!(tree.node_count(), 7);
+    }
+}
@@ -0,0 +1,92 @@
+use crate::{Category, CategoryTree, Tree};
+
+impl<T> Tree<T> {
+    /// Tree of the same shape with every payload passed through `f`
+    pub fn map<U, F: FnMut(&T) -> U>(&self, mut f: F) -> Tree<U> {
+        self.map_inner(&mut f)
+    }
+
+    fn map_inner<U, F: FnMut(&T) -> U>(&self, f: &mut F) -> Tree<U> {
+        Tree {
+            children: self
+                .children
+                .iter()
+                .map(|(key, child)| (key.clone(), child.map_inner(f)))
+                .collect(),
+            items: self.items.iter().map(&mut *f).collect(),
+        }
+    }
+
+    /// Tree with only the payloads for which `f` returns true
+    ///
+    /// Branches left without any payload are dropped.
+    pub fn filter<F: FnMut(&T) -> bool>(&self, mut f: F) -> Tree<T>
+    where
+        T: Clone,
+    {
+        self.filter_inner(&mut f)
+    }
+
+    fn filter_inner<F: FnMut(&T) -> bool>(&self, f: &mut F) -> Tree<T>
+    where
+        T: Clone,
+    {
+        Tree {
+            items: self.items.iter().filter(|c| f(c)).cloned().collect(),
+            children: self
+                .children
+                .iter()
+                .map(|(key, child)| (key.clone(), child.filter_inner(f)))
+                .filter(|(_, child)| !child.items.is_empty() || !child.children.is_empty())
+                .collect(),
+        }
+    }
+}
+
+impl CategoryTree {
+    /// Tree of the same shape with every category passed through `f`
+    pub fn map_categories<F: FnMut(&Category) -> Category>(&self, f: F) -> CategoryTree {
+        self.map(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> CategoryTree {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column(
+            "2-05(GBM20500)卫生专业技术人员\n2-05-01(GBM20501)临床和口腔医师\n2-05-01-01内科医师\n2-05-08(GBM20508)护理人员\n2-05-08-01内科护士\n2-06(GBM20600)经济和金融专业人员",
+        )
+        .unwrap();
+        tree
+    }
+
+    #[test]
+    fn test_map_categories() {
+        let tree = tree();
+        let mapped = tree.map_categories(|c| Category {
+            desc: c.desc.as_ref().map(|d| format!("[{}]", d)),
+            ..c.clone()
+        });
+        assert_eq!(mapped.node_count(), tree.node_count());
+        assert_eq!(
+            mapped.get("2-05-01-01").unwrap().desc.as_deref(),
+            Some("[内科医师]")
+        );
+    }
+
+    #[test]
+    fn test_filter() {
+        let tree = tree();
+        let nursing = tree.filter(|c| c.desc.as_deref().is_some_and(|d| d.contains("护")));
+        let ids = nursing
+            .iter()
+            .map(|(_, c)| c.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["2-05-08", "2-05-08-01"]);
+        assert!(nursing.get_node("2-06").is_none());
+        assert!(nursing.get_node("2-05-01").is_none());
+    }
+}
@@ -0,0 +1,192 @@
+use crate::Category;
+use crate::iter::{BfsIter, Iter, Leaves};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+/// Hierarchical tree keyed by path segments ("2", "02", "38", …), holding
+/// payloads of type `T` at any node
+///
+/// `Category` is the default payload, see `CategoryTree`. Other record types
+/// (salary data, headcounts) can be attached to the same hierarchy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tree<T = Category> {
+    pub(crate) children: IndexMap<String, Tree<T>>,
+    #[serde(rename = "categories")]
+    pub(crate) items: Vec<T>,
+}
+
+// Not derived: IndexMap equality ignores order, `==` on trees does not
+impl<T: PartialEq> PartialEq for Tree<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.items == other.items && self.children.iter().eq(other.children.iter())
+    }
+}
+
+impl<T: Eq> Eq for Tree<T> {}
+
+impl<T> Default for Tree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Tree<T> {
+    pub fn new() -> Self {
+        Self {
+            children: IndexMap::new(),
+            items: Vec::new(),
+        }
+    }
+
+    /// Insert `item` at a dash-separated id such as "1-01-00-01"
+    pub fn insert(&mut self, id: impl AsRef<str>, item: T) {
+        self.insert_at(id.as_ref().split('-'), item);
+    }
+
+    /// Insert `item` at the node reached by following `path`
+    pub fn insert_at<I, S>(&mut self, path: I, item: T)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut node = self;
+        for level in path {
+            node = node.children.entry(level.into()).or_default();
+        }
+        node.items.push(item);
+    }
+
+    /// Payloads stored at this node
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+
+    /// Child nodes keyed by path segment, in insertion order
+    pub fn children(&self) -> impl Iterator<Item = (&str, &Tree<T>)> {
+        self.children
+            .iter()
+            .map(|(key, child)| (key.as_str(), child))
+    }
+
+    /// Depth-first iterator over all payloads with their path segments
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter::new(self)
+    }
+
+    /// Breadth-first iterator over all payloads, level by level
+    pub fn iter_bfs(&self) -> BfsIter<'_, T> {
+        BfsIter::new(self)
+    }
+
+    /// Iterator over payloads of leaf (细类) nodes with their ancestry
+    pub fn leaves(&self) -> Leaves<'_, T> {
+        Leaves::new(self)
+    }
+
+    /// Find the node for a dash-separated id, e.g. "2-02-38"
+    pub fn get_node(&self, id: &str) -> Option<&Tree<T>> {
+        let mut node = self;
+        for level in id.split('-') {
+            node = node.children.get(level)?;
+        }
+        Some(node)
+    }
+
+    /// Payloads above `id`, ordered from 大类 down to the direct parent
+    ///
+    /// Levels missing from the tree are skipped.
+    pub fn ancestors(&self, id: &str) -> Vec<&T> {
+        let mut chain = Vec::new();
+        let mut node = self;
+        let segments = id.split('-').collect::<Vec<&str>>();
+        for level in &segments[..segments.len() - 1] {
+            match node.children.get(*level) {
+                Some(child) => node = child,
+                None => break,
+            }
+            chain.extend(node.items.first());
+        }
+        chain
+    }
+
+    /// Payloads of the other nodes under the same parent as `id`, in order
+    pub fn siblings(&self, id: &str) -> Vec<&T> {
+        let (parent, last) = match id.rsplit_once('-') {
+            Some((parent, last)) => (self.get_node(parent), last),
+            None => (Some(self), id),
+        };
+        parent.map_or(Vec::new(), |parent| {
+            parent
+                .children
+                .iter()
+                .filter(|(key, _)| key.as_str() != last)
+                .filter_map(|(_, child)| child.items.first())
+                .collect()
+        })
+    }
+
+    /// Structural equality ignoring the order of children and payloads
+    pub fn eq_ignore_order(&self, other: &Tree<T>) -> bool
+    where
+        T: PartialEq,
+    {
+        let count = |items: &[T], item: &T| items.iter().filter(|i| *i == item).count();
+        self.items.len() == other.items.len()
+            && self
+                .items
+                .iter()
+                .all(|i| count(&self.items, i) == count(&other.items, i))
+            && self.children.len() == other.children.len()
+            && self.children.iter().all(|(key, child)| {
+                other
+                    .children
+                    .get(key)
+                    .is_some_and(|o| child.eq_ignore_order(o))
+            })
+    }
+
+    /// Clone everything under `prefix`, e.g. "2-02"
+    ///
+    /// The result keeps the path from the root down to `prefix`, so ids and
+    /// lookups stay valid, but ancestor payloads are left out.
+    pub fn subtree(&self, prefix: &str) -> Option<Tree<T>>
+    where
+        T: Clone,
+    {
+        let mut subtree = self.get_node(prefix)?.clone();
+        for level in prefix.split('-').rev() {
+            let mut parent = Tree::new();
+            parent.children.insert(level.to_string(), subtree);
+            subtree = parent;
+        }
+        Some(subtree)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_custom_payload() {
+        let mut headcount: Tree<u32> = Tree::new();
+        headcount.insert("2-02-01-01", 120);
+        headcount.insert("2-02-01-02", 80);
+        headcount.insert_at(["2", "02"], 200);
+
+        assert_eq!(headcount.get_node("2-02").unwrap().items(), &[200]);
+        assert_eq!(headcount.ancestors("2-02-01-02"), vec![&200]);
+        assert_eq!(headcount.siblings("2-02-01-02"), vec![&120]);
+        assert!(headcount.siblings("3-01").is_empty());
+        assert_eq!(headcount.iter().map(|(_, n)| n).sum::<u32>(), 400);
+        assert_eq!(headcount.leaves().count(), 2);
+
+        let json = serde_json::to_value(&headcount).unwrap();
+        assert_eq!(
+            json["children"]["2"]["children"]["02"]["categories"][0],
+            200
+        );
+        let back: Tree<u32> = serde_json::from_value(json).unwrap();
+        assert_eq!(back, headcount);
+    }
+}
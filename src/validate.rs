@@ -0,0 +1,165 @@
+use crate::id::CategoryId;
+use crate::{Category, CategoryTree, IdFormat, KimiError, Level};
+use regex::Regex;
+use serde::Serialize;
+use std::fmt;
+
+/// Structural problem found by `CategoryTree::validate`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Problem {
+    /// The category one level up, e.g. the 小类 of a 细类, is missing
+    MissingParent { id: String, parent: String },
+    /// The digits of the GBM code do not match the id, e.g. GBM20300 on
+    /// 2-02
+    CodeMismatch { id: String, code: String },
+    /// The category has no description
+    EmptyDesc { id: String },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::MissingParent { id, parent } => {
+                write!(f, "{}: parent {} not found", id, parent)
+            }
+            Problem::CodeMismatch { id, code } => {
+                write!(f, "{}: GBM code {} does not match the id", id, code)
+            }
+            Problem::EmptyDesc { id } => write!(f, "{}: empty description", id),
+        }
+    }
+}
+
+impl CategoryTree {
+    /// Check that every category has its parent category, a GBM code
+    /// consistent with its id and a description, in tree order
+    pub fn validate(&self) -> Vec<Problem> {
+        self.validate_with(&IdFormat::default())
+            .expect("the default id format is valid")
+    }
+
+    /// Like `validate`, for codes starting with the prefix of `format`
+    pub fn validate_with(&self, format: &IdFormat) -> Result<Vec<Problem>, KimiError> {
+        let prefix = format.code_prefix_regex()?;
+        let mut problems = Vec::new();
+        for (_, cat) in self {
+            if let Some(parent) = CategoryId::from(cat.id.as_str()).parent_id()
+                && self.get(&parent).is_none()
+            {
+                problems.push(Problem::MissingParent {
+                    id: cat.id.clone(),
+                    parent,
+                });
+            }
+            if let Some(code) = &cat.code
+                && !code_matches(cat, code, &prefix)
+            {
+                problems.push(Problem::CodeMismatch {
+                    id: cat.id.clone(),
+                    code: code.clone(),
+                });
+            }
+            if cat.desc.as_deref().is_none_or(|d| d.trim().is_empty()) {
+                problems.push(Problem::EmptyDesc { id: cat.id.clone() });
+            }
+        }
+        Ok(problems)
+    }
+}
+
+/// GBM codes spell out 大类, 中类 and 小类 with zeros for the levels below,
+/// e.g. 2-02-02 → GBM20202 and 2-02 → GBM20200; trailing zeros may be cut
+/// off, as in GBM10 for 1; `prefix` matches the GBM of another `IdFormat`
+fn code_matches(cat: &Category, code: &str, prefix: &Regex) -> bool {
+    let code = code.trim();
+    let Some(digits) = prefix.find(code).map(|m| &code[m.end()..]) else {
+        return false;
+    };
+    let segments = cat.id.split('-').collect::<Vec<_>>();
+    let mut expected = segments[0].to_string();
+    for i in 1..Level::Minor.depth() {
+        expected.push_str(segments.get(i).copied().unwrap_or("00"));
+    }
+    !digits.is_empty()
+        && expected
+            .strip_prefix(digits)
+            .is_some_and(|rest| rest.chars().all(|c| c == '0'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column(
+            "1(GBM10)负责人\n1-01(GBM10100)机关负责人\n1-01-00(GBM10100)机关负责人\n1-01-00-01中国共产党机关负责人\n1-02(GBM10300)国家机关负责人\n2-01-01-01科学研究人员",
+        )
+        .unwrap();
+        tree.insert(
+            "1-01-00-02",
+            Category {
+                id: "1-01-00-02".to_string(),
+                code: None,
+                desc: Some(" ".to_string()),
+                is_green: false,
+                is_digital: false,
+                confidence: None,
+                source: None,
+            },
+        );
+        assert_eq!(
+            tree.validate(),
+            vec![
+                Problem::EmptyDesc {
+                    id: "1-01-00-02".to_string()
+                },
+                Problem::CodeMismatch {
+                    id: "1-02".to_string(),
+                    code: "GBM10300".to_string()
+                },
+                Problem::MissingParent {
+                    id: "2-01-01-01".to_string(),
+                    parent: "2-01-01".to_string()
+                },
+            ]
+        );
+        assert_eq!(
+            tree.validate()[1].to_string(),
+            "1-02: GBM code GBM10300 does not match the id"
+        );
+    }
+
+    #[test]
+    fn test_code_prefix() {
+        let mut tree = CategoryTree::new();
+        tree.parse_one_column("2(GBM20000)专业技术人员\n2-01(GBM20100)科学研究人员")
+            .unwrap();
+        let with_code = |code: &str| {
+            let mut tree = tree.clone();
+            tree.children["2"].children["01"].items[0].code = Some(code.to_string());
+            tree
+        };
+        for code in ["XYZ20100", "G8M20100", "NOC20100"] {
+            let mismatch = Problem::CodeMismatch {
+                id: "2-01".to_string(),
+                code: code.to_string(),
+            };
+            assert_eq!(with_code(code).validate(), [mismatch]);
+        }
+
+        let tree = with_code("NOC20100");
+        let noc = IdFormat {
+            code_prefix: "GBM|NOC".to_string(),
+            ..IdFormat::default()
+        };
+        assert_eq!(tree.validate_with(&noc).unwrap(), []);
+        let bad = IdFormat {
+            code_prefix: "(".to_string(),
+            ..IdFormat::default()
+        };
+        assert!(matches!(tree.validate_with(&bad), Err(KimiError::Regex(_))));
+    }
+}